@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::json::raw_path_bytes;
+use crate::types::StaleProject;
+
+/// Versão do arquivo de `--save-baseline`.
+const BASELINE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "StoredProject", from = "StoredProject")]
+pub struct BaselineProject {
+    /// Caminho canônico do projeto: chave da comparação.
+    pub path: PathBuf,
+    pub size: u64,
+    /// Segundos desde a época Unix.
+    pub last_modified: u64,
+    pub dep_dirs: Vec<PathBuf>,
+}
+
+/// `BaselineProject` como fica no arquivo. Caminhos fora de UTF-8 seguem o
+/// formato de `--format json`: `path` legível e, ao lado, `path_bytes` com os
+/// bytes exatos. Nas pastas de dependência, cada entrada é o texto ou, só
+/// para essas, o objeto `{ path, path_bytes }`.
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredProject {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path_bytes: Option<Vec<u8>>,
+    size: u64,
+    last_modified: u64,
+    dep_dirs: Vec<StoredPath>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredPath {
+    Text(String),
+    Bytes { path: String, path_bytes: Vec<u8> },
+}
+
+impl From<&Path> for StoredPath {
+    fn from(path: &Path) -> Self {
+        match raw_path_bytes(path) {
+            Some(path_bytes) => StoredPath::Bytes { path: path.to_string_lossy().into_owned(), path_bytes },
+            None => StoredPath::Text(path.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+impl From<StoredPath> for PathBuf {
+    fn from(stored: StoredPath) -> Self {
+        match stored {
+            StoredPath::Text(path) => PathBuf::from(path),
+            StoredPath::Bytes { path, path_bytes } => path_from_bytes(path, path_bytes),
+        }
+    }
+}
+
+/// Caminho gravado com `path_bytes`. Fora do Unix, fica o texto.
+#[cfg(unix)]
+fn path_from_bytes(_path: String, bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(path: String, _bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(path)
+}
+
+impl From<BaselineProject> for StoredProject {
+    fn from(project: BaselineProject) -> Self {
+        StoredProject {
+            path: project.path.to_string_lossy().into_owned(),
+            path_bytes: raw_path_bytes(&project.path),
+            size: project.size,
+            last_modified: project.last_modified,
+            dep_dirs: project.dep_dirs.iter().map(|d| StoredPath::from(d.as_path())).collect(),
+        }
+    }
+}
+
+impl From<StoredProject> for BaselineProject {
+    fn from(stored: StoredProject) -> Self {
+        let path = match stored.path_bytes {
+            Some(bytes) => path_from_bytes(stored.path, bytes),
+            None => PathBuf::from(stored.path),
+        };
+        BaselineProject {
+            path,
+            size: stored.size,
+            last_modified: stored.last_modified,
+            dep_dirs: stored.dep_dirs.into_iter().map(PathBuf::from).collect(),
+        }
+    }
+}
+
+/// Foto dos projetos inativos de uma varredura (`--save-baseline`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub projects: Vec<BaselineProject>,
+}
+
+impl Baseline {
+    pub fn from_projects(projects: &[StaleProject]) -> Self {
+        let projects = projects
+            .iter()
+            .map(|p| BaselineProject {
+                path: p.path.clone(),
+                size: p.total_size(),
+                last_modified: p
+                    .last_modified
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                dep_dirs: p.dep_dirs().iter().map(|d| d.path.clone()).collect(),
+            })
+            .collect();
+        Baseline { version: BASELINE_VERSION, projects }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Não foi possível gravar o baseline em '{}'", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Não foi possível ler o baseline '{}'", path.display()))?;
+        let baseline: Baseline = serde_json::from_str(&content)
+            .with_context(|| format!("Baseline inválido: '{}'", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            anyhow::bail!("Versão de baseline não suportada: {}", baseline.version);
+        }
+        Ok(baseline)
+    }
+}
+
+/// Diferença entre o baseline e a varredura atual (`--compare-baseline`).
+#[derive(Debug, Default)]
+pub struct BaselineDiff {
+    /// Inativos agora, ausentes no baseline.
+    pub newly_stale: Vec<BaselineProject>,
+    /// Estavam no baseline e ainda têm as pastas de dependência, mas não estão
+    /// mais inativos (voltaram a ser usados).
+    pub active_again: Vec<BaselineProject>,
+    /// Estavam no baseline e não têm mais as pastas (limpos ou apagados).
+    pub gone: Vec<BaselineProject>,
+    /// Presentes nos dois com tamanho diferente: (atual, tamanho no baseline).
+    pub resized: Vec<(BaselineProject, u64)>,
+}
+
+impl BaselineDiff {
+    pub fn is_empty(&self) -> bool {
+        self.newly_stale.is_empty() && self.active_again.is_empty() && self.gone.is_empty() && self.resized.is_empty()
+    }
+}
+
+pub fn compare(old: &Baseline, current: &Baseline) -> BaselineDiff {
+    let before: HashMap<&Path, &BaselineProject> =
+        old.projects.iter().map(|p| (p.path.as_path(), p)).collect();
+    let now: HashMap<&Path, &BaselineProject> =
+        current.projects.iter().map(|p| (p.path.as_path(), p)).collect();
+
+    let mut diff = BaselineDiff::default();
+
+    for project in &current.projects {
+        match before.get(project.path.as_path()) {
+            None => diff.newly_stale.push(project.clone()),
+            Some(prev) if prev.size != project.size => diff.resized.push((project.clone(), prev.size)),
+            Some(_) => {}
+        }
+    }
+
+    for project in &old.projects {
+        if now.contains_key(project.path.as_path()) {
+            continue;
+        }
+        if project.dep_dirs.iter().any(|d| d.exists()) {
+            diff.active_again.push(project.clone());
+        } else {
+            diff.gone.push(project.clone());
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DepDir, DepKind};
+    use std::time::SystemTime;
+
+    fn stale(path: &Path, size: u64) -> StaleProject {
+        StaleProject::new(
+            path.file_name().unwrap().to_string_lossy().to_string(),
+            path.to_path_buf(),
+            vec![DepDir { path: path.join("node_modules"), size, files: 0, kind: DepKind::NodeModules }],
+            SystemTime::now(),
+        )
+    }
+
+    #[test]
+    fn test_compare_categorizes_changes() {
+        let temp = std::env::temp_dir().join(format!("test_baseline_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        for name in ["api", "web", "old", "blog"] {
+            fs::create_dir_all(temp.join(name).join("node_modules")).unwrap();
+        }
+        let file = temp.join("baselines/week.json");
+
+        Baseline::from_projects(&[
+            stale(&temp.join("api"), 100),
+            stale(&temp.join("web"), 200),
+            stale(&temp.join("old"), 300),
+        ])
+        .save(&file)
+        .unwrap();
+
+        // Uma semana depois: "blog" ficou inativo, "api" cresceu, "web" voltou a
+        // ser usado e "old" foi limpo.
+        fs::remove_dir_all(temp.join("old/node_modules")).unwrap();
+        let current = Baseline::from_projects(&[stale(&temp.join("api"), 150), stale(&temp.join("blog"), 50)]);
+
+        let diff = compare(&Baseline::load(&file).unwrap(), &current);
+        let paths = |list: &[BaselineProject]| list.iter().map(|p| p.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&diff.newly_stale), vec![temp.join("blog")]);
+        assert_eq!(paths(&diff.active_again), vec![temp.join("web")]);
+        assert_eq!(paths(&diff.gone), vec![temp.join("old")]);
+        assert_eq!(diff.resized.len(), 1);
+        assert_eq!((diff.resized[0].0.size, diff.resized[0].1), (150, 100));
+
+        assert!(compare(&current, &current).is_empty());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = std::env::temp_dir().join(format!("test_baseline_bytes_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        fs::create_dir_all(&temp).unwrap();
+        let file = temp.join("baseline.json");
+
+        let weird = temp.join(std::ffi::OsStr::from_bytes(b"caf\xe9"));
+        let saved = Baseline::from_projects(&[stale(&weird, 10), stale(&temp.join("plain"), 20)]);
+        saved.save(&file).unwrap();
+
+        let content = fs::read_to_string(&file).unwrap();
+        assert!(content.contains("path_bytes"));
+        assert_eq!(Baseline::load(&file).unwrap().projects, saved.projects);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
@@ -1,95 +1,861 @@
-use anyhow::Error;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use indicatif::{ProgressBar, ProgressStyle};
+use clap::ValueEnum;
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use log::{debug, warn};
 
-use crate::types::StaleProject;
+use crate::display::icon;
+use crate::projects::{all_project_types, ProjectType};
+use crate::resume;
+use crate::staging;
+use crate::types::{DepDir, StaleProject};
+
+/// Em que ordem as pastas são removidas (`--clean-order`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CleanOrder {
+    /// Na ordem da listagem (padrão)
+    #[default]
+    Listed,
+    /// Maiores primeiro: libera espaço mais rápido em disco quase cheio
+    SizeDesc,
+    /// Menores primeiro
+    SizeAsc,
+    /// Projetos mais antigos primeiro
+    Age,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    pub dry_run: bool,
+    pub verbose: bool,
+    /// Quando a pasta de dependência é um symlink, apaga também o destino
+    /// (por padrão só o link é removido, preservando stores compartilhados).
+    pub follow_symlinks: bool,
+    /// Manifesto de progresso (`--resume`): cada pasta removida é registrada
+    /// nele, e o arquivo é apagado quando a limpeza termina sem erros.
+    pub progress_manifest: Option<PathBuf>,
+    /// Pula pastas com arquivos abertos por algum processo (ex: `cargo build`
+    /// rodando). Melhor esforço: sem suporte na plataforma, não pula nada.
+    pub protect_open: bool,
+    /// `--progress off`: usa uma barra oculta em vez de desenhar no terminal.
+    pub hide_progress: bool,
+    pub order: CleanOrder,
+    /// `--stage`: move as pastas para esta área em vez de apagar (ver `staging`).
+    /// Pastas que são symlinks continuam tendo só o link removido.
+    pub stage_dir: Option<PathBuf>,
+    /// `--move-to`: move as pastas para este diretório de arquivo em vez de
+    /// apagar. Os bytes vão para `CleanResult::total_moved`, não `total_freed`.
+    pub move_to: Option<PathBuf>,
+    /// `--check-writable` (só no dry-run): testa, sem apagar nada, se cada
+    /// pasta poderia ser removida (ver `probe_removable`).
+    pub check_writable: bool,
+    /// `--atomic-per-project`: quando uma pasta de um projeto falha, as
+    /// restantes do mesmo projeto são puladas (ver `CleanResult::partial`).
+    pub atomic_per_project: bool,
+    /// `--verify-markers`: logo antes de cada remoção, confere se a pasta
+    /// ainda passa na detecção do seu tipo (ver `marker_present`).
+    pub verify_markers: bool,
+    /// Velocidade de remoção (bytes/s) por id de tipo, aprendida em execuções
+    /// anteriores (ver `history::load_throughput`). Quando cobre todos os
+    /// tipos da limpeza, a barra mostra a previsão baseada nela.
+    pub delete_rates: HashMap<String, f64>,
+    /// `--remove-lockfiles`: depois de remover uma pasta, apaga também os
+    /// lockfiles do tipo dela no projeto (`ProjectType::lockfiles`).
+    pub remove_lockfiles: bool,
+    /// `--repeat`: passadas extras, após uma pausa curta, sobre as pastas que
+    /// falharam e ainda existem. Para antes se não sobrar nenhuma.
+    pub repeat: u32,
+    /// `--prune-empty-parents`: depois de remover uma pasta, apaga também os
+    /// diretórios acima dela que ficaram vazios, até a raiz do projeto
+    /// (exclusive), como o `Carthage/` de `Carthage/Build`.
+    pub prune_empty_parents: bool,
+    /// `--quiet` (e `--format json`/`--print0`): sem as linhas avulsas no
+    /// stdout (passadas de `--repeat`, `--verbose`), só o resultado final.
+    pub quiet: bool,
+}
 
 #[derive(Debug)]
 pub struct CleanResult {
     pub total_freed: u64,
+    /// Bytes movidos para o diretório de `--move-to` (continuam ocupando disco).
+    pub total_moved: u64,
     pub dirs_removed: usize,
-    pub errors: Vec<(PathBuf, Error)>,
+    /// Pastas que eram symlinks: só o link foi removido, o destino ficou intacto.
+    pub links_removed: Vec<PathBuf>,
+    /// Pastas puladas por `--protect-open` (em uso por outro processo).
+    pub skipped_open: Vec<PathBuf>,
+    pub errors: Vec<(PathBuf, CleanError)>,
+    /// Dry-run com `--check-writable`: pastas cuja remoção provavelmente
+    /// falharia. Não entram em `total_freed`.
+    pub would_fail: Vec<(PathBuf, CleanError)>,
+    /// `--atomic-per-project`: projetos com alguma pasta que falhou.
+    pub partial: Vec<PartialProject>,
+    /// Pastas puladas por `--verify-markers`: o marcador do projeto (ex: o
+    /// `package.json`) sumiu entre a varredura e a limpeza.
+    pub marker_missing: Vec<PathBuf>,
+    /// Remoções reais (sem dry-run, `--stage` ou `--move-to`) bem-sucedidas,
+    /// somadas por id de tipo: alimentam `history::record_throughput`.
+    pub timings: HashMap<String, KindTiming>,
+    /// Lockfiles apagados por `--remove-lockfiles` (no dry-run: seriam).
+    pub lockfiles_removed: Vec<PathBuf>,
+    /// Passadas feitas: 1, ou mais com `--repeat` quando sobraram falhas.
+    pub passes: u32,
+    /// Diretórios que ficaram vazios e foram apagados por `--prune-empty-parents`.
+    pub empty_parents_removed: Vec<PathBuf>,
 }
 
-pub fn clean_projects(projects: &[StaleProject], dry_run: bool, verbose: bool) -> CleanResult {
-    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs.len()).sum();
+/// Bytes apagados de um tipo e quanto tempo isso levou.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KindTiming {
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+/// Projeto que falhou com `--atomic-per-project`: a falha está em
+/// `CleanResult::errors` e `skipped` são as pastas dele que nem foram tentadas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialProject {
+    pub path: PathBuf,
+    pub skipped: Vec<PathBuf>,
+}
+
+impl CleanResult {
+    /// Conta uma pasta que saiu do projeto: apagada ou, com `--move-to`, movida.
+    fn add_removed(&mut self, size: u64, options: &CleanOptions) {
+        if options.move_to.is_some() && options.stage_dir.is_none() {
+            self.total_moved += size;
+        } else {
+            self.total_freed += size;
+        }
+        self.dirs_removed += 1;
+    }
+
+    /// Incorpora uma passada de `--repeat` sobre `retried`: os erros e projetos
+    /// parciais anteriores dessas pastas dão lugar aos da nova passada.
+    fn absorb(&mut self, next: CleanResult, retried: &[StaleProject]) {
+        let dirs: HashSet<&Path> = retried.iter().flat_map(|p| p.dep_dirs()).map(|d| d.path.as_path()).collect();
+        self.errors.retain(|(path, _)| !dirs.contains(path.as_path()));
+        self.partial.retain(|partial| !retried.iter().any(|p| p.path == partial.path));
+
+        self.total_freed += next.total_freed;
+        self.total_moved += next.total_moved;
+        self.dirs_removed += next.dirs_removed;
+        self.links_removed.extend(next.links_removed);
+        self.skipped_open.extend(next.skipped_open);
+        self.errors.extend(next.errors);
+        self.would_fail.extend(next.would_fail);
+        self.partial.extend(next.partial);
+        self.marker_missing.extend(next.marker_missing);
+        for (kind, timing) in next.timings {
+            let total = self.timings.entry(kind).or_default();
+            total.bytes += timing.bytes;
+            total.elapsed += timing.elapsed;
+        }
+        self.lockfiles_removed.extend(next.lockfiles_removed);
+        self.passes = next.passes;
+        self.empty_parents_removed.extend(next.empty_parents_removed);
+    }
+}
+
+/// Por que uma pasta não pôde ser removida. Cada variante guarda a mensagem
+/// do erro original.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CleanError {
+    /// Sem permissão, mesmo depois dos retries e do `chmod u+w`.
+    PermissionDenied(String),
+    /// A pasta sumiu entre a varredura e a limpeza.
+    NotFound(String),
+    /// Continuou em uso (ex: antivírus no Windows) até acabarem as tentativas.
+    RetriesExhausted(String),
+    /// `--stage` para outro disco e a cópia não pôde ser feita.
+    CrossDevice(String),
+    Other(String),
+}
+
+impl CleanError {
+    /// Identificador estável da categoria, para scripts e JSON.
+    pub fn category(&self) -> &'static str {
+        match self {
+            CleanError::PermissionDenied(_) => "permission-denied",
+            CleanError::NotFound(_) => "not-found",
+            CleanError::RetriesExhausted(_) => "retries-exhausted",
+            CleanError::CrossDevice(_) => "cross-device",
+            CleanError::Other(_) => "other",
+        }
+    }
+
+    /// Categoria em texto para o resumo no terminal.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CleanError::PermissionDenied(_) => "permissão negada",
+            CleanError::NotFound(_) => "não encontrada",
+            CleanError::RetriesExhausted(_) => "em uso",
+            CleanError::CrossDevice(_) => "outro disco",
+            CleanError::Other(_) => "erro",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CleanError::PermissionDenied(m)
+            | CleanError::NotFound(m)
+            | CleanError::RetriesExhausted(m)
+            | CleanError::CrossDevice(m)
+            | CleanError::Other(m) => m,
+        }
+    }
+
+    fn with_note(self, note: &str) -> Self {
+        let message = format!("{}: {}", note, self.message());
+        match self {
+            CleanError::PermissionDenied(_) => CleanError::PermissionDenied(message),
+            CleanError::NotFound(_) => CleanError::NotFound(message),
+            CleanError::RetriesExhausted(_) => CleanError::RetriesExhausted(message),
+            CleanError::CrossDevice(_) => CleanError::CrossDevice(message),
+            CleanError::Other(_) => CleanError::Other(message),
+        }
+    }
+}
+
+impl fmt::Display for CleanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CleanError {}
+
+impl From<io::Error> for CleanError {
+    fn from(e: io::Error) -> Self {
+        let message = e.to_string();
+        match e.kind() {
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => CleanError::PermissionDenied(message),
+            io::ErrorKind::NotFound => CleanError::NotFound(message),
+            io::ErrorKind::CrossesDevices => CleanError::CrossDevice(message),
+            _ => CleanError::Other(message),
+        }
+    }
+}
+
+/// Como uma pasta terminou, em `CleanEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirStatus {
+    /// Removida (ou movida, com `--stage`/`--move-to`; só o link, se era um
+    /// symlink). No dry-run: seria removida.
+    Removed,
+    /// Não tentada: em uso (`--protect-open`) ou projeto que já falhou
+    /// (`--atomic-per-project`).
+    Skipped,
+    /// Falhou; no dry-run com `--check-writable`, falharia.
+    Error,
+}
+
+impl DirStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirStatus::Removed => "removed",
+            DirStatus::Skipped => "skipped",
+            DirStatus::Error => "error",
+        }
+    }
+}
+
+/// Uma pasta concluída, entregue assim que termina (ex: `--format json`
+/// transmite uma linha por pasta durante a limpeza).
+#[derive(Debug)]
+pub struct CleanEvent<'a> {
+    pub dep: &'a DepDir,
+    pub status: DirStatus,
+    pub error: Option<&'a CleanError>,
+    /// Passada em que a pasta terminou (1 na primeira). Com `--repeat`, uma
+    /// pasta que falhou gera um novo evento em cada passada que a refaz.
+    pub pass: u32,
+}
+
+pub fn clean_projects(projects: &[StaleProject], options: &CleanOptions) -> CleanResult {
+    clean_projects_streaming(projects, options, |_| {})
+}
 
-    let pb = ProgressBar::new(total_dirs as u64);
+/// `clean_projects` chamando `on_event` a cada pasta concluída, na ordem da limpeza.
+pub fn clean_projects_streaming<E>(projects: &[StaleProject], options: &CleanOptions, on_event: E) -> CleanResult
+where
+    E: FnMut(&CleanEvent),
+{
+    match (&options.stage_dir, &options.move_to) {
+        (Some(staging), _) => clean_projects_with(projects, options, |path: &Path| {
+            staging::stage(staging, path).map(|_| ()).map_err(CleanError::from)
+        }, on_event),
+        (None, Some(archive)) => clean_projects_with(projects, options, |path: &Path| {
+            let target = staging::move_into(archive, path)?;
+            debug!("movido path={} destino={}", path.display(), target.display());
+            Ok(())
+        }, on_event),
+        (None, None) => clean_projects_with(projects, options, remove_dir_all_with_retry, on_event),
+    }
+}
+
+/// Separa as pastas de cada projeto conforme `pred`: (as que passam, o resto).
+/// Um projeto aparece de cada lado em que sobrar alguma pasta. Usado pela
+/// política de confirmação por tipo (`[confirm]` no config).
+pub fn partition_dirs<F>(projects: Vec<StaleProject>, mut pred: F) -> (Vec<StaleProject>, Vec<StaleProject>)
+where
+    F: FnMut(&DepDir) -> bool,
+{
+    let mut matched = Vec::new();
+    let mut rest = Vec::new();
+    for project in projects {
+        let (yes, no): (Vec<DepDir>, Vec<DepDir>) = project.dep_dirs().iter().cloned().partition(&mut pred);
+        for (side, deps) in [(&mut matched, yes), (&mut rest, no)] {
+            if !deps.is_empty() {
+                side.push(
+                    StaleProject::new(project.name.clone(), project.path.clone(), deps, project.last_modified)
+                        .with_newest_file(project.newest_file().map(Path::to_path_buf)),
+                );
+            }
+        }
+    }
+    (matched, rest)
+}
+
+/// Ordem das pastas em `clean_projects`. Cada pasta leva junto o projeto dono.
+/// Com `group_by_project` (`--atomic-per-project`), as pastas de um projeto
+/// ficam juntas, na posição da primeira delas na ordem pedida.
+fn clean_queue(projects: &[StaleProject], order: CleanOrder, group_by_project: bool) -> Vec<(&StaleProject, &DepDir)> {
+    let mut queue: Vec<(&StaleProject, &DepDir)> = projects
+        .iter()
+        .flat_map(|p| p.dep_dirs().iter().map(move |d| (p, d)))
+        .collect();
+    // sort_by_key é estável: empates mantêm a ordem da listagem
+    match order {
+        CleanOrder::Listed => {}
+        CleanOrder::SizeDesc => queue.sort_by_key(|(_, d)| std::cmp::Reverse(d.size)),
+        CleanOrder::SizeAsc => queue.sort_by_key(|(_, d)| d.size),
+        CleanOrder::Age => queue.sort_by_key(|(p, _)| p.last_modified),
+    }
+    if group_by_project {
+        let mut first_seen: HashMap<&Path, usize> = HashMap::new();
+        for (i, (p, _)) in queue.iter().enumerate() {
+            first_seen.entry(p.path.as_path()).or_insert(i);
+        }
+        queue.sort_by_key(|(p, _)| first_seen[p.path.as_path()]);
+    }
+    queue
+}
+
+/// Pausa antes de cada passada de `--repeat`, para quem segurava a pasta
+/// (antivírus, indexador, um build terminando) ter tempo de soltá-la.
+const REPEAT_DELAY: Duration = Duration::from_millis(500);
+
+/// `clean_projects` com a remoção de pastas comuns injetável (testes).
+/// Symlinks continuam passando por `remove_symlinked_dir`.
+///
+/// Com `--repeat`, cada passada extra refaz só as pastas que falharam e ainda
+/// existem (com `--atomic-per-project`, também as que foram puladas por causa
+/// delas). Ao contrário dos retries de `remove_dir_all_with_retry`, que insistem
+/// numa pasta por alguns milissegundos, a nova passada vem depois do resto da
+/// limpeza. `on_event` recebe um evento por pasta a cada passada.
+fn clean_projects_with<R, E>(projects: &[StaleProject], options: &CleanOptions, mut remove_dir: R, mut on_event: E) -> CleanResult
+where
+    R: FnMut(&Path) -> Result<(), CleanError>,
+    E: FnMut(&CleanEvent),
+{
+    let mut result = clean_pass(projects, options, &mut remove_dir, &mut on_event, None);
+
+    let passes = options.repeat + 1;
+    for pass in 2..=passes {
+        let pending_dirs: HashSet<&Path> = result
+            .errors
+            .iter()
+            .map(|(path, _)| path)
+            .chain(result.partial.iter().flat_map(|p| &p.skipped))
+            .filter(|path| fs::symlink_metadata(path).is_ok())
+            .map(PathBuf::as_path)
+            .collect();
+        let (pending, _) = partition_dirs(projects.to_vec(), |d| pending_dirs.contains(d.path.as_path()));
+        if pending.is_empty() {
+            break;
+        }
+        debug!("passada {}/{}: {} projetos com pastas restantes", pass, passes, pending.len());
+        std::thread::sleep(REPEAT_DELAY);
+        let next = clean_pass(&pending, options, &mut remove_dir, &mut on_event, Some((pass, passes)));
+        result.absorb(next, &pending);
+    }
+
+    if let Some(manifest) = &options.progress_manifest {
+        if !options.dry_run && result.errors.is_empty() {
+            let _ = fs::remove_file(manifest);
+        }
+    }
+
+    result
+}
+
+/// Uma passada da limpeza sobre `projects`. `pass` é (passada, total) nas
+/// passadas de `--repeat`, mostrado na barra e anunciado numa linha própria.
+fn clean_pass<R, E>(
+    projects: &[StaleProject],
+    options: &CleanOptions,
+    remove_dir: &mut R,
+    on_event: &mut E,
+    pass: Option<(u32, u32)>,
+) -> CleanResult
+where
+    R: FnMut(&Path) -> Result<(), CleanError>,
+    E: FnMut(&CleanEvent),
+{
+    let dry_run = options.dry_run;
+    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+    let total_bytes: u64 = projects.iter().map(|p| p.total_size()).sum();
+
+    // A barra avança por bytes (tamanhos já conhecidos antes da deleção),
+    // o que deixa o ETA estável mesmo com pastas de tamanhos muito desiguais.
+    let pb = if options.hide_progress {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(total_bytes)
+    };
+    let queue = clean_queue(projects, options.order, options.atomic_per_project);
+    let deleting = !dry_run && options.stage_dir.is_none() && options.move_to.is_none();
+
+    // Previsão pelas velocidades aprendidas: só quando há dado para todos os
+    // tipos da fila; senão fica o ETA genérico da barra
+    let mut forecast: Option<f64> = if deleting {
+        queue.iter().try_fold(0.0, |secs, (_, dep)| {
+            let rate = options.delete_rates.get(dep.kind.as_str()).filter(|r| **r > 0.0)?;
+            Some(secs + dep.size as f64 / rate)
+        })
+    } else {
+        None
+    };
+    let eta = if forecast.is_some() { "~{prefix}" } else { "{eta}" };
     pb.set_style(
-        ProgressStyle::with_template(
-            "  {spinner:.green} [{bar:30.cyan/dim}] {pos}/{len} pastas {msg}",
-        )
+        ProgressStyle::with_template(&format!(
+            "  {{spinner:.green}} [{{bar:30.cyan/dim}}] {{bytes}}/{{total_bytes}} ({{binary_bytes_per_sec}}, {{elapsed}} / ETA {}) {{msg}}",
+            eta
+        ))
         .unwrap()
         .progress_chars("█▓░"),
     );
+    let mut dirs_done = 0;
+    let pass_label = match pass {
+        Some((n, total)) => {
+            // `print_above` imprime direto quando a barra está oculta
+            // (`--progress off`, sem TTY): a passada aparece nos dois casos
+            if !options.quiet {
+                print_above(&pb, format!(
+                    "    {} Passada {}/{}: tentando de novo {} pastas que falharam.",
+                    icon("🔁"),
+                    n, total, total_dirs
+                ));
+            }
+            format!("passada {}/{} · ", n, total)
+        }
+        None => String::new(),
+    };
+    let pass_number = pass.map_or(1, |(n, _)| n);
 
     let mut result = CleanResult {
         total_freed: 0,
+        total_moved: 0,
         dirs_removed: 0,
+        links_removed: Vec::new(),
+        skipped_open: Vec::new(),
         errors: Vec::new(),
+        would_fail: Vec::new(),
+        partial: Vec::new(),
+        marker_missing: Vec::new(),
+        timings: HashMap::new(),
+        lockfiles_removed: Vec::new(),
+        passes: pass_number,
+        empty_parents_removed: Vec::new(),
     };
+    let types = if options.verify_markers || options.remove_lockfiles { all_project_types() } else { Vec::new() };
+    // Projeto -> posição em `result.partial`
+    let mut failed_projects: HashMap<&Path, usize> = HashMap::new();
 
-    for project in projects {
-        for dep in &project.dep_dirs {
-            pb.set_message(format!("removendo {}", dep.kind));
+    for (project, dep) in queue {
+        if let Some(secs) = forecast.as_mut() {
+            pb.set_prefix(HumanDuration(Duration::from_secs_f64(*secs)).to_string());
+            let rate = options.delete_rates.get(dep.kind.as_str()).copied().unwrap_or(f64::INFINITY);
+            *secs = (*secs - dep.size as f64 / rate).max(0.0);
+        }
+
+        if let Some(&i) = failed_projects.get(project.path.as_path()) {
+            debug!("pulando path={}: outra pasta do projeto falhou (--atomic-per-project)", dep.path.display());
+            result.partial[i].skipped.push(dep.path.clone());
+            on_event(&CleanEvent { dep, status: DirStatus::Skipped, error: None, pass: pass_number });
+            dirs_done += 1;
+            pb.inc(dep.size);
+            continue;
+        }
 
-            if verbose {
-                pb.println(format!("    → {}", dep.path.display()));
+        pb.set_message(format!("{}{}/{} pastas, removendo {}", pass_label, dirs_done + 1, total_dirs, dep.kind));
+
+        // No dry-run o detalhamento com tamanhos já foi impresso antes
+        if options.verbose && !dry_run && !options.quiet {
+            print_above(&pb, format!("    {} {}", icon("→"), dep.path.display()));
+        }
+
+        debug!("removendo path={} kind={} bytes={} dry_run={}", dep.path.display(), dep.kind, dep.size, dry_run);
+
+        if options.protect_open {
+            if let Some(pid) = open_handle_holder(&dep.path) {
+                warn!("pasta em uso, pulando path={} pid={}", dep.path.display(), pid);
+                if !options.quiet {
+                    print_above(&pb, format!(
+                        "    {}  {} em uso pelo processo {}, pulando.",
+                        icon("⏸️"),
+                        dep.path.display(),
+                        pid
+                    ));
+                }
+                result.skipped_open.push(dep.path.clone());
+                on_event(&CleanEvent { dep, status: DirStatus::Skipped, error: None, pass: pass_number });
+                dirs_done += 1;
+                pb.inc(dep.size);
+                continue;
+            }
+        }
+
+        if options.verify_markers && !marker_present(dep, &types) {
+            warn!("marcador do projeto sumiu, pulando path={}", dep.path.display());
+            if !options.quiet {
+                print_above(&pb, format!(
+                    "    {} {} não parece mais uma pasta de dependência (marcador ausente), pulando.",
+                    icon("🔎"),
+                    dep.path.display()
+                ));
             }
+            result.marker_missing.push(dep.path.clone());
+            on_event(&CleanEvent { dep, status: DirStatus::Skipped, error: None, pass: pass_number });
+            dirs_done += 1;
+            pb.inc(dep.size);
+            continue;
+        }
 
-            if dry_run {
-                result.total_freed += dep.size;
-                result.dirs_removed += 1;
+        let is_link = is_symlink(&dep.path);
+        let errors_before = result.errors.len();
+        let would_fail_before = result.would_fail.len();
+
+        if dry_run {
+            let probe = if options.check_writable { probe_removable(&dep.path, is_link) } else { Ok(()) };
+            if let Err(e) = probe {
+                debug!("remoção falharia path={} error={}", dep.path.display(), e);
+                result.would_fail.push((dep.path.clone(), e));
+            } else if is_link && !options.follow_symlinks {
+                result.links_removed.push(dep.path.clone());
             } else {
-                match remove_dir_all_with_retry(&dep.path) {
-                    Ok(_) => {
-                        result.total_freed += dep.size;
-                        result.dirs_removed += 1;
-                    }
-                    Err(e) => {
-                        result.errors.push((dep.path.clone(), e));
+                result.add_removed(dep.size, options);
+            }
+        } else if is_link {
+            match remove_symlinked_dir(&dep.path, options.follow_symlinks) {
+                Ok(_) if options.follow_symlinks => {
+                    result.total_freed += dep.size;
+                    result.dirs_removed += 1;
+                }
+                Ok(_) => result.links_removed.push(dep.path.clone()),
+                Err(e) => {
+                    warn!("falha ao remover symlink path={} error={}", dep.path.display(), e);
+                    result.errors.push((dep.path.clone(), e));
+                }
+            }
+        } else {
+            let started = Instant::now();
+            match remove_dir(&dep.path) {
+                Ok(_) => {
+                    result.add_removed(dep.size, options);
+                    if deleting {
+                        let timing = result.timings.entry(dep.kind.as_str().to_string()).or_default();
+                        timing.bytes += dep.size;
+                        timing.elapsed += started.elapsed();
                     }
                 }
+                Err(e) => {
+                    warn!("falha ao remover path={} error={}", dep.path.display(), e);
+                    result.errors.push((dep.path.clone(), e));
+                }
             }
+        }
 
-            pb.inc(1);
+        let error = if result.errors.len() > errors_before {
+            result.errors.last()
+        } else if result.would_fail.len() > would_fail_before {
+            result.would_fail.last()
+        } else {
+            None
+        };
+        let failed = error.is_some();
+        let status = if failed { DirStatus::Error } else { DirStatus::Removed };
+        on_event(&CleanEvent { dep, status, error: error.map(|(_, e)| e), pass: pass_number });
+        // Erro num lockfile não desfaz a remoção da pasta: ela entra no manifesto
+        if !dry_run && !failed {
+            if let Some(manifest) = &options.progress_manifest {
+                if let Err(e) = resume::record(manifest, &dep.path) {
+                    warn!("falha ao gravar manifesto path={} error={}", manifest.display(), e);
+                }
+            }
+        }
+        if !failed && options.remove_lockfiles {
+            remove_lockfiles(&project.path, dep, &types, dry_run, &mut result);
+        }
+        if !dry_run && !failed && options.prune_empty_parents {
+            result.empty_parents_removed.extend(remove_empty_parents(&dep.path, &project.path));
+        }
+        if failed && options.atomic_per_project {
+            failed_projects.insert(&project.path, result.partial.len());
+            result.partial.push(PartialProject { path: project.path.clone(), skipped: Vec::new() });
         }
+
+        dirs_done += 1;
+        pb.inc(dep.size);
     }
 
     pb.finish_and_clear();
     result
 }
 
-fn remove_dir_all_with_retry(path: &std::path::Path) -> Result<(), Error> {
-    use std::thread;
-    use std::time::Duration;
+/// PID de algum processo com arquivo aberto (ou diretório de trabalho) sob
+/// `path`. No Linux lê `/proc/*/fd`; em outros Unix usa `lsof`, se existir.
+#[cfg(target_os = "linux")]
+fn open_handle_holder(path: &Path) -> Option<u32> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        let proc_dir = entry.path();
+        let cwd = fs::read_link(proc_dir.join("cwd")).into_iter();
+        // Processos de outros usuários dão PermissionDenied: são ignorados
+        let fds = fs::read_dir(proc_dir.join("fd"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|fd| fs::read_link(fd.path()).ok());
+        if cwd.chain(fds).any(|target| target.starts_with(&path)) {
+            return Some(pid);
+        }
+    }
+    None
+}
 
+#[cfg(all(unix, not(target_os = "linux")))]
+fn open_handle_holder(path: &Path) -> Option<u32> {
+    let output = std::process::Command::new("lsof")
+        .arg("-t")
+        .arg("+D")
+        .arg(path)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|l| l.trim().parse().ok())
+}
+
+#[cfg(not(unix))]
+fn open_handle_holder(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// `--remove-lockfiles`: apaga os lockfiles do tipo de `dep` que existirem
+/// em `project` (no dry-run só os lista). Falhas entram em `result.errors`.
+fn remove_lockfiles(project: &Path, dep: &DepDir, types: &[Box<dyn ProjectType>], dry_run: bool, result: &mut CleanResult) {
+    let lockfiles = types
+        .iter()
+        .filter(|t| t.dep_kind() == dep.kind)
+        .flat_map(|t| t.lockfiles(project));
+    for lockfile in lockfiles {
+        if !lockfile.is_file() || result.lockfiles_removed.contains(&lockfile) {
+            continue;
+        }
+        debug!("removendo lockfile path={} dry_run={}", lockfile.display(), dry_run);
+        let outcome = if dry_run { Ok(()) } else { fs::remove_file(&lockfile) };
+        match outcome {
+            Ok(_) => result.lockfiles_removed.push(lockfile),
+            Err(e) => {
+                warn!("falha ao remover lockfile path={} error={}", lockfile.display(), e);
+                result.errors.push((lockfile, e.into()));
+            }
+        }
+    }
+}
+
+/// `--prune-empty-parents`: sobe de `dep` apagando cada diretório vazio, sem
+/// chegar a `project` nem sair dele. Para no primeiro que ainda tem algo
+/// (`remove_dir` só apaga diretórios vazios).
+fn remove_empty_parents(dep: &Path, project: &Path) -> Vec<PathBuf> {
+    let mut removed = Vec::new();
+    for dir in dep.ancestors().skip(1) {
+        if dir == project || !dir.starts_with(project) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        debug!("diretório vazio removido path={}", dir.display());
+        removed.push(dir.to_path_buf());
+    }
+    removed
+}
+
+/// A pasta ainda passa na detecção do seu tipo (`ProjectType::is_dependency_dir`):
+/// o `package.json` ao lado do `node_modules` continua lá, o venv ainda tem
+/// `pyvenv.cfg` etc. Pega projetos apagados ou movidos entre a varredura e a
+/// limpeza. Pastas de `--prune-dir` não têm marcador e sempre passam.
+fn marker_present(dep: &DepDir, types: &[Box<dyn ProjectType>]) -> bool {
+    let mut owners = types.iter().filter(|t| t.dep_kind() == dep.kind).peekable();
+    owners.peek().is_none() || owners.any(|t| t.is_dependency_dir(&dep.path))
+}
+
+/// Espaço livre, em bytes, no disco de `path` (`--ensure-free`), para quem não
+/// é root (`statvfs`); `None` se não der para consultar (ex: Windows).
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `c_path` é uma string C válida e `stat` é preenchido pela chamada
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let bytes = stat.f_bavail as u128 * stat.f_frsize as u128;
+    Some(u64::try_from(bytes).unwrap_or(u64::MAX))
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// `a` e `b` estão no mesmo sistema de arquivos, ou seja, mover de um para o
+/// outro não libera nada. Um caminho que ainda não existe conta pelo ancestral
+/// mais próximo que existe; sem como saber, `true`.
+#[cfg(unix)]
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let device = |path: &Path| path.ancestors().find_map(|p| fs::metadata(p).ok()).map(|m| m.dev());
+    match (device(a), device(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn same_filesystem(_a: &Path, _b: &Path) -> bool {
+    true
+}
+
+/// `ProgressBar::println` não imprime nada quando a barra está oculta
+/// (`--progress off` ou stderr fora de um terminal).
+fn print_above(pb: &ProgressBar, line: String) {
+    if pb.is_hidden() {
+        println!("{}", line);
+    } else {
+        pb.println(line);
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).map(|m| m.file_type().is_symlink()).unwrap_or(false)
+}
+
+/// Remove uma pasta de dependência que é um symlink. Por padrão apaga só o
+/// link; com `follow` apaga antes o conteúdo do destino (ex: store global).
+fn remove_symlinked_dir(path: &Path, follow: bool) -> Result<(), CleanError> {
+    if follow {
+        let target = fs::canonicalize(path)?;
+        remove_dir_all_with_retry(&target)?;
+    }
+
+    // No Windows, symlinks/junctions de diretório são removidos com remove_dir
+    #[cfg(windows)]
+    fs::remove_dir(path)?;
     #[cfg(not(windows))]
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+/// Tentativas de `remove_dir_all_with_retry` fora do Windows, com a remoção
+/// injetável (testes). Além de `PermissionDenied` (indexadores, antivírus),
+/// insiste numa pasta que algo continua enchendo ou segurando (watcher, build
+/// rodando): se ela não sai em 3 tentativas, o erro é `RetriesExhausted`.
+#[cfg(not(windows))]
+fn retry_removal<F>(path: &Path, mut remove: F) -> Result<(), CleanError>
+where
+    F: FnMut(&Path) -> io::Result<()>,
+{
+    use std::thread;
+
+    let mut last_err = None;
+    for i in 0..3 {
+        match remove(path) {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                let retriable = matches!(
+                    e.kind(),
+                    io::ErrorKind::PermissionDenied | io::ErrorKind::DirectoryNotEmpty | io::ErrorKind::ResourceBusy
+                );
+                if !retriable {
+                    return Err(e.into());
+                }
+                if i < 2 {
+                    thread::sleep(Duration::from_millis(100 * 2u64.pow(i as u32)));
+                }
+                last_err = Some(e);
+            }
+        }
+    }
+
+    let last_err = last_err.unwrap();
+    if last_err.kind() != io::ErrorKind::PermissionDenied {
+        return Err(CleanError::RetriesExhausted(format!("após 3 tentativas: {}", last_err)));
+    }
+
+    // Arquivos/pastas sem permissão de escrita (ex: caches read-only):
+    // libera u+w na subárvore e tenta uma última vez.
+    #[cfg(unix)]
     {
-        let mut last_err = None;
-        // Retry up to 3 times for PermissionDenied (common with file indexers/AV on some setups)
-        for i in 0..3 {
-            match fs::remove_dir_all(path) {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    if e.kind() != std::io::ErrorKind::PermissionDenied {
-                        return Err(e.into());
+        debug!("remoção negada path={} err={}; corrigindo permissões", path.display(), last_err);
+        let fixup = make_tree_writable(path);
+        match remove(path) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let note = match fixup {
+                    Ok(_) => "após tentativa de corrigir permissões (chmod u+w)".to_string(),
+                    Err(fe) => format!("correção de permissões (chmod u+w) falhou: {}", fe),
+                };
+                // Depois de todas as tentativas, algo ainda recria ou
+                // segura arquivos na pasta (ex: watcher, build rodando)
+                let err = match e.kind() {
+                    io::ErrorKind::DirectoryNotEmpty | io::ErrorKind::ResourceBusy => {
+                        CleanError::RetriesExhausted(e.to_string())
                     }
-                    if i < 2 {
-                        thread::sleep(Duration::from_millis(100 * 2u64.pow(i as u32)));
-                    }
-                    last_err = Some(e);
-                }
+                    _ => CleanError::from(e),
+                };
+                Err(err.with_note(&note))
             }
         }
-        Err(last_err.unwrap().into())
+    }
+
+    #[cfg(not(unix))]
+    Err(last_err.into())
+}
+
+fn remove_dir_all_with_retry(path: &Path) -> Result<(), CleanError> {
+    #[cfg(not(windows))]
+    {
+        retry_removal(path, |p| fs::remove_dir_all(p))
     }
 
     #[cfg(windows)]
     {
         use std::io::ErrorKind;
+        use std::thread;
 
         // ERROR_SHARING_VIOLATION (32) — arquivo em uso por outro processo (ex: antivírus)
         const ERROR_SHARING_VIOLATION: i32 = 32;
@@ -114,13 +880,180 @@ fn remove_dir_all_with_retry(path: &std::path::Path) -> Result<(), Error> {
                 }
             }
         }
-        Err(last_err.unwrap().into())
+        let last_err = last_err.unwrap();
+        if last_err.raw_os_error() == Some(ERROR_SHARING_VIOLATION) {
+            return Err(CleanError::RetriesExhausted(format!("após 5 tentativas: {}", last_err)));
+        }
+        Err(last_err.into())
+    }
+}
+
+/// Verifica, sem alterar nada, se `path` poderia ser removida: a pasta pai
+/// precisa aceitar escrita (é dela que a entrada sai) e cada subpasta precisa
+/// ser legível e gravável, ou ao menos corrigível pelo `chmod u+w` da remoção
+/// real (só o dono consegue). No Unix o acesso é perguntado ao kernel
+/// (`faccessat`), que já considera root e ACLs; o sticky bit (ex: `/tmp`) é
+/// conferido à parte. Um arquivo em uso ainda pode travar a remoção.
+pub fn probe_removable(path: &Path, is_link: bool) -> Result<(), CleanError> {
+    probe_removable_as(path, is_link, &Identity::current())
+}
+
+fn probe_removable_as(path: &Path, is_link: bool, who: &Identity) -> Result<(), CleanError> {
+    if let Some(parent) = path.parent() {
+        if !who.can_modify(parent) {
+            return Err(CleanError::PermissionDenied(format!(
+                "pasta pai sem permissão de escrita: {}",
+                parent.display()
+            )));
+        }
+        if !who.can_unlink(&fs::metadata(parent)?, &fs::symlink_metadata(path)?) {
+            return Err(CleanError::PermissionDenied(format!(
+                "pasta pai com sticky bit e a entrada é de outro usuário: {}",
+                parent.display()
+            )));
+        }
+    }
+    // Só o link sai: o conteúdo do destino não importa
+    if is_link {
+        return Ok(());
+    }
+    probe_tree(path, who)
+}
+
+fn probe_tree(dir: &Path, who: &Identity) -> Result<(), CleanError> {
+    let meta = fs::symlink_metadata(dir)?;
+    if !who.can_modify(dir) && !who.can_chmod(&meta) {
+        return Err(CleanError::PermissionDenied(format!(
+            "sem permissão de escrita e não corrigível (chmod): {}",
+            dir.display()
+        )));
+    }
+    let entries = fs::read_dir(dir).map_err(|e| CleanError::from(e).with_note(&dir.display().to_string()))?;
+    for entry in entries {
+        let entry = entry?;
+        if who.is_sticky(&meta) && !who.can_unlink(&meta, &entry.metadata()?) {
+            return Err(CleanError::PermissionDenied(format!(
+                "pasta com sticky bit e a entrada é de outro usuário: {}",
+                entry.path().display()
+            )));
+        }
+        if entry.file_type()?.is_dir() {
+            probe_tree(&entry.path(), who)?;
+        }
     }
+    Ok(())
+}
+
+/// Usuário efetivo do processo, para `probe_removable` avaliar as regras que
+/// o `faccessat` não cobre (sticky bit, quem pode dar `chmod`).
+#[cfg(unix)]
+struct Identity {
+    uid: u32,
+}
+
+#[cfg(unix)]
+impl Identity {
+    fn current() -> Self {
+        // SAFETY: só consulta o processo
+        Identity { uid: unsafe { libc::geteuid() } }
+    }
+
+    /// Pode criar e apagar entradas na pasta (`w` e `x`), segundo o kernel,
+    /// com o usuário efetivo.
+    fn can_modify(&self, dir: &Path) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+
+        let Ok(c_path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: `c_path` é uma string C válida durante toda a chamada
+        unsafe { libc::faccessat(libc::AT_FDCWD, c_path.as_ptr(), libc::W_OK | libc::X_OK, libc::AT_EACCESS) == 0 }
+    }
+
+    fn is_sticky(&self, meta: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        meta.mode() & 0o1000 != 0
+    }
+
+    /// Numa pasta com sticky bit, só o dono da entrada, o da pasta ou root
+    /// apagam a entrada.
+    fn can_unlink(&self, dir: &fs::Metadata, entry: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        !self.is_sticky(dir) || self.uid == 0 || dir.uid() == self.uid || entry.uid() == self.uid
+    }
+
+    /// O `chmod u+w` da remoção real só funciona para o dono (ou root).
+    fn can_chmod(&self, meta: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        self.uid == 0 || meta.uid() == self.uid
+    }
+}
+
+#[cfg(not(unix))]
+struct Identity;
+
+#[cfg(not(unix))]
+impl Identity {
+    fn current() -> Self {
+        Identity
+    }
+
+    fn can_modify(&self, dir: &Path) -> bool {
+        fs::metadata(dir).is_ok_and(|m| !m.permissions().readonly())
+    }
+
+    fn is_sticky(&self, _meta: &fs::Metadata) -> bool {
+        false
+    }
+
+    fn can_unlink(&self, _dir: &fs::Metadata, _entry: &fs::Metadata) -> bool {
+        true
+    }
+
+    fn can_chmod(&self, _meta: &fs::Metadata) -> bool {
+        false
+    }
+}
+
+/// Adiciona `u+w` em toda a subárvore, sem seguir symlinks.
+/// Em caso de falha nada é restaurado — a pasta vai ser removida mesmo.
+#[cfg(unix)]
+fn make_tree_writable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let meta = fs::symlink_metadata(path)?;
+    if meta.file_type().is_symlink() {
+        return Ok(());
+    }
+
+    let mut perms = meta.permissions();
+    if perms.mode() & 0o200 == 0 {
+        perms.set_mode(perms.mode() | 0o200);
+        fs::set_permissions(path, perms)?;
+    }
+
+    if meta.is_dir() {
+        // Pastas sem `r`/`x` também impedem a remoção dos filhos
+        let mut perms = fs::symlink_metadata(path)?.permissions();
+        if perms.mode() & 0o700 != 0o700 {
+            perms.set_mode(perms.mode() | 0o700);
+            fs::set_permissions(path, perms)?;
+        }
+        for entry in fs::read_dir(path)? {
+            make_tree_writable(&entry?.path())?;
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::history;
     use crate::types::{DepDir, DepKind};
     use std::time::SystemTime;
 
@@ -132,18 +1065,19 @@ mod tests {
         let dep_path = temp.join("node_modules");
         fs::create_dir(&dep_path).unwrap();
 
-        let project = StaleProject {
-            name: "test".to_string(),
-            path: temp.clone(),
-            dep_dirs: vec![DepDir {
+        let project = StaleProject::new(
+            "test".to_string(),
+            temp.clone(),
+            vec![DepDir {
                 path: dep_path.clone(),
                 size: 100,
+                files: 0,
                 kind: DepKind::NodeModules,
             }],
-            last_modified: SystemTime::now(),
-        };
+            SystemTime::now(),
+        );
 
-        let result = clean_projects(&[project], true, false);
+        let result = clean_projects(&[project], &CleanOptions { dry_run: true, ..Default::default() });
 
         assert_eq!(result.total_freed, 100);
         assert_eq!(result.dirs_removed, 1);
@@ -163,19 +1097,20 @@ mod tests {
         let dep_path = temp.join("node_modules");
         fs::create_dir(&dep_path).unwrap();
 
-        let project = StaleProject {
-            name: "test".to_string(),
-            path: temp.clone(),
-            dep_dirs: vec![DepDir {
+        let project = StaleProject::new(
+            "test".to_string(),
+            temp.clone(),
+            vec![DepDir {
                 path: dep_path.clone(),
                 size: 200,
+                files: 0,
                 kind: DepKind::NodeModules,
             }],
-            last_modified: SystemTime::now(),
-        };
+            SystemTime::now(),
+        );
 
         // Run actual clean
-        let result = clean_projects(&[project], false, false);
+        let result = clean_projects(&[project], &CleanOptions::default());
 
         assert_eq!(result.total_freed, 200);
         assert_eq!(result.dirs_removed, 1);
@@ -186,4 +1121,570 @@ mod tests {
 
         fs::remove_dir_all(&temp).unwrap();
     }
+
+    #[test]
+    fn test_clean_projects_uneven_sizes_totals() {
+        let temp = std::env::temp_dir().join(format!("test_clean_uneven_{}", std::process::id()));
+        fs::create_dir_all(&temp).unwrap();
+
+        let sizes = [0u64, 10, 5_000_000];
+        let dep_dirs: Vec<DepDir> = sizes.iter().enumerate().map(|(i, size)| {
+            let path = temp.join(format!("dep_{}", i));
+            fs::create_dir(&path).unwrap();
+            DepDir { path, size: *size, files: 0, kind: DepKind::Target }
+        }).collect();
+
+        let project = StaleProject::new(
+            "uneven".to_string(),
+            temp.clone(),
+            dep_dirs,
+            SystemTime::now(),
+        );
+
+        let result = clean_projects(&[project], &CleanOptions::default());
+
+        assert_eq!(result.total_freed, sizes.iter().sum::<u64>());
+        assert_eq!(result.dirs_removed, 3);
+        assert!(result.errors.is_empty());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_clean_order_controls_removal_sequence() {
+        use std::time::Duration;
+
+        let now = SystemTime::now();
+        let project = |name: &str, days: u64, sizes: &[u64]| StaleProject::new(
+            name.to_string(),
+            PathBuf::from("/code").join(name),
+            sizes.iter().enumerate().map(|(i, size)| DepDir {
+                path: PathBuf::from("/code").join(name).join(format!("dep{}", i)),
+                size: *size,
+                files: 0,
+                kind: DepKind::NodeModules,
+            }).collect(),
+            now - Duration::from_secs(days * 24 * 3600),
+        );
+        let projects = vec![project("recent", 40, &[5, 50]), project("oldest", 400, &[20]), project("mid", 100, &[1])];
+
+        let removed_in = |order: CleanOrder| {
+            let mut seen: Vec<String> = Vec::new();
+            let options = CleanOptions { order, hide_progress: true, ..Default::default() };
+            let result = clean_projects_with(&projects, &options, |path: &Path| {
+                seen.push(path.strip_prefix("/code").unwrap().display().to_string());
+                Ok(())
+            }, |_| {});
+            assert_eq!(result.dirs_removed, 4);
+            seen
+        };
+
+        assert_eq!(removed_in(CleanOrder::Listed), ["recent/dep0", "recent/dep1", "oldest/dep0", "mid/dep0"]);
+        assert_eq!(removed_in(CleanOrder::SizeDesc), ["recent/dep1", "oldest/dep0", "recent/dep0", "mid/dep0"]);
+        assert_eq!(removed_in(CleanOrder::SizeAsc), ["mid/dep0", "recent/dep0", "oldest/dep0", "recent/dep1"]);
+        assert_eq!(removed_in(CleanOrder::Age), ["oldest/dep0", "mid/dep0", "recent/dep0", "recent/dep1"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_protect_open_skips_dir_in_use() {
+        let temp = std::env::temp_dir().join(format!("test_clean_open_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let busy = temp.join("busy/target");
+        let idle = temp.join("idle/target");
+        fs::create_dir_all(&busy).unwrap();
+        fs::create_dir_all(&idle).unwrap();
+        let _held = fs::File::create(busy.join("build.lock")).unwrap();
+
+        let project = StaleProject::new(
+            "proj".to_string(),
+            temp.clone(),
+            vec![
+                DepDir { path: busy.clone(), size: 1, files: 0, kind: DepKind::Target },
+                DepDir { path: idle.clone(), size: 1, files: 0, kind: DepKind::Target },
+            ],
+            SystemTime::now(),
+        );
+
+        let options = CleanOptions { protect_open: true, ..Default::default() };
+        let result = clean_projects(&[project], &options);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.skipped_open, vec![busy.clone()]);
+        assert_eq!(result.dirs_removed, 1);
+        assert!(busy.exists());
+        assert!(!idle.exists());
+
+        drop(_held);
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_verify_markers_skips_dir_whose_manifest_vanished() {
+        let temp = std::env::temp_dir().join(format!("test_clean_markers_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let project = |name: &str| {
+            let dir = temp.join(name);
+            fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+            StaleProject::new(
+                name.to_string(),
+                dir.clone(),
+                vec![DepDir { path: dir.join("node_modules"), size: 1, files: 0, kind: DepKind::NodeModules }],
+                SystemTime::now(),
+            )
+        };
+        let projects = [project("kept"), project("moved")];
+
+        // Entre a varredura e a limpeza o manifesto de um dos projetos sumiu
+        fs::remove_file(temp.join("moved/package.json")).unwrap();
+
+        let options = CleanOptions { verify_markers: true, ..Default::default() };
+        let result = clean_projects(&projects, &options);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.marker_missing, vec![temp.join("moved/node_modules")]);
+        assert_eq!(result.dirs_removed, 1);
+        assert!(!temp.join("kept/node_modules").exists());
+        assert!(temp.join("moved/node_modules/pkg").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_real_clean_updates_throughput_record() {
+        let temp = std::env::temp_dir().join(format!("test_clean_throughput_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = temp.join("app/node_modules");
+        fs::create_dir_all(dep.join("pkg")).unwrap();
+        fs::write(dep.join("pkg/index.js"), vec![b'x'; 4096]).unwrap();
+        let project = StaleProject::new(
+            "app".to_string(),
+            temp.join("app"),
+            vec![DepDir { path: dep.clone(), size: 4096, files: 1, kind: DepKind::NodeModules }],
+            SystemTime::now(),
+        );
+
+        // Dry-run não mede nada
+        let dry = clean_projects(std::slice::from_ref(&project), &CleanOptions { dry_run: true, ..Default::default() });
+        assert!(dry.timings.is_empty());
+
+        let result = clean_projects(&[project], &CleanOptions::default());
+        assert!(!dep.exists());
+        assert_eq!(result.timings["node_modules"].bytes, 4096);
+
+        let record = temp.join("cache/throughput.tsv");
+        history::record_throughput(&record, &result.timings).unwrap();
+        assert!(history::load_throughput(&record)["node_modules"] > 0.0);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_remove_lockfiles_only_under_flag() {
+        let temp = std::env::temp_dir().join(format!("test_clean_lockfiles_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let project = |name: &str| {
+            let dir = temp.join(name);
+            fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+            fs::write(dir.join("package-lock.json"), "{}").unwrap();
+            StaleProject::new(
+                name.to_string(),
+                dir.clone(),
+                vec![DepDir { path: dir.join("node_modules"), size: 1, files: 0, kind: DepKind::NodeModules }],
+                SystemTime::now(),
+            )
+        };
+
+        let result = clean_projects(&[project("plain")], &CleanOptions::default());
+        assert!(result.lockfiles_removed.is_empty());
+        assert!(temp.join("plain/package-lock.json").exists());
+
+        let options = CleanOptions { remove_lockfiles: true, ..Default::default() };
+        let result = clean_projects(&[project("fresh")], &options);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.lockfiles_removed, vec![temp.join("fresh/package-lock.json")]);
+        assert!(!temp.join("fresh/package-lock.json").exists());
+        assert!(temp.join("fresh/package.json").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_move_to_keeps_same_named_dirs_apart() {
+        let temp = std::env::temp_dir().join(format!("test_clean_move_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let archive = temp.join("archive");
+        let project = |name: &str, content: &str| {
+            let dep = temp.join(name).join("node_modules");
+            fs::create_dir_all(&dep).unwrap();
+            fs::write(dep.join("lib.js"), content).unwrap();
+            StaleProject::new(
+                name.to_string(),
+                temp.join(name),
+                vec![DepDir { path: dep, size: content.len() as u64, files: 1, kind: DepKind::NodeModules }],
+                SystemTime::now(),
+            )
+        };
+        let options = CleanOptions { move_to: Some(archive.clone()), ..Default::default() };
+
+        let result = clean_projects(&[project("a", "aaa"), project("b", "bb")], &options);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!((result.dirs_removed, result.total_moved, result.total_freed), (2, 5, 0));
+
+        // O destino recria o caminho absoluto de origem
+        let mirrored = |name: &str| {
+            let relative: PathBuf = temp.join(name).components().skip(1).collect();
+            archive.join(relative)
+        };
+        assert_eq!(fs::read_to_string(mirrored("a/node_modules/lib.js")).unwrap(), "aaa");
+        assert_eq!(fs::read_to_string(mirrored("b/node_modules/lib.js")).unwrap(), "bb");
+        assert!(!temp.join("a/node_modules").exists());
+
+        // Mesmo caminho de novo: ganha sufixo em vez de sobrescrever
+        let result = clean_projects(&[project("a", "new")], &options);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(fs::read_to_string(mirrored("a/node_modules/lib.js")).unwrap(), "aaa");
+        assert_eq!(fs::read_to_string(mirrored("a/node_modules-1/lib.js")).unwrap(), "new");
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_clean_errors_are_categorized() {
+        let temp = std::env::temp_dir().join(format!("test_clean_errors_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = |name: &str| DepDir { path: temp.join(name), size: 1, files: 0, kind: DepKind::NodeModules };
+        let project = StaleProject::new(
+            "proj".to_string(),
+            temp.clone(),
+            vec![dep("denied"), dep("busy"), dep("moved"), dep("gone")],
+            SystemTime::now(),
+        );
+
+        let result = clean_projects_with(&[project], &CleanOptions::default(), |path: &Path| {
+            let kind = match path.file_name().unwrap().to_str().unwrap() {
+                "denied" => io::ErrorKind::PermissionDenied,
+                "busy" => io::ErrorKind::Interrupted,
+                "moved" => io::ErrorKind::CrossesDevices,
+                _ => return remove_dir_all_with_retry(path),
+            };
+            Err(io::Error::new(kind, "simulado").into())
+        }, |_| {});
+
+        let categories: Vec<&str> = result.errors.iter().map(|(_, e)| e.category()).collect();
+        assert_eq!(categories, vec!["permission-denied", "other", "cross-device", "not-found"]);
+        assert_eq!(result.errors[0], (temp.join("denied"), CleanError::PermissionDenied("simulado".to_string())));
+        assert_eq!(result.dirs_removed, 0);
+    }
+
+    #[test]
+    fn test_atomic_per_project_skips_rest_of_failed_project() {
+        let temp = std::env::temp_dir().join(format!("test_clean_atomic_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = |path: PathBuf, size: u64| DepDir { path, size, files: 0, kind: DepKind::NodeModules };
+        for dir in ["app/bin", "app/obj", "lib/bin"] {
+            fs::create_dir_all(temp.join(dir)).unwrap();
+        }
+        let projects = vec![
+            StaleProject::new(
+                "app".to_string(),
+                temp.join("app"),
+                vec![dep(temp.join("app/bin"), 10), dep(temp.join("app/obj"), 5)],
+                SystemTime::now(),
+            ),
+            StaleProject::new("lib".to_string(), temp.join("lib"), vec![dep(temp.join("lib/bin"), 7)], SystemTime::now()),
+        ];
+        // `app/bin` não pode ser apagada
+        let remove = |path: &Path| {
+            if path.ends_with("app/bin") {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, "simulado").into());
+            }
+            remove_dir_all_with_retry(path)
+        };
+
+        // `size-desc` intercalaria lib entre as pastas de app; o modo atômico agrupa
+        let options = CleanOptions { atomic_per_project: true, order: CleanOrder::SizeDesc, ..Default::default() };
+        let result = clean_projects_with(&projects, &options, remove, |_| {});
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.partial, vec![PartialProject { path: temp.join("app"), skipped: vec![temp.join("app/obj")] }]);
+        assert!(temp.join("app/obj").exists(), "the second dir of the failed project is left alone");
+        assert!(!temp.join("lib/bin").exists(), "other projects are still cleaned");
+        assert_eq!((result.dirs_removed, result.total_freed), (1, 7));
+
+        // Sem o modo atômico a outra pasta do projeto é removida
+        let result = clean_projects_with(&projects, &CleanOptions::default(), remove, |_| {});
+        assert!(result.partial.is_empty());
+        assert!(!temp.join("app/obj").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_prune_empty_parents_stops_at_project_and_non_empty_dirs() {
+        let temp = std::env::temp_dir().join(format!("test_clean_prune_parents_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = |path: PathBuf| DepDir { path, size: 10, files: 0, kind: DepKind::IosDeps };
+        let setup = |name: &str, keep_checkouts: bool| {
+            let proj = temp.join(name);
+            fs::create_dir_all(proj.join("Carthage/Build/iOS")).unwrap();
+            fs::write(proj.join("Cartfile"), "").unwrap();
+            if keep_checkouts {
+                fs::create_dir_all(proj.join("Carthage/Checkouts")).unwrap();
+            }
+            StaleProject::new(name.to_string(), proj.clone(), vec![dep(proj.join("Carthage/Build"))], SystemTime::now())
+        };
+        let projects = vec![setup("app", false), setup("lib", true)];
+
+        let options = CleanOptions { prune_empty_parents: true, quiet: true, ..Default::default() };
+        let result = clean_projects(&projects, &options);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.empty_parents_removed, vec![temp.join("app/Carthage")]);
+        assert!(!temp.join("app/Carthage").exists());
+        assert!(temp.join("app/Cartfile").exists());
+        assert!(temp.join("lib/Carthage/Checkouts").exists());
+
+        // Sem a flag o diretório vazio fica
+        let projects = vec![setup("other", false)];
+        let result = clean_projects(&projects, &CleanOptions { quiet: true, ..Default::default() });
+        assert!(result.empty_parents_removed.is_empty());
+        assert!(temp.join("other/Carthage").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_retry_removal_exhausts_on_a_dir_that_keeps_refilling() {
+        let refilled = || io::Error::new(io::ErrorKind::DirectoryNotEmpty, "simulado");
+
+        let mut attempts = 0;
+        let result = retry_removal(Path::new("/x/node_modules"), |_| {
+            attempts += 1;
+            Err(refilled())
+        });
+        assert_eq!(attempts, 3);
+        match result {
+            Err(e @ CleanError::RetriesExhausted(_)) => assert_eq!(e.category(), "retries-exhausted"),
+            other => panic!("expected RetriesExhausted, got {:?}", other),
+        }
+
+        // Esvaziou a tempo: a segunda tentativa remove
+        let mut attempts = 0;
+        let result = retry_removal(Path::new("/x/node_modules"), |_| {
+            attempts += 1;
+            if attempts == 1 { Err(refilled()) } else { Ok(()) }
+        });
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+
+        // Outros erros não são repetidos
+        let mut attempts = 0;
+        let result = retry_removal(Path::new("/x/node_modules"), |_| {
+            attempts += 1;
+            Err(io::Error::new(io::ErrorKind::NotFound, "sumiu"))
+        });
+        assert!(matches!(result, Err(CleanError::NotFound(_))));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_repeat_retries_dirs_that_failed() {
+        let temp = std::env::temp_dir().join(format!("test_clean_repeat_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = |path: PathBuf| DepDir { path, size: 10, files: 0, kind: DepKind::NodeModules };
+        for dir in ["app/node_modules", "lib/node_modules"] {
+            fs::create_dir_all(temp.join(dir)).unwrap();
+        }
+        let projects = vec![
+            StaleProject::new("app".to_string(), temp.join("app"), vec![dep(temp.join("app/node_modules"))], SystemTime::now()),
+            StaleProject::new("lib".to_string(), temp.join("lib"), vec![dep(temp.join("lib/node_modules"))], SystemTime::now()),
+        ];
+        // `app/node_modules` está travada só na primeira tentativa
+        let remover = || {
+            let mut attempts: Vec<PathBuf> = Vec::new();
+            move |path: &Path| {
+                attempts.push(path.to_path_buf());
+                if path.ends_with("app/node_modules") && attempts.iter().filter(|p| *p == path).count() == 1 {
+                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, "simulado").into());
+                }
+                remove_dir_all_with_retry(path)
+            }
+        };
+
+        // Sem --repeat a falha fica
+        let result = clean_projects_with(&projects, &CleanOptions::default(), remover(), |_| {});
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.dirs_removed, 1);
+        assert!(temp.join("app/node_modules").exists());
+
+        fs::create_dir_all(temp.join("lib/node_modules")).unwrap();
+        let options = CleanOptions { repeat: 3, quiet: true, ..Default::default() };
+        let mut events = Vec::new();
+        let result = clean_projects_with(&projects, &options, remover(), |e| {
+            events.push((e.dep.path.clone(), e.status, e.pass));
+        });
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!((result.dirs_removed, result.total_freed), (2, 20));
+        assert!(!temp.join("app/node_modules").exists());
+        // A segunda passada refaz só a pasta que falhou, e não há uma terceira
+        assert_eq!(events, vec![
+            (temp.join("app/node_modules"), DirStatus::Error, 1),
+            (temp.join("lib/node_modules"), DirStatus::Removed, 1),
+            (temp.join("app/node_modules"), DirStatus::Removed, 2),
+        ]);
+        assert_eq!(result.passes, 2);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_symlinked_dep_keeps_target() {
+        let temp = std::env::temp_dir().join(format!("test_clean_symlink_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let store = temp.join("global-store");
+        let proj = temp.join("proj");
+        fs::create_dir_all(&store).unwrap();
+        fs::create_dir_all(&proj).unwrap();
+        fs::write(store.join("pkg.js"), "shared").unwrap();
+
+        let link = proj.join("node_modules");
+        std::os::unix::fs::symlink(&store, &link).unwrap();
+
+        let project = StaleProject::new(
+            "proj".to_string(),
+            proj.clone(),
+            vec![DepDir { path: link.clone(), size: 0, files: 0, kind: DepKind::NodeModules }],
+            SystemTime::now(),
+        );
+
+        let result = clean_projects(std::slice::from_ref(&project), &CleanOptions::default());
+        assert!(result.errors.is_empty());
+        assert_eq!(result.links_removed, vec![link.clone()]);
+        assert_eq!(result.dirs_removed, 0);
+        assert!(fs::symlink_metadata(&link).is_err(), "Link should be gone");
+        assert!(store.join("pkg.js").exists(), "Shared store must survive");
+
+        // Opt-in: apaga também o destino
+        std::os::unix::fs::symlink(&store, &link).unwrap();
+        let options = CleanOptions { follow_symlinks: true, ..Default::default() };
+        let result = clean_projects(&[project], &options);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.dirs_removed, 1);
+        assert!(!store.exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_removes_read_only_tree() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = std::env::temp_dir().join(format!("test_clean_readonly_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep_path = temp.join("node_modules");
+        let cache = dep_path.join(".cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("artifact.bin"), "data").unwrap();
+        fs::set_permissions(cache.join("artifact.bin"), fs::Permissions::from_mode(0o444)).unwrap();
+        fs::set_permissions(&cache, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let project = StaleProject::new(
+            "readonly".to_string(),
+            temp.clone(),
+            vec![DepDir {
+                path: dep_path.clone(),
+                size: 4,
+                files: 0,
+                kind: DepKind::NodeModules,
+            }],
+            SystemTime::now(),
+        );
+
+        let result = clean_projects(&[project], &CleanOptions::default());
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.dirs_removed, 1);
+        assert!(!dep_path.exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_writable_reports_read_only_parent() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = std::env::temp_dir().join(format!("test_check_writable_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = |path: PathBuf| DepDir { path, size: 10, files: 0, kind: DepKind::NodeModules };
+
+        // Projeto travado: a entrada node_modules não pode sair da pasta pai
+        let locked = temp.join("locked");
+        fs::create_dir_all(locked.join("node_modules/pkg")).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        // Subpasta read-only do próprio usuário: o chmod u+w resolve na remoção real
+        let fixable = temp.join("fixable");
+        fs::create_dir_all(fixable.join("node_modules/.cache")).unwrap();
+        fs::set_permissions(fixable.join("node_modules/.cache"), fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root passa por cima dos bits: nada a reportar
+        if fs::write(locked.join(".probe"), "").is_ok() {
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            fs::set_permissions(fixable.join("node_modules/.cache"), fs::Permissions::from_mode(0o755)).unwrap();
+            fs::remove_dir_all(&temp).unwrap();
+            return;
+        }
+
+        let projects = vec![
+            StaleProject::new("locked".to_string(), locked.clone(), vec![dep(locked.join("node_modules"))], SystemTime::now()),
+            StaleProject::new("fixable".to_string(), fixable.clone(), vec![dep(fixable.join("node_modules"))], SystemTime::now()),
+        ];
+        let options = CleanOptions { dry_run: true, check_writable: true, ..Default::default() };
+        let result = clean_projects(&projects, &options);
+
+        assert_eq!(result.would_fail.len(), 1, "{:?}", result.would_fail);
+        assert_eq!(result.would_fail[0].0, locked.join("node_modules"));
+        assert_eq!(result.would_fail[0].1.category(), "permission-denied");
+        assert_eq!((result.dirs_removed, result.total_freed), (1, 10));
+        assert!(locked.join("node_modules/pkg").exists() && fixable.join("node_modules/.cache").exists());
+
+        // Sem a flag o dry-run continua contando tudo
+        let result = clean_projects(&projects, &CleanOptions { dry_run: true, ..Default::default() });
+        assert!(result.would_fail.is_empty());
+        assert_eq!(result.dirs_removed, 2);
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::set_permissions(fixable.join("node_modules/.cache"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_writable_reports_sticky_parent() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let temp = std::env::temp_dir().join(format!("test_check_writable_sticky_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        // Como em /tmp: todos escrevem na pasta, mas só o dono de cada entrada
+        // (ou o da pasta) a apaga
+        let shared = temp.join("shared");
+        fs::create_dir_all(shared.join("node_modules/pkg")).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        let node_modules = shared.join("node_modules");
+
+        let owner = fs::metadata(&node_modules).unwrap().uid();
+        assert!(probe_removable_as(&node_modules, false, &Identity { uid: owner }).is_ok());
+
+        // Sem a posse da entrada nem da pasta, e sem ser root, a escrita na pasta não basta
+        let stranger = Identity { uid: owner + 1 };
+        let err = probe_removable_as(&node_modules, false, &stranger).unwrap_err();
+        assert_eq!(err.category(), "permission-denied");
+        assert!(err.message().contains("sticky"), "{}", err);
+        assert!(node_modules.join("pkg").exists());
+
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&temp).unwrap();
+    }
 }
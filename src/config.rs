@@ -1,14 +1,19 @@
-use std::path::PathBuf;
-use directories::ProjectDirs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use directories::{BaseDirs, ProjectDirs};
 use serde::Deserialize;
 use std::fs;
 use std::fmt;
 
+use crate::types::DepKind;
+
 #[derive(Debug)]
 pub enum ConfigError {
     NotFound,
     ParseError(PathBuf, String),
     IoError(std::io::Error),
+    /// `--profile` sem `[profiles.<nome>]` correspondente: (nome, perfis existentes).
+    UnknownProfile(String, Vec<String>),
 }
 
 impl fmt::Display for ConfigError {
@@ -17,29 +22,156 @@ impl fmt::Display for ConfigError {
             ConfigError::NotFound => write!(f, "Arquivo de configuração não encontrado"),
             ConfigError::ParseError(path, msg) => write!(f, "Erro de sintaxe no arquivo '{}': {}", path.display(), msg),
             ConfigError::IoError(err) => write!(f, "Erro de I/O: {}", err),
+            ConfigError::UnknownProfile(name, available) if available.is_empty() => {
+                write!(f, "Perfil '{}' não encontrado: a configuração não define nenhum [profiles.<nome>]", name)
+            }
+            ConfigError::UnknownProfile(name, available) => {
+                write!(f, "Perfil '{}' não encontrado. Perfis disponíveis: {}", name, available.join(", "))
+            }
         }
     }
 }
 
 impl std::error::Error for ConfigError {}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct Config {
     pub days: Option<u64>,
     pub excluded_dirs: Option<Vec<String>>,
     pub auto_confirm: Option<bool>,
+    pub confirm_default_yes: Option<bool>,
+    pub mtime_ignore: Option<Vec<String>>,
+    pub jobs: Option<usize>,
+    /// Política de confirmação por tipo (`[confirm]`), ex: `node_modules = "always"`.
+    pub confirm: Option<HashMap<String, ConfirmPolicy>>,
+    /// Perfis nomeados (`[profiles.<nome>]`), escolhidos com `--profile`.
+    pub profiles: Option<HashMap<String, Profile>>,
+}
+
+/// Valores de um perfil: os presentes substituem os do topo do arquivo.
+/// Chaves desconhecidas são erro: um `dayz = 90` ignorado cairia no `days`
+/// do topo, em geral mais agressivo.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub days: Option<u64>,
+    pub excluded_dirs: Option<Vec<String>>,
+    pub auto_confirm: Option<bool>,
+}
+
+/// O que fazer com as pastas de um tipo na hora de confirmar a limpeza.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmPolicy {
+    /// Limpa sem perguntar, mesmo sem `--yes`
+    Always,
+    /// Passa pela confirmação normal (padrão)
+    #[default]
+    Ask,
+    /// Nunca limpa: as pastas saem da lista
+    Never,
+}
+
+/// Seção `[confirm]` já validada: chaves são ids de `DepKind` (os de `--keep`).
+#[derive(Debug, Default)]
+pub struct ConfirmPolicies(HashMap<DepKind, ConfirmPolicy>);
+
+impl ConfirmPolicies {
+    /// Tipos desconhecidos são ignorados com aviso, como as demais chaves inválidas.
+    pub fn from_config(raw: Option<HashMap<String, ConfirmPolicy>>) -> Self {
+        let policies = raw
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(kind, policy)| match kind.parse::<DepKind>() {
+                Ok(kind) => Some((kind, policy)),
+                Err(e) => {
+                    log::warn!("Chave ignorada em [confirm]: {}", e);
+                    None
+                }
+            })
+            .collect();
+        ConfirmPolicies(policies)
+    }
+
+    pub fn for_kind(&self, kind: &DepKind) -> ConfirmPolicy {
+        self.0.get(kind).copied().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
+/// Conteúdo gravado por `--init-config`: todas as chaves, comentadas.
+const DEFAULT_CONFIG: &str = r#"# Configuração do faxina-cli.
+# Flags da linha de comando têm prioridade sobre estes valores.
+
+# Dias sem modificação para um projeto ser considerado inativo (padrão: 30).
+# days = 30
+
+# Pastas ignoradas na varredura. Aceita `~` e variáveis como `$HOME`.
+# excluded_dirs = ["~/trabalho", "$HOME/clientes/ativo"]
+
+# Pula a confirmação antes de limpar (equivale a --yes).
+# auto_confirm = false
+
+# Enter confirma a limpeza em vez de cancelar; a pergunta continua aparecendo
+# (equivale a --confirm-default-yes).
+# confirm_default_yes = false
+
+# Arquivos que não contam para a data de modificação, além de
+# *.log, *.tmp e *.swp (equivale a --mtime-ignore).
+# mtime_ignore = ["*.bak", ".DS_Store"]
+
+# Threads das varreduras; 1 = sem paralelismo (equivale a --jobs).
+# jobs = 4
+
+# Confirmação por tipo de pasta: "always" limpa sem perguntar, "ask" pergunta
+# (padrão) e "never" nunca limpa. Tipos: os mesmos ids de --keep.
+# [confirm]
+# node_modules = "always"
+# target = "ask"
+
+# Perfis escolhidos com --profile <nome>: days, excluded_dirs e auto_confirm
+# de um perfil substituem os valores acima.
+# [profiles.trabalho]
+# days = 60
+# excluded_dirs = ["~/trabalho/cliente-ativo"]
+#
+# [profiles.casa]
+# days = 14
+# auto_confirm = true
+"#;
+
 impl Config {
-    pub fn load() -> Result<Self, ConfigError> {
+    pub fn default_path() -> Result<PathBuf, ConfigError> {
         let proj_dirs = ProjectDirs::from("", "", "faxina-cli")
             .ok_or_else(|| ConfigError::IoError(std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")))?;
-            
-        let config_path = proj_dirs.config_dir().join("config.toml");
-        Self::load_from_path(config_path)
+        Ok(proj_dirs.config_dir().join("config.toml"))
+    }
+
+    pub fn load(profile: Option<&str>) -> Result<Self, ConfigError> {
+        Self::load_from_path(Self::default_path()?, profile)
+    }
+
+    /// Grava a configuração comentada padrão em `path`. Recusa sobrescrever
+    /// um arquivo existente (`ErrorKind::AlreadyExists`) a menos que `force`.
+    pub fn write_default(path: &std::path::Path, force: bool) -> Result<(), ConfigError> {
+        if path.exists() && !force {
+            return Err(ConfigError::IoError(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("'{}' já existe (use --force para sobrescrever)", path.display()),
+            )));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(ConfigError::IoError)?;
+        }
+        fs::write(path, DEFAULT_CONFIG).map_err(ConfigError::IoError)
     }
 
-    pub fn load_from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, ConfigError> {
+    /// Lê a configuração de `path` e, com `profile`, aplica por cima o
+    /// `[profiles.<profile>]` correspondente.
+    pub fn load_from_path<P: AsRef<std::path::Path>>(path: P, profile: Option<&str>) -> Result<Self, ConfigError> {
         let path = path.as_ref();
         if !path.exists() {
              return Err(ConfigError::NotFound);
@@ -48,11 +180,131 @@ impl Config {
         let content = fs::read_to_string(path)
             .map_err(ConfigError::IoError)?;
             
-        match toml::from_str(&content) {
-            Ok(c) => Ok(c),
-            Err(e) => Err(ConfigError::ParseError(path.to_path_buf(), e.to_string())),
+        let mut config: Config = toml::from_str(&content)
+            .map_err(|e| ConfigError::ParseError(path.to_path_buf(), e.to_string()))?;
+        if let Some(name) = profile {
+            config.apply_profile(name)?;
+        }
+        Ok(config)
+    }
+
+    fn apply_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        let profiles = self.profiles.take().unwrap_or_default();
+        let Some(profile) = profiles.get(name).cloned() else {
+            let mut available: Vec<String> = profiles.into_keys().collect();
+            available.sort();
+            return Err(ConfigError::UnknownProfile(name.to_string(), available));
+        };
+        self.days = profile.days.or(self.days);
+        self.excluded_dirs = profile.excluded_dirs.or(self.excluded_dirs.take());
+        self.auto_confirm = profile.auto_confirm.or(self.auto_confirm);
+        self.profiles = Some(profiles);
+        Ok(())
+    }
+}
+
+/// Safe-list padrão: `safe-list.txt` ao lado do config.toml.
+pub fn default_safe_list_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "faxina-cli").map(|d| d.config_dir().join("safe-list.txt"))
+}
+
+/// Lê a safe-list: um caminho por linha, com `~` e variáveis expandidos.
+/// Linhas vazias e comentários (`#`) são ignorados; variáveis indefinidas
+/// descartam a linha com aviso.
+pub fn load_safe_list(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|raw| match expand_path(raw) {
+            Some(p) => Some(PathBuf::from(p)),
+            None => {
+                log::warn!("Variável de ambiente não definida em '{}' (safe-list); linha ignorada.", raw);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Configuração por projeto, na raiz de cada projeto encontrado.
+pub const PROJECT_CONFIG_FILE: &str = ".faxina.toml";
+
+/// `.faxina.toml` de um projeto: vale só para ele e tem precedência sobre a
+/// configuração global e as flags.
+/// Chaves desconhecidas invalidam o arquivo: um `protect = true` digitado
+/// errado protege o projeto em vez de ser ignorado.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Limite de dias de inatividade próprio do projeto.
+    pub days: Option<u64>,
+    /// Nunca limpa o projeto, qualquer que seja a idade.
+    #[serde(default)]
+    pub protected: bool,
+}
+
+/// Lê o `.faxina.toml` de `project_dir`; `None` se não houver. Um arquivo
+/// inválido protege o projeto (com aviso): quem o escreveu queria controlar
+/// a limpeza, e limpar por engano não tem volta.
+pub fn load_project_config(project_dir: &Path) -> Option<ProjectConfig> {
+    let path = project_dir.join(PROJECT_CONFIG_FILE);
+    let content = fs::read_to_string(&path).ok()?;
+    match toml::from_str(&content) {
+        Ok(c) => Some(c),
+        Err(e) => {
+            log::warn!("{} inválido ({}); projeto preservado.", path.display(), e);
+            Some(ProjectConfig { days: None, protected: true })
+        }
+    }
+}
+
+/// Expande `~` inicial e `$VAR`/`${VAR}` num caminho da configuração.
+/// Retorna `None` se alguma variável não estiver definida (o chamador avisa e ignora).
+/// Caminhos sem `~` nem `$` são devolvidos intactos.
+pub fn expand_path(raw: &str) -> Option<String> {
+    let mut out = String::with_capacity(raw.len());
+
+    let rest = if raw == "~" || raw.starts_with("~/") || raw.starts_with("~\\") {
+        let home = BaseDirs::new()?.home_dir().to_string_lossy().to_string();
+        out.push_str(&home);
+        &raw[1..]
+    } else {
+        raw
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        let name: String = if chars.peek() == Some(&'{') {
+            chars.next();
+            let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+            name
+        } else {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    name.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            name
+        };
+
+        if name.is_empty() {
+            out.push('$');
+            continue;
         }
+        out.push_str(&std::env::var(&name).ok()?);
     }
+
+    Some(out)
 }
 
 #[cfg(test)]
@@ -60,6 +312,27 @@ mod tests {
     use super::*;
     use std::io::Write;
 
+    #[test]
+    fn test_confirm_policies_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            [confirm]
+            node_modules = "always"
+            target = "never"
+            bogus = "ask"
+            "#,
+        )
+        .unwrap();
+
+        let policies = ConfirmPolicies::from_config(config.confirm);
+        assert_eq!(policies.for_kind(&DepKind::NodeModules), ConfirmPolicy::Always);
+        assert_eq!(policies.for_kind(&DepKind::Target), ConfirmPolicy::Never);
+        // Sem entrada: confirmação normal
+        assert_eq!(policies.for_kind(&DepKind::Venv), ConfirmPolicy::Ask);
+
+        assert!(toml::from_str::<Config>("[confirm]\ntarget = \"sometimes\"").is_err());
+    }
+
     #[test]
     fn test_load_from_path_valid() {
         let temp_dir = std::env::temp_dir().join(format!("test_config_{}", std::process::id()));
@@ -77,7 +350,7 @@ mod tests {
             f.write_all(content.as_bytes()).unwrap();
         }
 
-        let config = Config::load_from_path(&config_path).unwrap();
+        let config = Config::load_from_path(&config_path, None).unwrap();
         
         assert_eq!(config.days, Some(45));
         assert_eq!(config.auto_confirm, Some(true));
@@ -86,12 +359,99 @@ mod tests {
         fs::remove_dir_all(&temp_dir).unwrap();
     }
 
+    #[test]
+    fn test_profile_overrides_base() {
+        let temp_dir = std::env::temp_dir().join(format!("test_config_profile_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+        let config_path = temp_dir.join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+            days = 30
+            excluded_dirs = ["~/base"]
+            auto_confirm = false
+            jobs = 2
+
+            [profiles.work]
+            days = 90
+            excluded_dirs = ["/srv/clients"]
+
+            [profiles.home]
+            auto_confirm = true
+            "#,
+        )
+        .unwrap();
+
+        // Sem perfil vale o topo do arquivo
+        let base = Config::load_from_path(&config_path, None).unwrap();
+        assert_eq!((base.days, base.auto_confirm), (Some(30), Some(false)));
+
+        // O perfil substitui só o que define
+        let work = Config::load_from_path(&config_path, Some("work")).unwrap();
+        assert_eq!(work.days, Some(90));
+        assert_eq!(work.excluded_dirs, Some(vec!["/srv/clients".to_string()]));
+        assert_eq!(work.auto_confirm, Some(false));
+        assert_eq!(work.jobs, Some(2));
+
+        let home = Config::load_from_path(&config_path, Some("home")).unwrap();
+        assert_eq!(home.days, Some(30));
+        assert_eq!(home.excluded_dirs, Some(vec!["~/base".to_string()]));
+        assert_eq!(home.auto_confirm, Some(true));
+
+        match Config::load_from_path(&config_path, Some("travel")) {
+            Err(e @ ConfigError::UnknownProfile(..)) => {
+                assert_eq!(e.to_string(), "Perfil 'travel' não encontrado. Perfis disponíveis: home, work");
+            }
+            other => panic!("Should reject unknown profile, got {:?}", other),
+        }
+
+        fs::write(&config_path, "days = 7").unwrap();
+        assert!(matches!(
+            Config::load_from_path(&config_path, Some("work")),
+            Err(ConfigError::UnknownProfile(_, available)) if available.is_empty()
+        ));
+
+        fs::write(&config_path, "days = 30\n[profiles.work]\ndayz = 90\n").unwrap();
+        assert!(matches!(
+            Config::load_from_path(&config_path, Some("work")),
+            Err(ConfigError::ParseError(..))
+        ));
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_default_creates_parseable_file() {
+        let temp_dir = std::env::temp_dir().join(format!("test_config_init_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp_dir);
+        let config_path = temp_dir.join("nested/config.toml");
+
+        Config::write_default(&config_path, false).unwrap();
+        let config = Config::load_from_path(&config_path, None).unwrap();
+        assert_eq!(config.days, None);
+        assert!(fs::read_to_string(&config_path).unwrap().contains("excluded_dirs"));
+
+        // Sem --force não sobrescreve
+        fs::write(&config_path, "days = 7").unwrap();
+        match Config::write_default(&config_path, false) {
+            Err(ConfigError::IoError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::AlreadyExists),
+            _ => panic!("Should refuse to overwrite"),
+        }
+        assert_eq!(Config::load_from_path(&config_path, None).unwrap().days, Some(7));
+
+        Config::write_default(&config_path, true).unwrap();
+        assert_eq!(Config::load_from_path(&config_path, None).unwrap().days, None);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
     #[test]
     fn test_load_from_path_missing() {
         let temp_dir = std::env::temp_dir().join(format!("test_config_missing_{}", std::process::id()));
         let config_path = temp_dir.join("non_existent.toml");
         
-        match Config::load_from_path(&config_path) {
+        match Config::load_from_path(&config_path, None) {
             Err(ConfigError::NotFound) => (), // pass
             _ => panic!("Should return NotFound"),
         }
@@ -108,11 +468,31 @@ mod tests {
             f.write_all(b"days = 'invalid_number'").unwrap(); // String instead of int
         }
 
-        match Config::load_from_path(&config_path) {
+        match Config::load_from_path(&config_path, None) {
             Err(ConfigError::ParseError(_, _)) => (), // pass
             _ => panic!("Should return ParseError"),
         }
 
         fs::remove_dir_all(&temp_dir).unwrap();
     }
+
+    #[test]
+    fn test_expand_path_tilde() {
+        let home = BaseDirs::new().unwrap().home_dir().to_string_lossy().to_string();
+        assert_eq!(expand_path("~/archive").unwrap(), format!("{}/archive", home));
+        assert_eq!(expand_path("~").unwrap(), home);
+        // Só `~` no início é expandido
+        assert_eq!(expand_path("/data/~x").unwrap(), "/data/~x");
+    }
+
+    #[test]
+    fn test_expand_path_env_vars() {
+        std::env::set_var("FAXINA_TEST_EXPAND", "/srv/keep");
+        assert_eq!(expand_path("$FAXINA_TEST_EXPAND/a").unwrap(), "/srv/keep/a");
+        assert_eq!(expand_path("${FAXINA_TEST_EXPAND}_b").unwrap(), "/srv/keep_b");
+        assert_eq!(expand_path("/abs/literal").unwrap(), "/abs/literal");
+
+        std::env::remove_var("FAXINA_TEST_EXPAND_UNSET");
+        assert_eq!(expand_path("$FAXINA_TEST_EXPAND_UNSET/x"), None);
+    }
 }
@@ -1,30 +1,235 @@
 use std::collections::HashMap;
-use std::io::IsTerminal;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::time::SystemTime;
 
+use clap::ValueEnum;
 use colored::Colorize;
 use dialoguer::Confirm;
+use indicatif::{ProgressBar, ProgressStyle};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::baseline::{BaselineDiff, BaselineProject};
 use crate::cleaner::CleanResult;
-use crate::types::{StaleProject, DepKind};
+use crate::history::{self, HistoryEntry};
+use crate::projects::{all_project_types, project_types};
+use crate::scanner::{ProjectInfo, ScanCounters};
+use crate::types::{DepDir, DepKind, ProtectionReason, StaleProject};
+
+/// Layout da listagem de projetos (`--format`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Blocos por projeto (padrão)
+    #[default]
+    Text,
+    /// Tabela alinhada, uma linha por projeto
+    Table,
+    /// Documento JSON no stdout (ver `--json-schema`)
+    Json,
+}
+
+/// Barras de progresso e spinners (`--progress`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Mostra no terminal; o indicatif já esconde quando stderr não é um TTY
+    #[default]
+    Auto,
+    /// Nunca desenha (CIs que simulam TTY e poluem o log)
+    Off,
+}
+
+/// Spinner das fases de varredura; oculto (no-op) com `--progress off`.
+pub fn new_spinner(mode: ProgressMode) -> ProgressBar {
+    if mode == ProgressMode::Off {
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::with_template("  {spinner:.green} {msg}")
+            .unwrap()
+            .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
+    );
+    spinner
+}
+
+/// Se a contagem de arquivos da varredura deve ir para o spinner agora. Começa
+/// a cada 10 arquivos e espaça conforme a varredura cresce: em árvores
+/// pequenas a contagem não fica parada em zero, e nas grandes o spinner não
+/// vira gargalo.
+pub fn scan_progress_due(count: usize) -> bool {
+    let step = match count {
+        0..1_000 => 10,
+        1_000..10_000 => 100,
+        _ => 500,
+    };
+    count.is_multiple_of(step)
+}
+
+pub fn scan_progress_message(root: &str, days: &str, files: usize) -> String {
+    format!(
+        "Varrendo {} (projetos inativos há {}+ dias)... {} arquivos",
+        root.bold(),
+        days.bold(),
+        files.to_string().dimmed()
+    )
+}
+
+/// Encerra o spinner da varredura deixando a contagem final na tela (as
+/// atualizações durante a varredura são espaçadas e perdem o total exato).
+pub fn finish_scan_spinner(spinner: &ProgressBar, root: &str, files: usize, visible: bool) {
+    if visible {
+        spinner.finish_with_message(format!(
+            "Varredura de {} concluída: {} arquivos verificados.",
+            root.bold(),
+            files.to_string().bold()
+        ));
+    } else {
+        spinner.finish_and_clear();
+    }
+}
+
+/// Unidade forçada para tamanhos (`--size-unit`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "UPPER")]
+pub enum SizeUnit {
+    /// Escolhe a maior unidade que cabe (padrão)
+    #[default]
+    #[value(name = "auto")]
+    Auto,
+    /// Bytes exatos, sem arredondamento
+    B,
+    KB,
+    MB,
+    GB,
+}
+
+/// Como `format_size` apresenta tamanhos (`--size-unit` / `--size-precision`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeFormat {
+    pub unit: SizeUnit,
+    pub precision: usize,
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        SizeFormat { unit: SizeUnit::Auto, precision: 2 }
+    }
+}
+
+static SIZE_FORMAT: OnceLock<SizeFormat> = OnceLock::new();
+
+/// Define o formato usado por `format_size` no resto da execução.
+/// Chamado uma vez em `main`, antes de qualquer saída.
+pub fn set_size_format(format: SizeFormat) {
+    let _ = SIZE_FORMAT.set(format);
+}
+
+static ASCII_ICONS: OnceLock<bool> = OnceLock::new();
+
+/// Troca os ícones dos tipos por tags ASCII (`[node]`, `[rust]`...) no resto
+/// da execução. Chamado uma vez em `main`, como `set_size_format`.
+pub fn set_ascii_icons(ascii: bool) {
+    let _ = ASCII_ICONS.set(ascii);
+}
+
+/// Sem `--ascii`, detecta terminais que não vão mostrar emoji: saída que não
+/// é um terminal (logs de CI, pipes) ou locale explicitamente não UTF-8.
+/// Sem locale definido, assume UTF-8. `FAXINA_ICONS=emoji` (ou `ascii`)
+/// desliga a detecção, para quem redireciona a saída para um terminal que
+/// mostra emoji.
+pub fn detect_ascii_icons() -> bool {
+    match std::env::var("FAXINA_ICONS").as_deref() {
+        Ok("emoji") => return false,
+        Ok("ascii") => return true,
+        _ => {}
+    }
+    if !std::io::stdout().is_terminal() {
+        return true;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty());
+    locale.is_some_and(|l| {
+        let l = l.to_ascii_lowercase();
+        !(l.contains("utf-8") || l.contains("utf8"))
+    })
+}
+
+/// Ícone do tipo conforme `set_ascii_icons`. Toda a saída de terminal passa
+/// por aqui, em vez de chamar `DepKind::icon()` direto.
+pub fn kind_icon(kind: &DepKind) -> &'static str {
+    if ASCII_ICONS.get().copied().unwrap_or(false) {
+        kind.ascii_tag()
+    } else {
+        kind.icon()
+    }
+}
+
+/// Ícone decorativo das mensagens (`🧹`, `⚠️`, `✗`...) conforme
+/// `set_ascii_icons`, como `kind_icon` faz para os tipos. Toda mensagem com
+/// ícone passa por aqui em vez de embutir o emoji no texto.
+pub fn icon(emoji: &'static str) -> &'static str {
+    if !ASCII_ICONS.get().copied().unwrap_or(false) {
+        return emoji;
+    }
+    match emoji {
+        "🧹" | "✨" => "*",
+        "⚠️" => "[!]",
+        "✗" => "x",
+        "✅" => "[ok]",
+        "→" => "->",
+        "•" => "-",
+        "▸" => ">",
+        "↷" | "↩" | "↩️" => "<-",
+        "📦" | "📥" => "[pkg]",
+        "📂" | "📁" | "🗂️" => "[dir]",
+        "📄" => "[file]",
+        "🕐" => "[time]",
+        "🔍" | "🔎" | "🔬" | "👀" => "[?]",
+        "🔒" | "🛡️" => "[lock]",
+        "🗑️" => "[rm]",
+        "🔁" | "⏯️" => "[retry]",
+        "⏸️" | "🚫" => "[skip]",
+        "✂️" => "[cut]",
+        "🔗" => "[link]",
+        "📊" | "🏋️" | "👴" => "[stats]",
+        "📜" => "[log]",
+        "💾" => "[save]",
+        "🎉" => "",
+        other => other,
+    }
+}
 
 pub fn format_size(bytes: u64) -> String {
+    format_size_with(bytes, SIZE_FORMAT.get().copied().unwrap_or_default())
+}
+
+pub fn format_size_with(bytes: u64, format: SizeFormat) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
     const GB: u64 = 1024 * MB;
 
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+    let unit = match format.unit {
+        SizeUnit::Auto if bytes >= GB => SizeUnit::GB,
+        SizeUnit::Auto if bytes >= MB => SizeUnit::MB,
+        SizeUnit::Auto if bytes >= KB => SizeUnit::KB,
+        SizeUnit::Auto => SizeUnit::B,
+        forced => forced,
+    };
+    let precision = format.precision;
+
+    match unit {
+        SizeUnit::GB => format!("{:.*} GB", precision, bytes as f64 / GB as f64),
+        SizeUnit::MB => format!("{:.*} MB", precision, bytes as f64 / MB as f64),
+        SizeUnit::KB => format!("{:.*} KB", precision, bytes as f64 / KB as f64),
+        _ => format!("{} B", bytes),
     }
 }
 
-fn days_ago(time: SystemTime) -> String {
+pub fn days_ago(time: SystemTime) -> String {
     match SystemTime::now().duration_since(time) {
         Ok(duration) => {
             let days = duration.as_secs() / (24 * 3600);
@@ -44,7 +249,7 @@ pub fn print_header() {
     println!();
     println!(
         "{}",
-        "  🧹 Faxina CLI — Lixeiro Inteligente de Projetos"
+        format!("  {} Faxina CLI — Lixeiro Inteligente de Projetos", icon("🧹"))
             .bold()
             .cyan()
     );
@@ -56,54 +261,496 @@ pub fn print_header() {
     println!();
 }
 
-pub fn print_scan_results(projects: &[StaleProject]) {
+pub fn print_prune_warning(names: &[String]) {
+    println!(
+        "  {} {} qualquer pasta chamada {} será tratada como descartável, sem checar o tipo de projeto.",
+        icon("⚠️").yellow(),
+        "ATENÇÃO:".bold().yellow(),
+        names.iter().map(|n| format!("'{}'", n)).collect::<Vec<_>>().join(", ").bold()
+    );
+    println!();
+}
+
+/// Converte tamanhos como `500K`, `120MB` ou `1.5G` (base 1024) em bytes.
+pub fn parse_size(raw: &str) -> Result<u64, String> {
+    let trimmed = raw.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("Tamanho inválido: '{}'", raw))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        "T" | "TB" => 1024 * 1024 * 1024 * 1024,
+        other => return Err(format!("Unidade desconhecida '{}' em '{}'", other, raw)),
+    };
+
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Separa os projetos listados individualmente dos que ficam abaixo do limite
+/// de `--collapse-below`. Retorna os visíveis, a quantidade e o total agrupados.
+pub fn collapse_small(
+    projects: &[StaleProject],
+    collapse_below: Option<u64>,
+) -> (Vec<&StaleProject>, usize, u64) {
+    let Some(limit) = collapse_below else {
+        return (projects.iter().collect(), 0, 0);
+    };
+
+    let (shown, collapsed): (Vec<&StaleProject>, Vec<&StaleProject>) =
+        projects.iter().partition(|p| p.total_size() >= limit);
+    let collapsed_size = collapsed.iter().map(|p| p.total_size()).sum();
+
+    (shown, collapsed.len(), collapsed_size)
+}
+
+/// Acima disso a contagem de `--show-counts` aparece em destaque: pastas com
+/// muitos arquivos pequenos são lentas de apagar e consomem inodes.
+const HUGE_FILE_COUNT: u64 = 100_000;
+
+/// Contagem de arquivos de `--show-counts`, ex: ` (12.345 arquivos)`.
+fn file_count_label(files: u64) -> String {
+    let digits = files.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(c);
+    }
+    let label = format!("({} arquivos)", grouped);
+    if files >= HUGE_FILE_COUNT {
+        format!(" {}", label.yellow().bold())
+    } else {
+        format!(" {}", label.dimmed())
+    }
+}
+
+pub fn print_scan_results(projects: &[StaleProject], collapse_below: Option<u64>, show_counts: bool, show_newest: bool) {
     let total_size: u64 = projects.iter().map(|p| p.total_size()).sum();
-    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs.len()).sum();
+    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
 
     println!(
         "  {} {} projetos inativos encontrados ({} pastas, {})",
-        "📦".to_string(),
+        icon("📦"),
         projects.len().to_string().bold().yellow(),
         total_dirs.to_string().bold(),
         format_size(total_size).bold().red()
     );
     println!();
 
-    for project in projects {
+    let (shown, collapsed_count, collapsed_size) = collapse_small(projects, collapse_below);
+
+    for project in shown {
         println!(
             "  {} {}",
-            "▸".bold().cyan(),
+            icon("▸").bold().cyan(),
             project.name.bold().white()
         );
         println!(
             "    {}  {}",
-            "📂".to_string(),
+            icon("📂"),
             project.path.display().to_string().dimmed()
         );
         println!(
             "    {}  Última modificação: {}",
-            "🕐".to_string(),
+            icon("🕐"),
             days_ago(project.last_modified).yellow()
         );
+        if show_newest {
+            if let Some(newest) = project.newest_file() {
+                // Relativo ao projeto: o caminho completo já está na linha 📂
+                let shown = newest.strip_prefix(&project.path).unwrap_or(newest);
+                let shown = if shown.as_os_str().is_empty() { Path::new(".") } else { shown };
+                println!("    {}  Arquivo mais recente: {}", icon("📄"), shown.display().to_string().dimmed());
+            }
+        }
 
-        for dep in &project.dep_dirs {
+        for dep in project.dep_dirs() {
             println!(
-                "    {} {} {}",
-                dep.kind.icon(),
+                "    {} {} {}{}",
+                kind_icon(&dep.kind),
                 dep.kind.to_string().bold(),
-                format_size(dep.size).red()
+                format_size(dep.size).red(),
+                if show_counts { file_count_label(dep.files) } else { String::new() }
             );
         }
 
         println!();
     }
+
+    if collapsed_count > 0 {
+        println!(
+            "  {} …e mais {} projetos menores somando {}",
+            icon("▸").bold().cyan(),
+            collapsed_count.to_string().bold(),
+            format_size(collapsed_size).red()
+        );
+        println!();
+    }
 }
 
-pub fn print_stats(projects: &[StaleProject]) {
+/// Listagem de `--flatten`: uma linha por pasta de dependência, com o projeto dono.
+pub fn print_flat_results(projects: &[StaleProject], show_counts: bool) {
+    let total_size: u64 = projects.iter().map(|p| p.total_size()).sum();
+    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+
+    println!(
+        "  {} {} pastas de dependência em {} projetos inativos ({})",
+        icon("📦"),
+        total_dirs.to_string().bold().yellow(),
+        projects.len().to_string().bold(),
+        format_size(total_size).bold().red()
+    );
+    println!();
+
+    for project in projects {
+        for dep in project.dep_dirs() {
+            println!(
+                "  {} {:>10}  {}  {}{}",
+                kind_icon(&dep.kind),
+                format_size(dep.size).red(),
+                dep.path.display(),
+                format!("({}, {})", project.name, days_ago(project.last_modified)).dimmed(),
+                if show_counts { file_count_label(dep.files) } else { String::new() }
+            );
+        }
+    }
+    println!();
+}
+
+/// Largura usada quando o terminal não informa a sua (ex: saída redirecionada).
+const DEFAULT_TABLE_WIDTH: usize = 120;
+
+fn pad(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+/// Corta o início do texto com `…` para caber em `width` colunas,
+/// preservando o final (a parte mais informativa de um caminho).
+fn truncate_start(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut kept: Vec<char> = Vec::new();
+    let mut used = 1; // `…`
+    for c in text.chars().rev() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        kept.push(c);
+    }
+    kept.reverse();
+    format!("…{}", kept.into_iter().collect::<String>())
+}
+
+/// Monta a tabela de `--format table`, ajustando a coluna de caminho à largura.
+pub fn render_table(projects: &[StaleProject], width: usize) -> String {
+    let rows: Vec<Vec<String>> = projects
+        .iter()
+        .map(|p| {
+            let kinds: Vec<String> = p
+                .dep_dirs()
+                .iter()
+                .map(|d| format!("{} {}", kind_icon(&d.kind).trim_end(), d.kind))
+                .collect();
+            vec![
+                p.name.clone(),
+                p.path.display().to_string(),
+                kinds.join(", "),
+                format_size(p.total_size()),
+                days_ago(p.last_modified),
+            ]
+        })
+        .collect();
+
+    render_grid(&["Projeto", "Caminho", "Tipos", "Tamanho", "Idade"], rows, 1, width)
+}
+
+/// Tabela de `--format table --flatten`: uma linha por pasta de dependência.
+pub fn render_flat_table(projects: &[StaleProject], width: usize) -> String {
+    let rows: Vec<Vec<String>> = projects
+        .iter()
+        .flat_map(|p| {
+            p.dep_dirs().iter().map(move |d| {
+                vec![
+                    d.path.display().to_string(),
+                    format!("{} {}", kind_icon(&d.kind).trim_end(), d.kind),
+                    p.name.clone(),
+                    format_size(d.size),
+                    days_ago(p.last_modified),
+                ]
+            })
+        })
+        .collect();
+
+    render_grid(&["Pasta", "Tipo", "Projeto", "Tamanho", "Idade"], rows, 0, width)
+}
+
+/// Alinha as colunas; só a coluna `flex` (um caminho) encolhe para caber em `width`.
+fn render_grid(headers: &[&str], rows: Vec<Vec<String>>, flex: usize, width: usize) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.width()).collect();
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.width());
+        }
+    }
+
+    // Recuo de 2 + separadores de 2 entre as colunas
+    let fixed: usize = 2
+        + 2 * (headers.len() - 1)
+        + widths.iter().enumerate().filter(|(i, _)| *i != flex).map(|(_, w)| w).sum::<usize>();
+    widths[flex] = widths[flex].min(width.saturating_sub(fixed).max(headers[flex].width()));
+
+    let mut out = String::new();
+    let line = |cells: Vec<String>| -> String {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(c, w)| pad(c, *w)).collect();
+        format!("  {}\n", padded.join("  ").trim_end())
+    };
+
+    out.push_str(&line(headers.iter().map(|h| h.to_string()).collect()));
+    out.push_str(&line(widths.iter().map(|w| "─".repeat(*w)).collect()));
+    for mut cells in rows {
+        cells[flex] = truncate_start(&cells[flex], widths[flex]);
+        out.push_str(&line(cells));
+    }
+    out
+}
+
+/// Tabela de `--list-types`, gerada a partir dos tipos registrados: o que a
+/// varredura detecta, com os mesmos nomes e manifestos que ela usa.
+pub fn render_project_types() -> String {
+    let default_kinds: Vec<DepKind> = project_types(false).iter().map(|t| t.dep_kind()).collect();
+    let rows = all_project_types()
+        .iter()
+        .map(|t| {
+            let kind = t.dep_kind();
+            let mut name = format!("{} {}", kind_icon(&kind).trim_end(), t.name());
+            if !default_kinds.contains(&kind) {
+                name.push_str(" (--include-dist)");
+            }
+            let dirs: Vec<String> = t.dir_labels().iter().map(|n| format!("{}/", n)).collect();
+            vec![name, dirs.join(", "), t.detection_markers().join(", "), kind.as_str().to_string()]
+        })
+        .collect();
+    render_grid(&["Tipo", "Pastas", "Detectado por", "Id (--keep)"], rows, 2, usize::MAX)
+}
+
+pub fn print_table(projects: &[StaleProject], flatten: bool) {
+    let total_size: u64 = projects.iter().map(|p| p.total_size()).sum();
+    let width = terminal_size::terminal_size()
+        .map(|(w, _)| w.0 as usize)
+        .unwrap_or(DEFAULT_TABLE_WIDTH);
+
+    println!(
+        "  {} {} projetos inativos encontrados ({})",
+        icon("📦"),
+        projects.len().to_string().bold().yellow(),
+        format_size(total_size).bold().red()
+    );
+    println!();
+    if flatten {
+        print!("{}", render_flat_table(projects, width));
+    } else {
+        print!("{}", render_table(projects, width));
+    }
+    println!();
+}
+
+/// Inventário compacto de `--list-all`: um projeto por linha, sem tamanhos.
+pub fn print_project_list(projects: &[ProjectInfo]) {
+    println!(
+        "  {} {} projetos detectados",
+        icon("🗂️"),
+        projects.len().to_string().bold()
+    );
+    println!();
+
+    for project in projects {
+        let kinds: Vec<String> = project.deps.iter()
+            .map(|d| format!("{} {}", kind_icon(&d.kind), d.kind))
+            .collect();
+        println!(
+            "  {} {}  {}  {}",
+            icon("▸").bold().cyan(),
+            project.path.display().to_string().bold(),
+            kinds.join(", "),
+            days_ago(project.last_modified).dimmed()
+        );
+    }
+    println!();
+}
+
+/// Aviso de varredura incompleta; com `verbose` lista cada pasta e o erro.
+pub fn print_unreadable(unreadable: &[(PathBuf, String)], verbose: bool) {
+    if unreadable.is_empty() {
+        return;
+    }
+
+    println!(
+        "  {}  {} pastas não puderam ser lidas; a varredura pode estar incompleta{}",
+        icon("⚠️"),
+        unreadable.len().to_string().bold().yellow(),
+        if verbose { ":" } else { " (use -v para ver os caminhos)." }
+    );
+    if verbose {
+        for (path, err) in unreadable {
+            println!("    {} {} — {}", icon("✗").red(), path.display(), err.dimmed());
+        }
+    }
+    println!();
+}
+
+/// Pastas inativas preservadas pela safe-list; com `verbose`, cada caminho.
+pub fn print_safe_listed(paths: &[PathBuf], verbose: bool) {
+    if paths.is_empty() {
+        return;
+    }
+
+    println!(
+        "  {}  {} caminhos protegidos pela safe-list{}",
+        icon("🛡️"),
+        paths.len().to_string().bold().cyan(),
+        if verbose { ":" } else { "." }
+    );
+    if verbose {
+        for path in paths {
+            println!("    {}", path.display().to_string().dimmed());
+        }
+    }
+    println!();
+}
+
+/// Decisões da varredura (`-v`): cada projeto encontrado e a classificação.
+/// Com `-vv` (`level >= 2`), também o motivo da proteção e o mtime calculado.
+/// De um projeto ativo o mtime é só o do primeiro arquivo recente achado (a
+/// leitura para ali), então aparece como limite inferior.
+pub fn print_scan_decisions(stale: &[StaleProject], protected: &[StaleProject], level: u8) {
+    let mut decisions: Vec<&StaleProject> = stale.iter().chain(protected).collect();
+    if decisions.is_empty() {
+        return;
+    }
+    decisions.sort_by(|a, b| a.path.cmp(&b.path));
+
+    println!("  {} {} projetos avaliados:", icon("🔎"), decisions.len().to_string().bold());
+    for project in decisions {
+        let verdict = match project.is_protected_reason() {
+            None => "inativo".yellow().to_string(),
+            Some(ProtectionReason::ActiveSelf) => "ativo".green().to_string(),
+            Some(_) => "preservado".cyan().to_string(),
+        };
+        let mut line = format!("    {} {}", verdict, project.path.display());
+        if level >= 2 {
+            let mtime = jiff::Timestamp::try_from(project.last_modified)
+                .map(|t| t.to_zoned(jiff::tz::TimeZone::system()).strftime("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| days_ago(project.last_modified));
+            let detail = match project.is_protected_reason() {
+                Some(reason @ ProtectionReason::ActiveSelf) => format!("motivo: {}, modificado em {} ou depois", reason, mtime),
+                Some(reason) => format!("motivo: {}, modificado em {}", reason, mtime),
+                None => format!("modificado em {}", mtime),
+            };
+            line.push_str(&format!(" {}", format!("({})", detail).dimmed()));
+        }
+        println!("{}", line);
+    }
+    println!();
+}
+
+/// Caminhos das pastas de dependência separados por NUL (`--print0`), para
+/// `xargs -0`. No Unix saem os bytes crus: nomes fora de UTF-8 chegam intactos.
+pub fn print_null_separated(projects: &[StaleProject]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    for dir in projects.iter().flat_map(|p| p.dep_dirs()) {
+        #[cfg(unix)]
+        out.write_all(std::os::unix::ffi::OsStrExt::as_bytes(dir.path.as_os_str()))?;
+        #[cfg(not(unix))]
+        out.write_all(dir.path.to_string_lossy().as_bytes())?;
+        out.write_all(b"\0")?;
+    }
+    out.flush()
+}
+
+pub fn print_sizes(sizes: &[(PathBuf, u64)]) {
+    for (path, size) in sizes {
+        println!("  {:>10}  {}", format_size(*size).bold(), path.display());
+    }
+}
+
+pub fn print_kept_recent(kept: &[StaleProject]) {
+    if kept.is_empty() {
+        return;
+    }
+
+    println!(
+        "  {} {} projetos mantidos (recentes):",
+        icon("🔒"),
+        kept.len().to_string().bold()
+    );
+    for project in kept {
+        println!(
+            "    {} {} ({})",
+            icon("•").dimmed(),
+            project.name.bold(),
+            days_ago(project.last_modified).dimmed()
+        );
+    }
+    println!();
+}
+
+/// Aviso de `--max-results`: a lista (e a limpeza) ficou só com os maiores.
+pub fn print_truncated(shown: usize, total: usize, dry_run: bool) {
+    println!(
+        "  {}  {} os {} maiores de {} projetos (--max-results); os demais {}.",
+        icon("✂️"),
+        if dry_run { "Mostrando" } else { "Mostrando e limpando" },
+        shown.to_string().bold().yellow(),
+        total.to_string().bold(),
+        if dry_run { "ficam de fora" } else { "não serão removidos" }
+    );
+    println!();
+}
+
+/// Seção de `--max-size`: projetos fora da limpeza por serem grandes demais.
+pub fn print_oversized(oversized: &[StaleProject], limit: u64) {
+    if oversized.is_empty() {
+        return;
+    }
+
+    println!(
+        "  {}  {} projetos ignorados por passarem de {} (revise manualmente):",
+        icon("⚠️"),
+        oversized.len().to_string().bold().yellow(),
+        format_size(limit).bold()
+    );
+    for project in oversized {
+        println!(
+            "    {} {} {}  {}",
+            icon("•").dimmed(),
+            project.name.bold(),
+            // A medição para ao passar do limite: o tamanho é um piso
+            format!("≥ {}", format_size(project.total_size())).red(),
+            project.path.display().to_string().dimmed()
+        );
+    }
+    println!();
+}
+
+/// Agrega (tipo, quantidade de pastas, bytes) ordenado por tamanho decrescente.
+pub fn kind_stats(projects: &[StaleProject]) -> Vec<(DepKind, usize, u64)> {
     let mut stats: HashMap<DepKind, (usize, u64)> = HashMap::new();
 
     for project in projects {
-        for dep in &project.dep_dirs {
+        for dep in project.dep_dirs() {
             let entry = stats.entry(dep.kind.clone()).or_insert((0, 0));
             entry.0 += 1;
             entry.1 += dep.size;
@@ -117,16 +764,118 @@ pub fn print_stats(projects: &[StaleProject]) {
         .collect();
 
     // Sort by size (descending)
-    stats_vec.sort_by(|a, b| b.2.cmp(&a.2));
+    stats_vec.sort_by_key(|s| std::cmp::Reverse(s.2));
+    stats_vec
+}
+
+/// Grupo de `--group-by-depth` dos projetos acima da profundidade pedida.
+pub const ROOT_GROUP: &str = "(raiz)";
 
+/// Agrega (grupo, projetos, bytes) por pasta a `depth` níveis abaixo da raiz
+/// da varredura (`--group-by-depth`), ordenado por tamanho decrescente.
+/// Com `depth = 1`, `~/code/acme/api` e `~/code/acme/web` caem em `acme`.
+pub fn depth_groups(projects: &[StaleProject], roots: &[PathBuf], depth: usize) -> Vec<(String, usize, u64)> {
+    let mut groups: HashMap<String, (usize, u64)> = HashMap::new();
+
+    for project in projects {
+        let relative = roots.iter().find_map(|r| project.path.strip_prefix(r).ok());
+        let parts: Vec<String> = relative
+            .map(|rel| rel.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        let group = if parts.len() < depth { ROOT_GROUP.to_string() } else { parts[..depth].join("/") };
+        let entry = groups.entry(group).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += project.total_size();
+    }
+
+    let mut groups: Vec<(String, usize, u64)> =
+        groups.into_iter().map(|(name, (count, size))| (name, count, size)).collect();
+    groups.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+pub fn print_depth_groups(projects: &[StaleProject], roots: &[PathBuf], depth: usize) {
+    let groups = depth_groups(projects, roots, depth);
+    let width = groups.iter().map(|(name, _, _)| name.width()).max().unwrap_or(0);
+
+    println!();
+    println!("  {}", format!("{} Por pasta (profundidade {}):", icon("📁"), depth).bold().yellow());
+    println!();
+    for (name, count, size) in groups {
+        println!(
+            "  {} {} projetos, {}",
+            pad(&name, width).bold(),
+            count.to_string().bold().cyan(),
+            format_size(size).red()
+        );
+    }
+    println!();
+}
+
+/// Saída de `--compare-baseline`.
+pub fn print_baseline_diff(diff: &BaselineDiff) {
+    println!("  {} {}", icon("📊"), "Comparação com o baseline:".bold());
+    if diff.is_empty() {
+        println!("    Nada mudou desde o baseline.");
+        println!();
+        return;
+    }
+
+    let section = |title: String, list: &[BaselineProject]| {
+        if list.is_empty() {
+            return;
+        }
+        println!("    {}", title);
+        for p in list {
+            println!("      {} {} ({})", icon("•").dimmed(), p.path.display(), format_size(p.size));
+        }
+    };
+    section(
+        format!("{} novos inativos:", diff.newly_stale.len().to_string().bold().yellow()),
+        &diff.newly_stale,
+    );
+    section(
+        format!("{} voltaram a ser usados:", diff.active_again.len().to_string().bold().green()),
+        &diff.active_again,
+    );
+    section(
+        format!("{} sem pastas de dependência (limpos ou apagados):", diff.gone.len().to_string().bold()),
+        &diff.gone,
+    );
+
+    if !diff.resized.is_empty() {
+        println!("    {} mudaram de tamanho:", diff.resized.len().to_string().bold());
+        for (p, before) in &diff.resized {
+            let delta = if p.size > *before {
+                format!("+{}", format_size(p.size - before)).red()
+            } else {
+                format!("-{}", format_size(before - p.size)).green()
+            };
+            println!(
+                "      {} {} {} {} {} ({})",
+                icon("•").dimmed(),
+                p.path.display(),
+                format_size(*before),
+                icon("→"),
+                format_size(p.size),
+                delta
+            );
+        }
+    }
     println!();
-    println!("  {}", "📊 Estatísticas por Tipo de Projeto:".bold().yellow());
+}
+
+pub fn print_stats(projects: &[StaleProject]) {
+    let stats_vec = kind_stats(projects);
+
+    println!();
+    println!("  {}", format!("{} Estatísticas por Tipo de Projeto:", icon("📊")).bold().yellow());
     println!();
 
     for (kind, count, size) in stats_vec {
         println!(
             "  {} {:<15} {} projetos, {}",
-            kind.icon(),
+            kind_icon(&kind),
             kind.to_string().bold(),
             count.to_string().bold().cyan(),
             format_size(size).red()
@@ -140,16 +889,16 @@ pub fn print_stats(projects: &[StaleProject]) {
 
     if let Some(h) = heaviest {
         println!(
-            "  {} Projeto mais pesado: {} ({})", 
-            "🏋️".to_string(),
+            "  {} Projeto mais pesado: {} ({})",
+            icon("🏋️"),
             h.name.bold().white(),
             format_size(h.total_size()).bold().red()
         );
     }
     if let Some(o) = oldest {
         println!(
-            "  {} Projeto mais antigo: {} ({})", 
-            "👴".to_string(),
+            "  {} Projeto mais antigo: {} ({})",
+            icon("👴"),
             o.name.bold().white(),
             days_ago(o.last_modified).bold().yellow()
         );
@@ -157,54 +906,208 @@ pub fn print_stats(projects: &[StaleProject]) {
     println!();
 }
 
-pub fn confirm_cleanup(dry_run: bool) -> bool {
-    if dry_run {
+/// Seção do `--stats` com o que a varredura preservou, por motivo.
+pub fn print_protection_stats(protected: &[StaleProject]) {
+    if protected.is_empty() {
+        return;
+    }
+
+    let mut by_reason: HashMap<ProtectionReason, (usize, u64)> = HashMap::new();
+    for project in protected {
+        if let Some(reason) = project.is_protected_reason() {
+            let entry = by_reason.entry(reason).or_default();
+            entry.0 += 1;
+            entry.1 += project.total_size();
+        }
+    }
+    let mut rows: Vec<_> = by_reason.into_iter().collect();
+    rows.sort_by_key(|(reason, _)| *reason);
+
+    println!("  {}", format!("{}  Projetos preservados:", icon("🛡️")).bold().yellow());
+    println!();
+    for (reason, (count, size)) in rows {
         println!(
-            "  {}",
-            "🔍 Modo dry-run: nenhum arquivo será deletado."
-                .bold()
-                .blue()
+            "  {:<36} {} projetos, {}",
+            reason.to_string().bold(),
+            count.to_string().bold().cyan(),
+            format_size(size).red()
         );
+    }
+    let total: u64 = protected.iter().map(|p| p.total_size()).sum();
+    println!();
+    println!("  Total preservado: {}", format_size(total).bold().red());
+    println!();
+}
+
+/// Limite de nomes listados por pasta no `--preview`.
+const PREVIEW_ENTRIES: usize = 20;
+/// Quantas das maiores pastas são pré-visualizadas.
+const PREVIEW_DIRS: usize = 3;
+
+/// Lê um único nível de `path` (sem caminhar a árvore) e devolve até `cap`
+/// nomes ordenados, junto com o total de entradas.
+pub fn list_top_level(path: &Path, cap: usize) -> (Vec<String>, usize) {
+    let mut names: Vec<String> = match fs::read_dir(path) {
+        Ok(rd) => rd
+            .filter_map(|e| e.ok())
+            .map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                if e.file_type().map(|t| t.is_dir()).unwrap_or(false) { format!("{}/", name) } else { name }
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    let total = names.len();
+    names.sort();
+    names.truncate(cap);
+    (names, total)
+}
+
+/// Mostra o conteúdo de primeiro nível das maiores pastas antes da confirmação.
+pub fn print_preview(projects: &[StaleProject]) {
+    let mut deps: Vec<&DepDir> = projects.iter().flat_map(|p| p.dep_dirs()).collect();
+    deps.sort_by_key(|d| std::cmp::Reverse(d.size));
+
+    println!("  {}", format!("{} Prévia das maiores pastas:", icon("👀")).bold());
+    for dep in deps.into_iter().take(PREVIEW_DIRS) {
+        let (names, total) = list_top_level(&dep.path, PREVIEW_ENTRIES);
         println!();
-        return true;
+        println!(
+            "    {} {} ({})",
+            kind_icon(&dep.kind),
+            dep.path.display().to_string().bold(),
+            format_size(dep.size).red()
+        );
+        for name in &names {
+            println!("      {}", name.dimmed());
+        }
+        if total > names.len() {
+            println!("      {}", format!("… e mais {} itens", total - names.len()).dimmed());
+        }
+    }
+    println!();
+}
+
+/// Detalhamento do `--dry-run --verbose`: cada pasta com seu tamanho e o
+/// subtotal por projeto.
+pub fn print_dry_run_breakdown(projects: &[StaleProject]) {
+    for project in projects {
+        println!("  {} {}", icon("▸").bold().cyan(), project.name.bold().white());
+        for dep in project.dep_dirs() {
+            println!(
+                "    {} {} {}",
+                icon("→"),
+                dep.path.display(),
+                format_size(dep.size).red()
+            );
+        }
+        println!(
+            "    {} {}",
+            "Subtotal:".dimmed(),
+            format_size(project.total_size()).bold().red()
+        );
     }
+    println!();
+}
+
+/// Aviso de `--ensure-free`: nem limpando tudo o disco chega à meta.
+pub fn print_free_space_shortfall(free: u64, reclaimable: u64, target: u64) {
+    eprintln!(
+        "  {} A limpeza não alcança a meta de {} livres: {} livres + {} recuperáveis = {}.",
+        icon("⚠️").yellow(),
+        format_size(target).bold(),
+        format_size(free),
+        format_size(reclaimable),
+        format_size(free.saturating_add(reclaimable)).bold().yellow()
+    );
+}
 
-    if !std::io::stdin().is_terminal() {
+/// `--ensure-free`: segue mesmo sem alcançar a meta? Sem terminal, não segue.
+pub fn confirm_short_of_goal() -> bool {
+    std::io::stdin().is_terminal()
+        && Confirm::new()
+            .with_prompt("  Limpar mesmo assim?")
+            .default(false)
+            .interact()
+            .unwrap_or(false)
+}
+
+/// Pergunta se a limpeza pode seguir. `default_yes` (`--confirm-default-yes`)
+/// faz o Enter confirmar em vez de cancelar.
+pub fn confirm_cleanup(default_yes: bool) -> bool {
+    confirm_cleanup_with(default_yes, std::io::stdin().is_terminal(), |default| {
+        Confirm::new()
+            .with_prompt(format!("  {}  Deseja remover essas pastas de dependência?", icon("🗑️")))
+            .default(default)
+            .interact()
+            .unwrap_or(false)
+    })
+}
+
+/// `confirm_cleanup` sem o terminal: `ask` recebe o padrão do prompt.
+fn confirm_cleanup_with<F>(default_yes: bool, interactive: bool, ask: F) -> bool
+where
+    F: FnOnce(bool) -> bool,
+{
+    // Sem terminal não há quem aperte Enter: o padrão nunca vale como resposta
+    if !interactive {
         eprintln!(
             "  {} Stdin não é interativo. Use a flag {} para pular confirmação.",
-            "✗".red().bold(),
+            icon("✗").red().bold(),
             "--yes".bold()
         );
         return false;
     }
 
-    Confirm::new()
-        .with_prompt("  🗑️  Deseja remover essas pastas de dependência?")
-        .default(false)
-        .interact()
-        .unwrap_or(false)
+    ask(default_yes)
 }
 
-pub fn print_summary(result: &CleanResult, dry_run: bool, quiet: bool) {
+/// Última linha antes da barra de progresso: o que foi aprovado para remoção.
+pub fn print_cleanup_recap(projects: &[StaleProject]) {
+    let total_size: u64 = projects.iter().map(|p| p.total_size()).sum();
+    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+    println!(
+        "  {}  Removendo {} pastas de {} projetos ({})...",
+        icon("🗑️"),
+        total_dirs.to_string().bold(),
+        projects.len().to_string().bold(),
+        format_size(total_size).bold().red()
+    );
+    println!();
+}
+
+/// `moved_to`: destino de `--move-to`; o resumo fala em bytes movidos.
+/// Não é chamada com `--format json`, que vence `--quiet` (ver `run`).
+pub fn print_summary(result: &CleanResult, dry_run: bool, quiet: bool, moved_to: Option<&Path>) {
     if quiet {
-        println!("{}", format_size(result.total_freed));
+        println!("{}", format_size(result.total_freed + result.total_moved));
         return;
     }
 
 
     println!();
 
-    if dry_run {
+    if let Some(archive) = moved_to {
+        println!(
+            "  {} {} {} {} de {} pastas para {}.",
+            icon("📦"),
+            if dry_run { "Simulação concluída." } else { "Limpeza concluída." }.bold().green(),
+            format_size(result.total_moved).bold().green(),
+            if dry_run { "seriam movidos" } else { "movidos" },
+            result.dirs_removed.to_string().bold(),
+            archive.display().to_string().bold()
+        );
+    } else if dry_run {
         println!(
             "  {} Simulação concluída. {} seriam liberados de {} pastas.",
-            "🔍".to_string(),
+            icon("🔍"),
             format_size(result.total_freed).bold().green(),
             result.dirs_removed.to_string().bold()
         );
     } else {
         println!(
             "  {} {} {} liberados!",
-            "🧹".to_string(),
+            icon("🧹"),
             "Limpeza concluída.".bold().green(),
             format_size(result.total_freed).bold().green()
         );
@@ -214,31 +1117,192 @@ pub fn print_summary(result: &CleanResult, dry_run: bool, quiet: bool) {
         );
     }
 
+    if result.passes > 1 {
+        println!(
+            "    {} {} passadas (--repeat): as pastas que falharam foram tentadas de novo.",
+            icon("🔁"),
+            result.passes.to_string().bold()
+        );
+    }
+
+    if !result.empty_parents_removed.is_empty() {
+        println!(
+            "    {} {} diretórios que ficaram vazios também removidos (--prune-empty-parents).",
+            icon("🗑️"),
+            result.empty_parents_removed.len().to_string().bold()
+        );
+    }
+
+    if !result.links_removed.is_empty() {
+        println!(
+            "    {} {} symlinks {} (destino preservado).",
+            icon("🔗"),
+            result.links_removed.len().to_string().bold(),
+            if dry_run { "seriam removidos" } else { "removidos" }
+        );
+    }
+
+    if !result.lockfiles_removed.is_empty() {
+        println!(
+            "    {} {} lockfiles {} (--remove-lockfiles).",
+            icon("🔒"),
+            result.lockfiles_removed.len().to_string().bold(),
+            if dry_run { "seriam removidos" } else { "removidos" }
+        );
+        for path in &result.lockfiles_removed {
+            println!("      {} {}", icon("↷").dimmed(), path.display());
+        }
+    }
+
+    if !result.skipped_open.is_empty() {
+        println!(
+            "    {}  {} pastas puladas por estarem em uso (--protect-open).",
+            icon("⏸️"),
+            result.skipped_open.len().to_string().bold()
+        );
+    }
+
+    if !result.marker_missing.is_empty() {
+        println!();
+        println!(
+            "  {} {} pastas puladas: o marcador do projeto sumiu desde a varredura (--verify-markers):",
+            icon("🔎"),
+            result.marker_missing.len().to_string().bold().yellow()
+        );
+        for path in &result.marker_missing {
+            println!("    {} {}", icon("↷").dimmed(), path.display());
+        }
+    }
+
+    if !result.would_fail.is_empty() {
+        println!();
+        println!(
+            "  {} {} pastas provavelmente falhariam ao remover (--check-writable):",
+            icon("⚠️"),
+            result.would_fail.len().to_string().bold().red()
+        );
+        for (path, err) in &result.would_fail {
+            println!("    {} {} [{}] — {}", icon("✗").red(), path.display(), err.label().yellow(), err);
+        }
+    }
+
     if !result.errors.is_empty() {
         println!();
         println!(
             "  {} {} erros durante a limpeza:",
-            "⚠️".to_string(),
+            icon("⚠️"),
             result.errors.len().to_string().bold().red()
         );
         for (path, err) in &result.errors {
-            println!("    {} {} — {}", "✗".red(), path.display(), err);
+            println!("    {} {} [{}] — {}", icon("✗").red(), path.display(), err.label().yellow(), err);
+        }
+    }
+
+    if !result.partial.is_empty() {
+        println!();
+        println!(
+            "  {} {} projetos com falha (parcial); as demais pastas deles não foram tocadas (--atomic-per-project):",
+            icon("⚠️"),
+            result.partial.len().to_string().bold().red()
+        );
+        for project in &result.partial {
+            println!("    {} {} — {} pastas puladas", icon("✗").red(), project.path.display(), project.skipped.len());
+            for path in &project.skipped {
+                println!("      {} {}", icon("↷").dimmed(), path.display());
+            }
         }
     }
 
     println!();
 }
 
+/// Contadores de `--probe`. Vão para o stderr, junto com os demais
+/// diagnósticos, para não se misturar à listagem nem ao JSON.
+pub fn print_probe(counters: &ScanCounters) {
+    use std::sync::atomic::Ordering::Relaxed;
+
+    eprintln!();
+    eprintln!("  {}", format!("{} Diagnóstico da varredura (--probe):", icon("🔬")).bold());
+    eprintln!(
+        "    Descoberta: {} pastas, {} arquivos, {} pastas de dependência ({} verificações por tipo) em {:.2?}",
+        counters.dirs_visited.load(Relaxed),
+        counters.files_seen.load(Relaxed),
+        counters.dep_dirs_found.load(Relaxed),
+        counters.type_checks.load(Relaxed),
+        counters.discovery_time()
+    );
+    eprintln!(
+        "    Datas:      {} pastas, {} arquivos consultados em {:.2?}",
+        counters.mtime_dirs.load(Relaxed),
+        counters.mtime_files.load(Relaxed),
+        counters.mtime_time()
+    );
+    eprintln!(
+        "    Tamanhos:   {} arquivos em {:.2?}",
+        counters.sized_files.load(Relaxed),
+        counters.sizing_time()
+    );
+    eprintln!();
+}
+
+/// Resumo em uma linha JSON no stderr (`--summary-json-stderr`), para scripts
+/// capturarem sem perder a saída colorida do stdout.
+pub fn print_summary_json(result: &CleanResult, projects: usize) {
+    let summary = serde_json::json!({
+        "projects": projects,
+        "dirs": result.dirs_removed,
+        "freed": result.total_freed,
+        "moved": result.total_moved,
+        "errors": result.errors.len(),
+        "passes": result.passes,
+    });
+    eprintln!("{}", summary);
+}
+
+/// Quantas execuções recentes `--history` lista.
+const HISTORY_RECENT: usize = 5;
+
+pub fn print_history(entries: &[HistoryEntry]) {
+    println!();
+    if entries.is_empty() {
+        println!("  {} Nenhuma limpeza registrada ainda.", icon("📜"));
+        println!();
+        return;
+    }
+
+    println!(
+        "  {} {} liberados em {} limpezas registradas.",
+        icon("📜"),
+        format_size(history::total_freed(entries)).bold().green(),
+        entries.len().to_string().bold()
+    );
+    println!();
+
+    for entry in entries.iter().rev().take(HISTORY_RECENT) {
+        let when = jiff::Timestamp::from_second(entry.timestamp as i64)
+            .map(|t| t.to_zoned(jiff::tz::TimeZone::system()).strftime("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| entry.timestamp.to_string());
+        println!(
+            "    {}  {} ({} pastas)  {}",
+            when.dimmed(),
+            format_size(entry.total_freed).bold(),
+            entry.dirs_removed,
+            entry.root.display().to_string().dimmed()
+        );
+    }
+    println!();
+}
+
 pub fn print_no_stale_projects(days: u64) {
     println!();
     println!(
         "  {} Nenhum projeto inativo há mais de {} dias encontrado.",
-        "✨".to_string(),
+        icon("✨"),
         days.to_string().bold()
     );
     println!(
         "  {}",
-        "Seu disco está limpo! 🎉".green().bold()
+        format!("Seu disco está limpo! {}", icon("🎉")).green().bold()
     );
     println!();
 }
@@ -247,6 +1311,73 @@ pub fn print_no_stale_projects(days: u64) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_scan_progress_cadence() {
+        let due = |range: std::ops::Range<usize>| range.filter(|&n| scan_progress_due(n)).count();
+        // Varreduras pequenas já mostram progresso
+        assert!(scan_progress_due(10));
+        assert_eq!(due(1..1_000), 99);
+        assert_eq!(due(1_000..10_000), 90);
+        assert_eq!(due(10_000..20_000), 20);
+    }
+
+    #[test]
+    fn test_depth_groups_subtotals() {
+        let root = PathBuf::from("/code");
+        let project = |path: &str, size: u64| {
+            StaleProject::new(
+                path.rsplit('/').next().unwrap().to_string(),
+                PathBuf::from(path),
+                vec![DepDir { path: Path::new(path).join("node_modules"), size, files: 0, kind: DepKind::NodeModules }],
+                SystemTime::now(),
+            )
+        };
+        let projects = vec![
+            project("/code/acme/api", 300),
+            project("/code/acme/web", 200),
+            project("/code/globex/site", 100),
+            project("/code/globex/tools/cli", 50),
+            project("/code", 10),
+        ];
+
+        assert_eq!(
+            depth_groups(&projects, std::slice::from_ref(&root), 1),
+            vec![
+                ("acme".to_string(), 2, 500),
+                ("globex".to_string(), 2, 150),
+                (ROOT_GROUP.to_string(), 1, 10),
+            ]
+        );
+        // Um nível abaixo, projetos que ficam acima dele vão para a raiz
+        let deeper = depth_groups(&projects, &[root], 2);
+        assert_eq!(deeper[0], ("acme/api".to_string(), 1, 300));
+        assert!(deeper.contains(&("globex/tools".to_string(), 1, 50)));
+        assert!(deeper.contains(&(ROOT_GROUP.to_string(), 1, 10)));
+    }
+
+    #[test]
+    fn test_project_types_listing_covers_every_type() {
+        let listing = render_project_types();
+        let types = all_project_types();
+        // Cabeçalho, separador e uma linha por tipo
+        assert_eq!(listing.lines().count(), types.len() + 2);
+        for t in &types {
+            assert!(listing.contains(t.name()), "{} missing from listing", t.name());
+            assert!(listing.contains(t.dep_kind().as_str()), "{} missing its id", t.name());
+            for dir in t.dependency_dir_names() {
+                assert!(listing.contains(&format!("{}/", dir)), "{} missing dir {}", t.name(), dir);
+            }
+            assert!(!t.detection_markers().is_empty(), "{} has no detection marker", t.name());
+            for marker in t.detection_markers() {
+                assert!(listing.contains(marker), "{} missing marker {}", t.name(), marker);
+            }
+        }
+        assert!(listing.contains("(--include-dist)"));
+        // A coluna mostra o que a detecção checa, não os manifestos de reinstalação
+        let venv = listing.lines().find(|l| l.contains("venv/")).unwrap();
+        assert!(venv.contains("pyvenv.cfg") && !venv.contains("requirements.txt"), "{}", venv);
+    }
+
     #[test]
     fn test_format_size_bytes() {
         assert_eq!(format_size(0), "0 B");
@@ -272,9 +1403,167 @@ mod tests {
         assert_eq!(format_size(2 * 1024 * 1024 * 1024), "2.00 GB");
     }
 
+    #[test]
+    fn test_format_size_forced_unit() {
+        let fmt = |unit, precision| SizeFormat { unit, precision };
+        assert_eq!(format_size_with(1536, fmt(SizeUnit::B, 2)), "1536 B");
+        assert_eq!(format_size_with(5 * 1024 * 1024 * 1024, fmt(SizeUnit::B, 0)), "5368709120 B");
+        assert_eq!(format_size_with(512, fmt(SizeUnit::KB, 2)), "0.50 KB");
+        assert_eq!(format_size_with(3 * 1024 * 1024 * 1024, fmt(SizeUnit::MB, 1)), "3072.0 MB");
+        assert_eq!(format_size_with(512 * 1024 * 1024, fmt(SizeUnit::GB, 3)), "0.500 GB");
+    }
+
+    #[test]
+    fn test_format_size_precision() {
+        let auto = |precision| SizeFormat { unit: SizeUnit::Auto, precision };
+        assert_eq!(format_size_with(1536, auto(0)), "2 KB");
+        assert_eq!(format_size_with(1536, auto(1)), "1.5 KB");
+        assert_eq!(format_size_with(1536, auto(4)), "1.5000 KB");
+        assert_eq!(format_size_with(100, auto(0)), "100 B");
+        assert_eq!(format_size_with(1536, SizeFormat::default()), format_size(1536));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("2K").unwrap(), 2048);
+        assert_eq!(parse_size("120MB").unwrap(), 120 * 1024 * 1024);
+        assert_eq!(parse_size("1.5g").unwrap(), 1536 * 1024 * 1024);
+        assert!(parse_size("abc").is_err());
+        assert!(parse_size("10XB").is_err());
+    }
+
+    #[test]
+    fn test_collapse_small() {
+        let project = |name: &str, size: u64| StaleProject::new(
+            name.to_string(),
+            Path::new("/tmp").join(name),
+            vec![DepDir {
+                path: Path::new("/tmp").join(name).join("node_modules"),
+                size,
+                files: 0,
+                kind: DepKind::NodeModules,
+            }],
+            SystemTime::now(),
+        );
+        let projects = vec![project("big", 5000), project("a", 100), project("b", 300)];
+
+        let (shown, count, total) = collapse_small(&projects, Some(1000));
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].name, "big");
+        assert_eq!((count, total), (2, 400));
+
+        let (shown, count, total) = collapse_small(&projects, None);
+        assert_eq!((shown.len(), count, total), (3, 0, 0));
+    }
+
+    #[test]
+    fn test_list_top_level_caps_entries() {
+        let temp = std::env::temp_dir().join(format!("test_preview_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        fs::create_dir_all(temp.join("sub/deep")).unwrap();
+        for i in 0..25 {
+            fs::write(temp.join(format!("file_{:02}.js", i)), "").unwrap();
+        }
+
+        let (names, total) = list_top_level(&temp, 20);
+        assert_eq!(total, 26);
+        assert_eq!(names.len(), 20);
+        assert_eq!(names[0], "file_00.js");
+        assert!(!names.iter().any(|n| n.contains("deep")), "Only one level is read");
+
+        let (names, total) = list_top_level(&temp, 100);
+        assert_eq!((names.len(), total), (26, 26));
+        assert!(names.contains(&"sub/".to_string()));
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_render_table() {
+        let project = StaleProject::new(
+            "meu-app".to_string(),
+            Path::new("/home/user/projetos/clientes/meu-app").to_path_buf(),
+            vec![DepDir {
+                path: Path::new("/home/user/projetos/clientes/meu-app/node_modules").to_path_buf(),
+                size: 2048,
+                files: 0,
+                kind: DepKind::NodeModules,
+            }],
+            SystemTime::now(),
+        );
+
+        let table = render_table(std::slice::from_ref(&project), 200);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for header in ["Projeto", "Caminho", "Tipos", "Tamanho", "Idade"] {
+            assert!(lines[0].contains(header));
+        }
+        assert!(lines[2].contains("meu-app"));
+        assert!(lines[2].contains("/home/user/projetos/clientes/meu-app"));
+        assert!(lines[2].contains("📦 node_modules"));
+        assert!(lines[2].contains("2.00 KB"));
+
+        // Terminal estreito: o caminho é cortado no início com reticências
+        let narrow = render_table(&[project], 60);
+        let row = narrow.lines().nth(2).unwrap();
+        assert!(row.contains("…"));
+        assert!(row.contains("meu-app  "));
+        assert!(row.width() <= 60);
+    }
+
+    #[test]
+    fn test_render_flat_table_one_row_per_dir() {
+        let dep = |project: &str, name: &str, kind| DepDir {
+            path: Path::new("/code").join(project).join(name),
+            size: 1024,
+            files: 0,
+            kind,
+        };
+        let projects = vec![
+            StaleProject::new(
+                "web".to_string(),
+                Path::new("/code/web").to_path_buf(),
+                vec![dep("web", "node_modules", DepKind::NodeModules), dep("web", ".next", DepKind::NextBuild)],
+                SystemTime::now(),
+            ),
+            StaleProject::new(
+                "api".to_string(),
+                Path::new("/code/api").to_path_buf(),
+                vec![dep("api", "target", DepKind::Target)],
+                SystemTime::now(),
+            ),
+        ];
+        let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+
+        let table = render_flat_table(&projects, 200);
+        let rows: Vec<&str> = table.lines().skip(2).collect();
+        assert_eq!(rows.len(), total_dirs);
+        assert!(rows[1].contains("/code/web/.next"));
+        assert!(rows[1].contains("web"));
+        assert!(rows[2].contains("🦀 target"));
+    }
+
     #[test]
     fn test_days_ago_recent() {
         let now = SystemTime::now();
         assert_eq!(days_ago(now), "hoje");
     }
+
+    #[test]
+    fn test_confirm_cleanup_default() {
+        // Enter aceita o padrão do prompt
+        for default_yes in [false, true] {
+            let mut offered = None;
+            let answer = confirm_cleanup_with(default_yes, true, |default| {
+                offered = Some(default);
+                default
+            });
+            assert_eq!(offered, Some(default_yes));
+            assert_eq!(answer, default_yes);
+        }
+
+        // Sem terminal nunca confirma, mesmo com o padrão "sim"
+        assert!(!confirm_cleanup_with(true, false, |_| panic!("no prompt without a terminal")));
+    }
 }
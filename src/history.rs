@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use directories::ProjectDirs;
+use log::warn;
+
+use crate::cleaner::{CleanResult, KindTiming};
+
+/// Uma execução registrada. Gravada como uma linha separada por tabs:
+/// `timestamp\tdirs_removed\ttotal_freed\troot` (root por último, pode conter espaços).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub root: PathBuf,
+    pub dirs_removed: usize,
+    pub total_freed: u64,
+}
+
+impl HistoryEntry {
+    pub fn from_result(root: &Path, result: &CleanResult) -> Self {
+        HistoryEntry {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            root: root.to_path_buf(),
+            dirs_removed: result.dirs_removed,
+            total_freed: result.total_freed,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        format!("{}\t{}\t{}\t{}", self.timestamp, self.dirs_removed, self.total_freed, self.root.display())
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(4, '\t');
+        Some(HistoryEntry {
+            timestamp: parts.next()?.parse().ok()?,
+            dirs_removed: parts.next()?.parse().ok()?,
+            total_freed: parts.next()?.parse().ok()?,
+            root: PathBuf::from(parts.next()?),
+        })
+    }
+}
+
+/// Arquivo de histórico no diretório de cache do usuário.
+pub fn default_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "faxina-cli").map(|d| d.cache_dir().join("history.tsv"))
+}
+
+/// Velocidade de remoção por tipo, no diretório de cache: uma linha
+/// `id do tipo\tbytes por segundo`.
+pub fn throughput_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "faxina-cli").map(|d| d.cache_dir().join("throughput.tsv"))
+}
+
+/// Peso da limpeza mais recente na média móvel da velocidade de cada tipo.
+const THROUGHPUT_WEIGHT: f64 = 0.3;
+
+/// Velocidades conhecidas por id de tipo. Arquivo ausente = nenhum dado (a
+/// barra usa o ETA genérico); linhas inválidas são ignoradas.
+pub fn load_throughput(path: &Path) -> HashMap<String, f64> {
+    fs::read_to_string(path)
+        .map(|c| {
+            c.lines()
+                .filter_map(|l| l.split_once('\t'))
+                .filter_map(|(kind, rate)| Some((kind.to_string(), rate.parse::<f64>().ok().filter(|r| *r > 0.0)?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Junta as velocidades observadas numa limpeza às já conhecidas (média móvel
+/// exponencial) e regrava o arquivo.
+pub fn record_throughput(path: &Path, observed: &HashMap<String, KindTiming>) -> std::io::Result<()> {
+    let mut rates = load_throughput(path);
+    for (kind, timing) in observed {
+        let secs = timing.elapsed.as_secs_f64();
+        if timing.bytes == 0 || secs <= 0.0 {
+            continue;
+        }
+        let rate = timing.bytes as f64 / secs;
+        rates
+            .entry(kind.clone())
+            .and_modify(|avg| *avg = *avg * (1.0 - THROUGHPUT_WEIGHT) + rate * THROUGHPUT_WEIGHT)
+            .or_insert(rate);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut kinds: Vec<_> = rates.into_iter().collect();
+    kinds.sort_by(|a, b| a.0.cmp(&b.0));
+    let lines: String = kinds.iter().map(|(kind, rate)| format!("{}\t{:.0}\n", kind, rate)).collect();
+    fs::write(path, lines)
+}
+
+/// Arquivo por raiz de varredura em `<cache>/<subdir>/`, nomeado pelo hash do caminho.
+pub fn root_cache_path(subdir: &str, root: &Path) -> Option<PathBuf> {
+    ProjectDirs::from("", "", "faxina-cli")
+        .map(|d| d.cache_dir().join(subdir).join(format!("{:016x}.txt", path_hash(root))))
+}
+
+/// FNV-1a de 64 bits dos bytes do caminho. O nome do arquivo precisa ser o
+/// mesmo entre versões do Rust, o que o `DefaultHasher` não garante.
+fn path_hash(path: &Path) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(OFFSET, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(PRIME))
+}
+
+/// Caminho como uma linha de arquivo, sem perdas: `\`, tab e quebras de
+/// linha viram `\\`, `\t`, `\n` e `\r`, e no Unix bytes fora de UTF-8 viram
+/// `\xHH`. `None` quando não há como representá-lo (no Windows, nomes que não
+/// são Unicode válido): quem grava não deve registrar esse caminho.
+#[cfg(unix)]
+pub fn encode_path(path: &Path) -> Option<String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut out = String::new();
+    let mut bytes = path.as_os_str().as_bytes();
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => {
+                escape_into(&mut out, text);
+                return Some(out);
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                escape_into(&mut out, std::str::from_utf8(valid).unwrap_or_default());
+                let invalid = e.error_len().unwrap_or(rest.len());
+                for byte in &rest[..invalid] {
+                    out.push_str(&format!("\\x{:02x}", byte));
+                }
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn encode_path(path: &Path) -> Option<String> {
+    let mut out = String::new();
+    escape_into(&mut out, path.to_str()?);
+    Some(out)
+}
+
+fn escape_into(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Inverso de `encode_path`. `None` para uma linha malformada.
+pub fn decode_path(line: &str) -> Option<PathBuf> {
+    let mut bytes = Vec::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        match chars.next()? {
+            '\\' => bytes.push(b'\\'),
+            't' => bytes.push(b'\t'),
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 {
+                    return None;
+                }
+                bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+            }
+            _ => return None,
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+    }
+    #[cfg(not(unix))]
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+pub fn append(path: &Path, entry: &HistoryEntry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(f, "{}", entry.to_line())
+}
+
+/// Lê o histórico. Arquivo ausente vira histórico vazio; linhas corrompidas são ignoradas.
+pub fn load(path: &Path) -> Vec<HistoryEntry> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut entries = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match HistoryEntry::parse(line) {
+            Some(e) => entries.push(e),
+            None => warn!("Linha inválida no histórico ignorada: {}", line),
+        }
+    }
+    entries
+}
+
+/// Momento da última limpeza registrada em `root` (`--since-last-run`).
+pub fn last_run(entries: &[HistoryEntry], root: &Path) -> Option<SystemTime> {
+    entries
+        .iter()
+        .filter(|e| e.root == root)
+        .map(|e| e.timestamp)
+        .max()
+        .map(|secs| UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+pub fn total_freed(entries: &[HistoryEntry]) -> u64 {
+    entries.iter().map(|e| e.total_freed).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_cache_name_is_stable() {
+        // Valores de referência do FNV-1a: mudar o hash esconderia os arquivos já gravados
+        assert_eq!(path_hash(Path::new("")), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(path_hash(Path::new("a")), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(path_hash(Path::new("/home/dev/code")), path_hash(Path::new("/home/dev/code")));
+        assert_ne!(path_hash(Path::new("/home/dev/code")), path_hash(Path::new("/home/dev/code2")));
+    }
+
+    #[test]
+    fn test_append_and_aggregate() {
+        let temp = std::env::temp_dir().join(format!("test_history_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let path = temp.join("nested/history.tsv");
+
+        assert!(load(&path).is_empty(), "Missing file starts fresh");
+
+        let first = HistoryEntry { timestamp: 100, root: PathBuf::from("/code"), dirs_removed: 2, total_freed: 1000 };
+        let second = HistoryEntry { timestamp: 200, root: PathBuf::from("/my code"), dirs_removed: 1, total_freed: 500 };
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+
+        let entries = load(&path);
+        assert_eq!(entries, vec![first, second]);
+        assert_eq!(total_freed(&entries), 1500);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_last_run_for_root() {
+        let temp = std::env::temp_dir().join(format!("test_history_last_run_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let path = temp.join("history.tsv");
+
+        let code = Path::new("/code");
+        assert_eq!(last_run(&load(&path), code), None, "No history: caller falls back to --days");
+
+        for (timestamp, root) in [(1_700_000_000, "/code"), (1_700_500_000, "/code"), (1_800_000_000, "/other")] {
+            let entry = HistoryEntry { timestamp, root: PathBuf::from(root), dirs_removed: 1, total_freed: 10 };
+            append(&path, &entry).unwrap();
+        }
+
+        let cutoff = last_run(&load(&path), code).unwrap();
+        assert_eq!(cutoff.duration_since(UNIX_EPOCH).unwrap().as_secs(), 1_700_500_000);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_throughput_moving_average() {
+        let temp = std::env::temp_dir().join(format!("test_history_throughput_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let path = temp.join("throughput.tsv");
+        assert!(load_throughput(&path).is_empty(), "No data: generic ETA");
+
+        let timing = |bytes: u64, secs: u64| KindTiming { bytes, elapsed: std::time::Duration::from_secs(secs) };
+        let first = HashMap::from([("node_modules".to_string(), timing(1000, 1)), ("target".to_string(), timing(0, 0))]);
+        record_throughput(&path, &first).unwrap();
+        assert_eq!(load_throughput(&path), HashMap::from([("node_modules".to_string(), 1000.0)]));
+
+        // 2000 B/s observados: 1000 * 0.7 + 2000 * 0.3
+        record_throughput(&path, &HashMap::from([("node_modules".to_string(), timing(4000, 2))])).unwrap();
+        assert_eq!(load_throughput(&path)["node_modules"], 1300.0);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_load_skips_corrupt_lines() {
+        let temp = std::env::temp_dir().join(format!("test_history_corrupt_{}", std::process::id()));
+        fs::create_dir_all(&temp).unwrap();
+        let path = temp.join("history.tsv");
+        fs::write(&path, "lixo\n300\t1\t42\t/code\n\t\t\n").unwrap();
+
+        let entries = load(&path);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].total_freed, 42);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
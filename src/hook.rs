@@ -0,0 +1,110 @@
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+use crate::cleaner::CleanResult;
+use crate::display::format_size;
+
+/// Variáveis aceitas em `--post-clean-cmd` e a variável de ambiente de cada uma.
+const PLACEHOLDERS: &[(&str, &str)] = &[
+    ("{freed}", "FAXINA_FREED"),
+    ("{freed_bytes}", "FAXINA_FREED_BYTES"),
+    ("{moved}", "FAXINA_MOVED"),
+    ("{moved_bytes}", "FAXINA_MOVED_BYTES"),
+    ("{dirs}", "FAXINA_DIRS"),
+    ("{errors}", "FAXINA_ERRORS"),
+    ("{root}", "FAXINA_ROOT"),
+];
+
+/// Troca cada `{var}` por uma referência à variável de ambiente correspondente
+/// em vez do valor: o shell expande o valor sem reinterpretá-lo, então aspas ou
+/// `;` num caminho não viram comandos. No Windows a referência é `!VAR!`, com
+/// expansão atrasada (`cmd /V:ON`): `%VAR%` seria expandido antes da análise
+/// do comando, e um `&` ou `|` no valor rodaria outro comando.
+fn expand(template: &str) -> String {
+    PLACEHOLDERS.iter().fold(template.to_string(), |cmd, (placeholder, var)| {
+        #[cfg(windows)]
+        let reference = format!("!{}!", var);
+        #[cfg(not(windows))]
+        let reference = format!("${{{}}}", var);
+        cmd.replace(placeholder, &reference)
+    })
+}
+
+/// Roda `--post-clean-cmd` via `sh -c` (`cmd /V:ON /C` no Windows), com os
+/// valores da limpeza nas variáveis `FAXINA_*`.
+pub fn run_post_clean(template: &str, result: &CleanResult, root: &Path) -> std::io::Result<ExitStatus> {
+    let values = [
+        format_size(result.total_freed),
+        result.total_freed.to_string(),
+        format_size(result.total_moved),
+        result.total_moved.to_string(),
+        result.dirs_removed.to_string(),
+        result.errors.len().to_string(),
+        root.display().to_string(),
+    ];
+
+    // O `cmd` não segue as regras de aspas do std: a linha vai crua
+    // (`raw_arg`), senão as aspas que o std acrescenta quebram o comando.
+    #[cfg(windows)]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+        let mut c = Command::new("cmd");
+        c.args(["/V:ON", "/C"]).raw_arg(expand(template));
+        c
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(expand(template));
+        c
+    };
+
+    for ((_, var), value) in PLACEHOLDERS.iter().zip(values) {
+        command.env(var, value);
+    }
+    command.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_post_clean_substitutes_placeholders() {
+        let temp = std::env::temp_dir().join(format!("test_hook_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        fs::create_dir_all(&temp).unwrap();
+        let out = temp.join("out.txt");
+
+        let result = CleanResult {
+            total_freed: 2048,
+            total_moved: 1024,
+            dirs_removed: 3,
+            links_removed: Vec::new(),
+            skipped_open: Vec::new(),
+            errors: Vec::new(),
+            would_fail: Vec::new(),
+            partial: Vec::new(),
+            marker_missing: Vec::new(),
+            timings: Default::default(),
+            lockfiles_removed: Vec::new(),
+            passes: 1,
+            empty_parents_removed: Vec::new(),
+        };
+        // Um root com aspas e `;` não pode escapar do comando
+        let root = Path::new("/code/it's; touch pwned");
+        let template = format!("echo \"{{freed}}|{{freed_bytes}}|{{moved_bytes}}|{{dirs}}|{{errors}}|{{root}}\" > '{}'", out.display());
+
+        let status = run_post_clean(&template, &result, root).unwrap();
+        assert!(status.success());
+        assert_eq!(
+            fs::read_to_string(&out).unwrap().trim_end(),
+            "2.00 KB|2048|1024|3|0|/code/it's; touch pwned"
+        );
+        assert!(!Path::new("pwned").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
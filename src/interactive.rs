@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Input, MultiSelect};
+
+use crate::display;
+use crate::history;
+use crate::types::{DepKind, StaleProject};
+
+/// Rótulo exibido para cada projeto: caminho, tamanho e ícones dos tipos.
+pub fn project_label(project: &StaleProject) -> String {
+    format!(
+        "{} ({}) - {}",
+        project.path.display(),
+        display::format_size(project.total_size()),
+        project.dep_dirs().iter().map(|d| display::kind_icon(&d.kind)).collect::<Vec<_>>().join(" ")
+    )
+}
+
+/// Índices dos projetos cujo nome ou caminho contém `filter` (sem diferenciar maiúsculas).
+/// Filtro vazio mantém todos.
+pub fn filter_indices(projects: &[StaleProject], filter: &str) -> Vec<usize> {
+    let needle = filter.trim().to_lowercase();
+    projects.iter()
+        .enumerate()
+        .filter(|(_, p)| {
+            needle.is_empty()
+                || p.name.to_lowercase().contains(&needle)
+                || p.path.to_string_lossy().to_lowercase().contains(&needle)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Tipos presentes nos projetos visíveis, na ordem em que aparecem.
+/// Cada um vira um atalho "[Selecionar todos ...]" no topo da lista.
+pub fn kind_shortcuts(projects: &[StaleProject], visible: &[usize]) -> Vec<DepKind> {
+    let mut kinds: Vec<DepKind> = Vec::new();
+    for &i in visible {
+        for dep in projects[i].dep_dirs() {
+            if !kinds.contains(&dep.kind) {
+                kinds.push(dep.kind.clone());
+            }
+        }
+    }
+    kinds
+}
+
+/// Converte as seleções da lista (atalhos primeiro, depois projetos visíveis)
+/// de volta para os projetos originais, sem duplicatas e na ordem original.
+pub fn map_selection(
+    projects: &[StaleProject],
+    visible: &[usize],
+    shortcuts: &[DepKind],
+    selections: &[usize],
+) -> Vec<StaleProject> {
+    let mut chosen = vec![false; projects.len()];
+    for &sel in selections {
+        if let Some(kind) = shortcuts.get(sel) {
+            for &i in visible {
+                if projects[i].dep_dirs().iter().any(|d| &d.kind == kind) {
+                    chosen[i] = true;
+                }
+            }
+        } else if let Some(&i) = visible.get(sel - shortcuts.len()) {
+            chosen[i] = true;
+        }
+    }
+
+    projects.iter()
+        .zip(chosen)
+        .filter(|(_, c)| *c)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+/// Última seleção interativa feita sob `root` (um caminho de projeto por linha,
+/// ver `history::encode_path`).
+pub fn selection_path(root: &Path) -> Option<PathBuf> {
+    history::root_cache_path("selection", root)
+}
+
+pub fn save_selection(path: &Path, selected: &[StaleProject]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lines: Vec<String> = selected.iter().filter_map(|p| history::encode_path(&p.path)).collect();
+    fs::write(path, lines.join("\n"))
+}
+
+/// Caminhos da última seleção. Arquivo ausente = nenhuma seleção lembrada.
+pub fn load_selection(path: &Path) -> HashSet<PathBuf> {
+    fs::read_to_string(path)
+        .map(|c| c.lines().filter(|l| !l.trim().is_empty()).filter_map(history::decode_path).collect())
+        .unwrap_or_default()
+}
+
+/// Marcação inicial da lista: atalhos desmarcados, projetos lembrados marcados.
+/// Caminhos lembrados que sumiram ou deixaram de estar inativos não estão em
+/// `projects` e simplesmente não marcam nada.
+pub fn preselected(
+    projects: &[StaleProject],
+    visible: &[usize],
+    shortcuts: usize,
+    remembered: &HashSet<PathBuf>,
+) -> Vec<bool> {
+    std::iter::repeat_n(false, shortcuts)
+        .chain(visible.iter().map(|&i| remembered.contains(&projects[i].path)))
+        .collect()
+}
+
+/// Pede um filtro opcional e mostra a seleção múltipla sobre os projetos restantes,
+/// com os projetos de `remembered` já marcados.
+/// Retorna a lista vazia quando nada é selecionado.
+pub fn select_projects(projects: &[StaleProject], remembered: &HashSet<PathBuf>) -> Result<Vec<StaleProject>> {
+    let theme = ColorfulTheme::default();
+
+    println!();
+    let filter: String = Input::with_theme(&theme)
+        .with_prompt("  Filtrar por nome ou caminho (Enter para listar todos)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let visible = filter_indices(projects, &filter);
+    if visible.is_empty() {
+        println!("  {} Nenhum projeto corresponde a '{}'.", display::icon("↩").dimmed(), filter);
+        return Ok(Vec::new());
+    }
+
+    println!();
+    println!("  {}", "Selecione os projetos para limpar (Espaço para selecionar, Enter para confirmar):".bold());
+
+    let shortcuts = kind_shortcuts(projects, &visible);
+    let labels: Vec<String> = shortcuts.iter()
+        .map(|k| format!("[Selecionar todos {} {}]", display::kind_icon(k), k))
+        .chain(visible.iter().map(|&i| project_label(&projects[i])))
+        .collect();
+    let defaults = preselected(projects, &visible, shortcuts.len(), remembered);
+    let selections = MultiSelect::with_theme(&theme)
+        .items(&labels)
+        .defaults(&defaults)
+        .interact()?;
+
+    Ok(map_selection(projects, &visible, &shortcuts, &selections))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DepDir, DepKind};
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    fn project(name: &str, path: &str, size: u64) -> StaleProject {
+        project_with(name, path, size, DepKind::NodeModules)
+    }
+
+    fn project_with(name: &str, path: &str, size: u64, kind: DepKind) -> StaleProject {
+        StaleProject::new(
+            name.to_string(),
+            PathBuf::from(path),
+            vec![DepDir {
+                path: PathBuf::from(path).join(kind.to_string()),
+                size,
+                files: 0,
+                kind,
+            }],
+            SystemTime::now(),
+        )
+    }
+
+    #[test]
+    fn test_project_label() {
+        let p = project("api", "/code/api", 2048);
+        assert_eq!(project_label(&p), "/code/api (2.00 KB) - 📦");
+    }
+
+    #[test]
+    fn test_filter_and_map_selection() {
+        let projects = vec![
+            project("api", "/code/acme/api", 1),
+            project("web", "/code/acme/web", 2),
+            project("blog", "/code/pessoal/blog", 3),
+        ];
+
+        assert_eq!(filter_indices(&projects, ""), vec![0, 1, 2]);
+        assert_eq!(filter_indices(&projects, "ACME"), vec![0, 1]);
+        assert_eq!(filter_indices(&projects, "blog"), vec![2]);
+        assert!(filter_indices(&projects, "nada").is_empty());
+
+        // Seleção do segundo item visível no filtro "acme" → projeto "web"
+        let visible = filter_indices(&projects, "acme");
+        let picked = map_selection(&projects, &visible, &[], &[1]);
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked[0].name, "web");
+    }
+
+    #[test]
+    fn test_map_selection_with_kind_shortcut() {
+        let projects = vec![
+            project_with("api", "/code/api", 1, DepKind::NodeModules),
+            project_with("cli", "/code/cli", 2, DepKind::Target),
+            project_with("web", "/code/web", 3, DepKind::NodeModules),
+            project_with("tool", "/code/tool", 4, DepKind::Target),
+        ];
+        let visible = filter_indices(&projects, "");
+        let shortcuts = kind_shortcuts(&projects, &visible);
+        assert_eq!(shortcuts, vec![DepKind::NodeModules, DepKind::Target]);
+
+        // Atalho "todos node_modules" (0) + projeto "tool" (2 atalhos + índice 3) + "web" repetido
+        let picked = map_selection(&projects, &visible, &shortcuts, &[0, 5, 4]);
+        let names: Vec<&str> = picked.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "web", "tool"]);
+    }
+
+    #[test]
+    fn test_selection_restore_ignores_vanished_paths() {
+        let temp = std::env::temp_dir().join(format!("test_selection_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let path = temp.join("selection/root.txt");
+
+        assert!(load_selection(&path).is_empty(), "Missing file remembers nothing");
+
+        let previous = vec![project("api", "/code/api", 1), project("gone", "/code/gone", 2)];
+        save_selection(&path, &previous).unwrap();
+        let remembered = load_selection(&path);
+        assert_eq!(remembered.len(), 2);
+
+        // Nesta execução "gone" já não existe (ou ficou ativo) e "web" é novo
+        let projects = vec![project("web", "/code/web", 3), project("api", "/code/api", 1)];
+        let visible = filter_indices(&projects, "");
+        assert_eq!(preselected(&projects, &visible, 1, &remembered), vec![false, false, true]);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
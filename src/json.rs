@@ -0,0 +1,584 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::cleaner::{CleanEvent, CleanResult};
+use crate::types::StaleProject;
+
+/// Versão do formato de `--format json`. Incrementar a cada mudança
+/// incompatível (campo removido/renomeado ou tipo alterado).
+pub const SCHEMA_VERSION: u32 = 2;
+
+// Caminhos que não são UTF-8 válido saem em `path` com U+FFFD no lugar dos
+// bytes inválidos (legível, mas ambíguo) e, ao lado, em `path_bytes` com os
+// bytes exatos. O mesmo vale para o nome do projeto (`name_bytes`), que vem
+// do último componente do caminho. A presença de `*_bytes` marca as entradas
+// afetadas.
+
+#[derive(Debug, Serialize)]
+pub struct JsonDepDir {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    pub kind: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonProject {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_bytes: Option<Vec<u8>>,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    /// Segundos desde a época Unix.
+    pub last_modified: u64,
+    pub total_size: u64,
+    pub dep_dirs: Vec<JsonDepDir>,
+}
+
+/// Registro de `--flatten`: uma pasta de dependência com o projeto dono.
+#[derive(Debug, Serialize)]
+pub struct JsonFlatDepDir {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    pub kind: String,
+    pub size: u64,
+    pub project_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_name_bytes: Option<Vec<u8>>,
+    pub project_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_path_bytes: Option<Vec<u8>>,
+    /// Segundos desde a época Unix (do projeto).
+    pub last_modified: u64,
+}
+
+/// Corpo do relatório: projetos com suas pastas, ou a lista plana de `--flatten`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonEntries {
+    Projects(Vec<JsonProject>),
+    DepDirs(Vec<JsonFlatDepDir>),
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct JsonSummary {
+    pub projects: usize,
+    pub dirs: usize,
+    pub freed: u64,
+    /// Bytes movidos por `--move-to`: continuam em disco, fora de `freed`.
+    pub moved: u64,
+    pub errors: usize,
+    /// Passadas feitas (mais de 1 só com `--repeat`); 0 sem limpeza.
+    pub passes: u32,
+    /// Uma entrada por pasta que falhou; omitido quando não houve erros.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub error_details: Vec<JsonCleanError>,
+    /// `--atomic-per-project`: projetos com falha e as pastas deles puladas.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub partial: Vec<JsonPartialProject>,
+    /// `--verify-markers`: pastas puladas porque o marcador do projeto sumiu.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub marker_missing: Vec<JsonPath>,
+    /// `--remove-lockfiles`: lockfiles apagados (no dry-run: seriam).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lockfiles_removed: Vec<JsonPath>,
+}
+
+/// Caminho avulso das listas do resumo.
+#[derive(Debug, Serialize)]
+pub struct JsonPath {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+}
+
+impl JsonPath {
+    fn new(path: &Path) -> Self {
+        JsonPath {
+            path: path.to_string_lossy().to_string(),
+            path_bytes: raw_path_bytes(path),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonPartialProject {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    /// Pastas do projeto que nem foram tentadas depois da falha.
+    pub skipped: Vec<JsonPath>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonCleanError {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    /// Ver `CleanError::category`.
+    pub category: &'static str,
+    pub message: String,
+}
+
+/// Linha transmitida por pasta durante uma limpeza real com `--format json`.
+#[derive(Debug, Serialize)]
+pub struct JsonDirEvent {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    pub kind: String,
+    pub bytes: u64,
+    /// `removed`, `skipped` ou `error` (ver `DirStatus`).
+    pub status: &'static str,
+    /// Só com `status: "error"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Ver `CleanError::category`; só com `status: "error"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<&'static str>,
+    /// Passada de `--repeat` (1 na primeira): uma pasta refeita aparece de
+    /// novo, uma vez por passada, e a última linha dela vale.
+    pub pass: u32,
+}
+
+impl JsonDirEvent {
+    pub fn new(event: &CleanEvent) -> Self {
+        JsonDirEvent {
+            path: event.dep.path.to_string_lossy().to_string(),
+            path_bytes: raw_path_bytes(&event.dep.path),
+            kind: event.dep.kind.to_string(),
+            bytes: event.dep.size,
+            status: event.status.as_str(),
+            error: event.error.map(|e| e.message().to_string()),
+            category: event.error.map(|e| e.category()),
+            pass: event.pass,
+        }
+    }
+}
+
+/// Documento completo de `--format json`.
+#[derive(Debug, Serialize)]
+pub struct JsonReport {
+    pub schema_version: u32,
+    pub dry_run: bool,
+    #[serde(flatten)]
+    pub entries: JsonEntries,
+    pub summary: JsonSummary,
+}
+
+impl JsonReport {
+    pub fn new(
+        projects: &[StaleProject],
+        result: Option<&CleanResult>,
+        dry_run: bool,
+        flatten: bool,
+    ) -> Self {
+        let entries = if flatten {
+            JsonEntries::DepDirs(
+                projects
+                    .iter()
+                    .flat_map(|p| {
+                        p.dep_dirs().iter().map(move |d| JsonFlatDepDir {
+                            path: d.path.to_string_lossy().to_string(),
+                            path_bytes: raw_path_bytes(&d.path),
+                            kind: d.kind.to_string(),
+                            size: d.size,
+                            project_name: p.name.clone(),
+                            project_name_bytes: raw_name_bytes(&p.path),
+                            project_path: p.path.to_string_lossy().to_string(),
+                            project_path_bytes: raw_path_bytes(&p.path),
+                            last_modified: unix_secs(p.last_modified),
+                        })
+                    })
+                    .collect(),
+            )
+        } else {
+            JsonEntries::Projects(
+                projects
+                    .iter()
+                    .map(|p| JsonProject {
+                        name: p.name.clone(),
+                        name_bytes: raw_name_bytes(&p.path),
+                        path: p.path.to_string_lossy().to_string(),
+                        path_bytes: raw_path_bytes(&p.path),
+                        last_modified: unix_secs(p.last_modified),
+                        total_size: p.total_size(),
+                        dep_dirs: p
+                            .dep_dirs()
+                            .iter()
+                            .map(|d| JsonDepDir {
+                                path: d.path.to_string_lossy().to_string(),
+                                path_bytes: raw_path_bytes(&d.path),
+                                kind: d.kind.to_string(),
+                                size: d.size,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            )
+        };
+
+        let summary = match result {
+            Some(r) => JsonSummary {
+                projects: projects.len(),
+                dirs: r.dirs_removed,
+                freed: r.total_freed,
+                moved: r.total_moved,
+                errors: r.errors.len(),
+                passes: r.passes,
+                error_details: r
+                    .errors
+                    .iter()
+                    .map(|(path, e)| JsonCleanError {
+                        path: path.to_string_lossy().to_string(),
+                        path_bytes: raw_path_bytes(path),
+                        category: e.category(),
+                        message: e.message().to_string(),
+                    })
+                    .collect(),
+                partial: r
+                    .partial
+                    .iter()
+                    .map(|p| JsonPartialProject {
+                        path: p.path.to_string_lossy().to_string(),
+                        path_bytes: raw_path_bytes(&p.path),
+                        skipped: p.skipped.iter().map(|s| JsonPath::new(s)).collect(),
+                    })
+                    .collect(),
+                marker_missing: r.marker_missing.iter().map(|p| JsonPath::new(p)).collect(),
+                lockfiles_removed: r.lockfiles_removed.iter().map(|p| JsonPath::new(p)).collect(),
+            },
+            None => JsonSummary::default(),
+        };
+
+        JsonReport {
+            schema_version: SCHEMA_VERSION,
+            dry_run,
+            entries,
+            summary,
+        }
+    }
+}
+
+/// Bytes exatos de um caminho que não é UTF-8 válido; `None` nos demais.
+/// No Unix são os bytes do sistema; no Windows, a codificação WTF-8 do caminho.
+pub fn raw_path_bytes(path: &Path) -> Option<Vec<u8>> {
+    path.to_str().is_none().then(|| path.as_os_str().as_encoded_bytes().to_vec())
+}
+
+/// `raw_path_bytes` do último componente, de onde vem o nome do projeto.
+fn raw_name_bytes(path: &Path) -> Option<Vec<u8>> {
+    path.file_name().and_then(|name| raw_path_bytes(Path::new(name)))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+pub fn print_report(report: &JsonReport) {
+    println!("{}", serde_json::to_string_pretty(report).unwrap_or_default());
+}
+
+/// Limpeza real com `--format json`: a saída vira JSON Lines, com uma linha
+/// por pasta assim que ela termina (`print_dir_event`) e o relatório numa
+/// linha só no final (`print_report_line`).
+pub fn print_dir_event(event: &CleanEvent) {
+    println!("{}", serde_json::to_string(&JsonDirEvent::new(event)).unwrap_or_default());
+}
+
+pub fn print_report_line(report: &JsonReport) {
+    println!("{}", serde_json::to_string(report).unwrap_or_default());
+}
+
+/// JSON Schema (draft 2020-12) de `JsonReport`, impresso por `--json-schema`.
+pub fn schema() -> Value {
+    let uint = json!({ "type": "integer", "minimum": 0 });
+    let path_bytes = json!({
+        "description": "Só para caminhos que não são UTF-8 válido: os bytes exatos (no Windows, WTF-8); o campo de texto traz U+FFFD no lugar dos inválidos",
+        "type": "array",
+        "items": { "type": "integer", "minimum": 0, "maximum": 255 }
+    });
+    let path_entry = json!({
+        "type": "object",
+        "required": ["path"],
+        "properties": { "path": { "type": "string" }, "path_bytes": path_bytes }
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "faxina-cli --format json",
+        "description": "Sem --dry-run, a limpeza transmite antes uma linha por pasta ({path, kind, bytes, status, error, pass}; com --repeat uma pasta refeita tem uma linha por passada) e este relatório vem na última linha",
+        "type": "object",
+        "required": ["schema_version", "dry_run", "summary"],
+        "oneOf": [
+            { "required": ["projects"] },
+            { "required": ["dep_dirs"], "description": "Saída de --flatten" }
+        ],
+        "properties": {
+            "schema_version": { "const": SCHEMA_VERSION },
+            "dry_run": { "type": "boolean" },
+            "projects": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "path", "last_modified", "total_size", "dep_dirs"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "name_bytes": path_bytes,
+                        "path": { "type": "string" },
+                        "path_bytes": path_bytes,
+                        "last_modified": { "description": "Segundos desde a época Unix", "type": "integer", "minimum": 0 },
+                        "total_size": uint,
+                        "dep_dirs": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["path", "kind", "size"],
+                                "properties": {
+                                    "path": { "type": "string" },
+                                    "path_bytes": path_bytes,
+                                    "kind": { "type": "string" },
+                                    "size": uint
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "dep_dirs": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "kind", "size", "project_name", "project_path", "last_modified"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "path_bytes": path_bytes,
+                        "kind": { "type": "string" },
+                        "size": uint,
+                        "project_name": { "type": "string" },
+                        "project_name_bytes": path_bytes,
+                        "project_path": { "type": "string" },
+                        "project_path_bytes": path_bytes,
+                        "last_modified": { "description": "Segundos desde a época Unix", "type": "integer", "minimum": 0 }
+                    }
+                }
+            },
+            "summary": {
+                "type": "object",
+                "required": ["projects", "dirs", "freed", "moved", "errors", "passes"],
+                "properties": {
+                    "projects": uint,
+                    "dirs": uint,
+                    "freed": uint,
+                    "moved": { "description": "Bytes movidos por --move-to (continuam em disco; não entram em freed)", "type": "integer", "minimum": 0 },
+                    "errors": uint,
+                    "passes": { "description": "Passadas da limpeza (mais de 1 só com --repeat); 0 sem limpeza", "type": "integer", "minimum": 0 },
+                    "error_details": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["path", "category", "message"],
+                            "properties": {
+                                "path": { "type": "string" },
+                                "path_bytes": path_bytes,
+                                "category": {
+                                    "type": "string",
+                                    "enum": ["permission-denied", "not-found", "retries-exhausted", "cross-device", "other"]
+                                },
+                                "message": { "type": "string" }
+                            }
+                        }
+                    },
+                    "partial": {
+                        "description": "--atomic-per-project: projetos com falha; skipped são as pastas deles que não foram tentadas",
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["path", "skipped"],
+                            "properties": {
+                                "path": { "type": "string" },
+                                "path_bytes": path_bytes,
+                                "skipped": { "type": "array", "items": path_entry }
+                            }
+                        }
+                    },
+                    "marker_missing": {
+                        "description": "--verify-markers: pastas puladas porque o marcador do projeto sumiu desde a varredura",
+                        "type": "array",
+                        "items": path_entry
+                    },
+                    "lockfiles_removed": {
+                        "description": "--remove-lockfiles: lockfiles apagados (no dry-run: seriam)",
+                        "type": "array",
+                        "items": path_entry
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_matches_report_fields() {
+        let schema = schema();
+        let props = schema["properties"].as_object().unwrap();
+        for key in ["schema_version", "dry_run", "projects", "dep_dirs", "summary"] {
+            assert!(props.contains_key(key), "schema missing {}", key);
+        }
+
+        // Os documentos reais (agrupado e --flatten) têm exatamente as propriedades declaradas
+        let report = serde_json::to_value(JsonReport::new(&[], None, true, false)).unwrap();
+        let flat = serde_json::to_value(JsonReport::new(&[], None, true, true)).unwrap();
+        let mut report_keys: Vec<&String> = report.as_object().unwrap().keys().collect();
+        for key in flat.as_object().unwrap().keys() {
+            if !report_keys.contains(&key) {
+                report_keys.push(key);
+            }
+        }
+        let mut schema_keys: Vec<&String> = props.keys().collect();
+        report_keys.sort();
+        schema_keys.sort();
+        assert_eq!(report_keys, schema_keys);
+        assert_eq!(report["schema_version"], SCHEMA_VERSION);
+        assert!(report.get("dep_dirs").is_none());
+        assert!(flat.get("projects").is_none());
+    }
+
+    #[test]
+    fn test_summary_reports_partial_markers_and_lockfiles() {
+        use crate::cleaner::{CleanError, PartialProject};
+        use std::path::PathBuf;
+
+        let result = CleanResult {
+            total_freed: 0,
+            total_moved: 0,
+            dirs_removed: 1,
+            links_removed: Vec::new(),
+            skipped_open: Vec::new(),
+            errors: vec![(PathBuf::from("/code/web/node_modules"), CleanError::PermissionDenied("negado".to_string()))],
+            would_fail: Vec::new(),
+            partial: vec![PartialProject { path: PathBuf::from("/code/web"), skipped: vec![PathBuf::from("/code/web/.next")] }],
+            marker_missing: vec![PathBuf::from("/code/old/target")],
+            timings: Default::default(),
+            lockfiles_removed: vec![PathBuf::from("/code/api/Cargo.lock")],
+            passes: 1,
+            empty_parents_removed: Vec::new(),
+        };
+        let report = serde_json::to_value(JsonReport::new(&[], Some(&result), false, false)).unwrap();
+        let summary = &report["summary"];
+        assert_eq!(summary["partial"][0]["path"], "/code/web");
+        assert_eq!(summary["partial"][0]["skipped"][0]["path"], "/code/web/.next");
+        assert_eq!(summary["marker_missing"][0]["path"], "/code/old/target");
+        assert_eq!(summary["lockfiles_removed"][0]["path"], "/code/api/Cargo.lock");
+
+        // Todo campo do resumo está no schema
+        let schema = schema();
+        let declared = schema["properties"]["summary"]["properties"].as_object().unwrap();
+        for key in summary.as_object().unwrap().keys() {
+            assert!(declared.contains_key(key), "schema missing summary.{}", key);
+        }
+
+        // Sem nada a relatar, as listas ficam de fora
+        let empty = serde_json::to_value(JsonReport::new(&[], None, true, false)).unwrap();
+        assert!(empty["summary"].get("partial").is_none());
+    }
+
+    #[test]
+    fn test_flatten_emits_one_record_per_dep_dir() {
+        use crate::types::{DepDir, DepKind};
+        use std::path::PathBuf;
+
+        let dep = |p: &str, kind| DepDir { path: PathBuf::from(p), size: 10, files: 0, kind };
+        let projects = vec![
+            StaleProject::new(
+                "web".to_string(),
+                PathBuf::from("/code/web"),
+                vec![dep("/code/web/node_modules", DepKind::NodeModules), dep("/code/web/.next", DepKind::NextBuild)],
+                SystemTime::now(),
+            ),
+            StaleProject::new(
+                "api".to_string(),
+                PathBuf::from("/code/api"),
+                vec![dep("/code/api/target", DepKind::Target)],
+                SystemTime::now(),
+            ),
+        ];
+        let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+
+        let flat = serde_json::to_value(JsonReport::new(&projects, None, true, true)).unwrap();
+        let records = flat["dep_dirs"].as_array().unwrap();
+        assert_eq!(records.len(), total_dirs);
+        assert_eq!(records[1]["path"], "/code/web/.next");
+        assert_eq!(records[1]["project_path"], "/code/web");
+        assert_eq!(records[2]["project_name"], "api");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path_is_not_corrupted() {
+        use crate::cleaner::CleanError;
+        use crate::types::{DepDir, DepKind};
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::PathBuf;
+
+        // "café" em Latin-1: o 0xE9 sozinho não é UTF-8 válido
+        let project = PathBuf::from(OsStr::from_bytes(b"/code/caf\xe9"));
+        let dep = project.join("node_modules");
+        let projects = vec![StaleProject::new(
+            "cafe".to_string(),
+            project.clone(),
+            vec![DepDir { path: dep.clone(), size: 10, files: 0, kind: DepKind::NodeModules }],
+            SystemTime::now(),
+        )];
+        let bytes = |v: &Value| -> Vec<u8> { serde_json::from_value(v.clone()).unwrap() };
+
+        let report = serde_json::to_value(JsonReport::new(&projects, None, true, false)).unwrap();
+        let entry = &report["projects"][0];
+        assert_eq!(entry["path"], "/code/caf\u{FFFD}");
+        assert_eq!(bytes(&entry["path_bytes"]), b"/code/caf\xe9");
+        assert_eq!(bytes(&entry["dep_dirs"][0]["path_bytes"]), dep.as_os_str().as_bytes());
+
+        assert_eq!(bytes(&entry["name_bytes"]), b"caf\xe9");
+
+        let flat = serde_json::to_value(JsonReport::new(&projects, None, true, true)).unwrap();
+        assert_eq!(bytes(&flat["dep_dirs"][0]["project_path_bytes"]), b"/code/caf\xe9");
+        assert_eq!(bytes(&flat["dep_dirs"][0]["project_name_bytes"]), b"caf\xe9");
+
+        // Justamente as pastas que falharam precisam do caminho exato
+        let result = CleanResult {
+            total_freed: 0,
+            total_moved: 0,
+            dirs_removed: 0,
+            links_removed: Vec::new(),
+            skipped_open: Vec::new(),
+            errors: vec![(dep.clone(), CleanError::PermissionDenied("negado".to_string()))],
+            would_fail: Vec::new(),
+            partial: Vec::new(),
+            marker_missing: Vec::new(),
+            timings: Default::default(),
+            lockfiles_removed: Vec::new(),
+            passes: 1,
+            empty_parents_removed: Vec::new(),
+        };
+        let report = serde_json::to_value(JsonReport::new(&projects, Some(&result), false, false)).unwrap();
+        let error = &report["summary"]["error_details"][0];
+        assert_eq!(error["path"], "/code/caf\u{FFFD}/node_modules");
+        assert_eq!(bytes(&error["path_bytes"]), dep.as_os_str().as_bytes());
+
+        // Caminhos UTF-8 não ganham o campo extra
+        let plain = vec![StaleProject::new("web".to_string(), PathBuf::from("/code/web"), Vec::new(), SystemTime::now())];
+        let report = serde_json::to_value(JsonReport::new(&plain, None, true, false)).unwrap();
+        assert!(report["projects"][0].get("path_bytes").is_none());
+    }
+}
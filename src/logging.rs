@@ -0,0 +1,75 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Logger que repassa os registros ao `env_logger` (terminal, controlado por
+/// `RUST_LOG`) e, opcionalmente, grava todos até `debug` num arquivo de auditoria.
+struct TeeLogger {
+    terminal: env_logger::Logger,
+    file: Option<Mutex<File>>,
+}
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.terminal.enabled(metadata) || (self.file.is_some() && metadata.level() <= LevelFilter::Debug)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.terminal.matches(record) {
+            self.terminal.log(record);
+        }
+
+        if let Some(file) = &self.file {
+            if record.level() <= LevelFilter::Debug {
+                let mut f = file.lock().unwrap();
+                let _ = writeln!(
+                    f,
+                    "{} {:<5} {} {}",
+                    jiff::Timestamp::now(),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                );
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.terminal.flush();
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Inicializa o logging global. Sem `log_file`, equivale ao `env_logger` padrão.
+pub fn init(log_file: Option<&Path>) -> Result<()> {
+    let terminal = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).build();
+
+    let file = match log_file {
+        Some(path) => {
+            let f = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Não foi possível abrir o arquivo de log '{}'", path.display()))?;
+            Some(Mutex::new(f))
+        }
+        None => None,
+    };
+
+    let max_level = if file.is_some() {
+        terminal.filter().max(LevelFilter::Debug)
+    } else {
+        terminal.filter()
+    };
+
+    log::set_boxed_logger(Box::new(TeeLogger { terminal, file }))
+        .context("Logger já inicializado")?;
+    log::set_max_level(max_level);
+    Ok(())
+}
@@ -1,21 +1,29 @@
 use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
 mod types;
 mod projects;
+mod baseline;
 mod cleaner;
 mod display;
 mod scanner;
+mod staging;
 mod config;
+mod history;
+mod interactive;
+mod logging;
+mod report;
+mod json;
+mod hook;
+mod resume;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use config::{Config, ConfigError};
 
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result, bail};
 use clap::Parser;
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use log::{error, debug};
+use log::{error, debug, info, warn};
 
 #[derive(Parser, Debug)]
 #[command(name = "faxina-cli", version, about, long_about = None)]
@@ -26,60 +34,423 @@ struct Cli {
     #[arg(short, long)]
     days: Option<u64>,
 
+    #[arg(long)]
+    since_last_run: bool, // Inativo = sem modificação desde a última limpeza desta pasta (sem histórico, usa --days)
+
+    #[arg(long, value_name = "N")]
+    parallel_roots: Option<usize>, // Com --root-glob, varre até N raízes ao mesmo tempo
+
+    #[arg(long, value_name = "PADRÃO", conflicts_with = "path")]
+    root_glob: Option<String>, // Varre cada pasta que casa com o padrão como uma raiz separada (ex: "~/code/*/")
+
     #[arg(long)]
     dry_run: bool,
 
+    #[arg(long, requires = "dry_run")]
+    check_writable: bool, // No dry-run, testa (sem apagar) se cada pasta poderia ser removida
+
+    #[arg(long)]
+    atomic_per_project: bool, // Se uma pasta de um projeto falha, pula as demais do mesmo projeto
+
+    #[arg(long, value_name = "N")]
+    repeat: Option<u32>, // Até N passadas extras sobre as pastas que falharam (ex: travadas por um instante)
+
+    #[arg(long)]
+    verify_markers: bool, // Antes de cada remoção, confere se o marcador do projeto (ex: package.json) ainda existe
+
     #[arg(short, long)]
     yes: bool,
 
-    #[arg(short, long)]
-    verbose: bool,
+    #[arg(long)]
+    confirm_default_yes: bool, // Enter confirma a limpeza (o prompt continua aparecendo; padrão: Enter cancela)
+
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8, // -v: projetos avaliados na varredura; -vv: também o motivo e o mtime
 
     #[arg(short, long)]
-    quiet: bool,
+    quiet: bool, // Só imprime o total liberado; com --format json, vale o JSON
 
     #[arg(long)]
     stats: bool, // Exibe estatísticas e sai sem limpar
 
+    #[arg(long)]
+    probe: bool, // Diagnóstico de desempenho: pastas, arquivos e tempo de cada fase da varredura (no stderr)
+
+    #[arg(long, conflicts_with = "stats")]
+    with_stats: bool, // Exibe as estatísticas por tipo junto com a listagem, antes de confirmar
+
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    group_by_depth: Option<u64>, // Subtotais por pasta N níveis abaixo da raiz (ex: 1 = por cliente em ~/code)
+
     #[arg(short, long)]
     interactive: bool, // Modo interativo de seleção
 
+    #[arg(long, requires = "interactive")]
+    no_remember: bool, // No modo interativo, não pré-marca nem salva a última seleção
+
     #[arg(long, value_delimiter = ',')]
     excluded_dirs: Option<Vec<String>>, // Pastas para ignorar (separadas por vírgula)
 
     #[arg(long)]
     config: Option<PathBuf>, // Arquivo de configuração personalizado
+
+    #[arg(long, value_name = "NOME")]
+    profile: Option<String>, // Perfil [profiles.<NOME>] do config.toml aplicado sobre os valores do topo
+
+    #[arg(long)]
+    dedup_hardlinks: bool, // Conta arquivos com hard links apenas uma vez (ex: pnpm)
+
+    #[arg(long = "keep", value_name = "TIPO@CAMINHO")]
+    keep_rules: Vec<scanner::KeepRule>, // Preserva um tipo de dependência só sob um prefixo (repetível)
+
+    #[arg(long, value_name = "N")]
+    keep_recent: Option<usize>, // Preserva os N projetos mais recentes de cada tipo
+
+    #[arg(long, value_name = "ARQUIVO")]
+    report_html: Option<PathBuf>, // Gera um relatório HTML autocontido
+
+    #[arg(long, value_name = "DIAS")]
+    min_age: Option<u64>, // Ignora projetos cuja pasta de dependência foi criada há menos de N dias
+
+    #[arg(long, value_name = "ARQUIVO")]
+    log_file: Option<PathBuf>, // Grava um log de auditoria (inclui decisões de debug)
+
+    #[arg(long, value_name = "TIPO", value_delimiter = ',')]
+    only: Vec<types::DepKind>, // Detecta só estes tipos de dependência (repetível ou separados por vírgula)
+
+    #[arg(long = "prune-dir", value_name = "NOME")]
+    prune_dirs: Vec<String>, // Limpa qualquer pasta com este nome, sem exigir tipo de projeto (repetível)
+
+    #[arg(long, num_args = 1.., value_name = "CAMINHO")]
+    size_only: Vec<PathBuf>, // Só mede o tamanho das pastas informadas e sai (sem detectar projetos)
+
+    #[arg(long)]
+    resume: bool, // Continua uma limpeza interrompida, pulando as pastas já removidas
+
+    #[arg(long)]
+    history: bool, // Exibe o total liberado em execuções anteriores e sai
+
+    #[arg(long)]
+    protect_active_siblings: bool, // Preserva projetos com irmãos (mesma pasta pai) ativos
+
+    #[arg(long, value_name = "DIAS")]
+    protect_active_within: Option<u64>, // Janela de "ativo" para proteger pais/filhos/irmãos (padrão: --days)
+
+    #[arg(long)]
+    quiet_if_empty: bool, // Nenhuma saída quando não há projetos inativos
+
+    #[arg(long)]
+    follow_symlinks: bool, // Entra em pastas que são symlinks e aceita projetos em symlinks (padrão: ignora, com log)
+
+    #[arg(long, conflicts_with_all = ["stage", "move_to"])]
+    follow_symlink_deletes: bool, // Se a pasta de dependência for symlink, apaga também o destino (apagar de vez: não combina com --stage/--move-to)
+
+    #[arg(long, conflicts_with_all = ["interactive", "stats", "format"])]
+    print0: bool, // Só lista os caminhos das pastas, separados por NUL (para xargs -0), sem limpar
+
+    #[arg(long)]
+    list_all: bool, // Lista todos os projetos detectados (ativos ou não), sem calcular tamanhos, e sai
+
+    #[arg(long)]
+    preview: bool, // Mostra o conteúdo de primeiro nível das maiores pastas antes de confirmar
+
+    #[arg(long, value_name = "N")]
+    min_dirs: Option<usize>, // Só considera projetos com pelo menos N pastas de dependência
+
+    #[arg(long, value_name = "TAMANHO", value_parser = display::parse_size)]
+    max_size: Option<u64>, // Não limpa projetos maiores que o tamanho (ex: 20GB); lista-os para revisão manual
+
+    #[arg(long, value_name = "TAMANHO", value_parser = display::parse_size)]
+    ensure_free: Option<u64>, // Meta de espaço livre (ex: 50GB): se a limpeza não chegar lá, pergunta antes (aborta com --yes)
+
+    #[arg(long, value_name = "TAMANHO", value_parser = display::parse_size)]
+    collapse_below: Option<u64>, // Agrupa numa única linha os projetos menores que o tamanho (ex: 50MB)
+
+    #[arg(long, value_enum, default_value_t = cleaner::CleanOrder::Listed)]
+    clean_order: cleaner::CleanOrder, // Ordem de remoção das pastas: listed, size-desc, size-asc ou age (mais antigos primeiro)
+
+    #[arg(long)]
+    stage: bool, // Move as pastas para uma área de staging em vez de apagar (desfazível)
+
+    #[arg(long, value_name = "DIR", conflicts_with = "stage")]
+    move_to: Option<PathBuf>, // Move as pastas para um diretório de arquivo em vez de apagar
+
+    #[arg(long, conflicts_with_all = ["stage", "move_to"])]
+    remove_lockfiles: bool, // Apaga também os lockfiles (package-lock.json, Cargo.lock...) dos projetos limpos
+
+    #[arg(long, conflicts_with = "empty_staged")]
+    restore_staged: bool, // Devolve as pastas do staging ao lugar original e sai
+
+    #[arg(long)]
+    empty_staged: bool, // Apaga de vez as pastas do staging e sai
+
+    #[arg(long)]
+    protect_open: bool, // Pula pastas com arquivos abertos por algum processo (ex: build em andamento)
+
+    #[arg(long)]
+    protect_dirty: bool, // Preserva projetos em repositórios git com alterações não commitadas
+
+    #[arg(long)]
+    skip_submodules: bool, // Preserva projetos dentro de submódulos git
+
+    #[arg(long)]
+    protect_if_manifest_newer_than_deps: bool, // Preserva projetos cujo manifesto foi editado depois das dependências
+
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>, // Threads das varreduras (descoberta, mtime e tamanhos); 1 = sem paralelismo
+
+    #[arg(long, value_name = "SEGUNDOS")]
+    scan_timeout: Option<u64>, // Interrompe a varredura após o prazo e usa só os projetos já completos (a leitura das datas tem um prazo igual, à parte; com --root-glob, o prazo vale por raiz)
+
+    #[arg(long)]
+    aggressive: bool, // Atalho: todos os tipos (inclui dist/), caches Python (__pycache__ etc.), --prune-empty-parents e 14 dias por padrão; flags explícitas prevalecem
+
+    #[arg(long)]
+    prune_empty_parents: bool, // Depois de remover uma pasta, apaga os diretórios acima dela que ficaram vazios (até a raiz do projeto)
+
+    #[arg(long)]
+    include_dist: bool, // Também limpa `dist/` com sinal forte de build (vite/rollup ou script que gera dist)
+
+    #[arg(long)]
+    show_newest: bool, // Mostra qual arquivo definiu a data de modificação de cada projeto
+
+    #[arg(long)]
+    show_counts: bool, // Mostra quantos arquivos cada pasta tem (destaca as com 100 mil+)
+
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_results: Option<u64>, // Mantém só os N maiores projetos, na listagem e na limpeza
+
+    #[arg(long, value_name = "ARQUIVO")]
+    safe_list: Option<PathBuf>, // Caminhos que nunca são limpos (padrão: safe-list.txt no diretório de config)
+
+    #[arg(long)]
+    ignore_case: bool, // Compara as pastas de --excluded-dirs sem diferenciar maiúsculas (macOS/Windows)
+
+    #[arg(short = 'x', long)]
+    one_file_system: bool, // Não entra em pastas de outro sistema de arquivos (discos externos, rede), como find -xdev
+
+    #[arg(long)]
+    include_dir_mtime: bool, // Conta também a data da própria pasta do projeto (renomeações, arquivos apagados)
+
+    #[arg(long)]
+    no_descend_into_stale: bool, // Não relê projetos aninhados dentro de um projeto inativo (mesmo resultado, menos leituras)
+
+    #[arg(long = "mtime-ignore", value_name = "GLOB")]
+    mtime_ignore: Vec<glob::Pattern>, // Arquivos que não contam para a data de modificação (repetível, ex: "*.bak")
+
+    #[arg(long, value_enum, default_value_t = display::OutputFormat::Text)]
+    format: display::OutputFormat, // Layout da listagem: blocos (text), tabela alinhada (table) ou JSON (json)
+
+    #[arg(long, value_enum, ignore_case = true, default_value_t = display::SizeUnit::Auto)]
+    size_unit: display::SizeUnit, // Força a unidade dos tamanhos (auto, B, KB, MB, GB); B = bytes exatos
+
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    size_precision: usize, // Casas decimais dos tamanhos
+
+    #[arg(long)]
+    flatten: bool, // Lista uma entrada por pasta de dependência (com o projeto dono) em vez de agrupar por projeto
+
+    #[arg(long, value_enum, default_value_t = display::ProgressMode::Auto)]
+    progress: display::ProgressMode, // Barras de progresso: auto (só em terminal) ou off (nunca)
+
+    #[arg(long, value_name = "COMANDO")]
+    post_clean_cmd: Option<String>, // Comando (sh -c; cmd /V:ON /C no Windows) rodado após uma limpeza real sem erros; aceita {freed}, {freed_bytes}, {moved}, {moved_bytes}, {dirs}, {errors}, {root}
+
+    #[arg(long, requires = "post_clean_cmd")]
+    post_clean_always: bool, // Roda o --post-clean-cmd mesmo se houve erros
+
+    #[arg(long, value_name = "ARQUIVO")]
+    save_baseline: Option<PathBuf>, // Salva os projetos inativos desta varredura (caminho, tamanho, data) para comparar depois
+
+    #[arg(long, value_name = "ARQUIVO")]
+    compare_baseline: Option<PathBuf>, // Mostra o que mudou desde um baseline salvo: novos inativos, reativados e tamanhos
+
+    #[arg(long)]
+    init_config: bool, // Cria um config.toml comentado com todas as opções e sai
+
+    #[arg(long, requires = "init_config")]
+    force: bool, // Com --init-config, sobrescreve um arquivo existente
+
+    #[arg(long)]
+    json_schema: bool, // Imprime o JSON Schema da saída de --format json e sai
+
+    #[arg(long)]
+    list_types: bool, // Lista os tipos de projeto detectados (pastas, arquivos que a detecção checa, ids) e sai
+
+    #[arg(long)]
+    ascii: bool, // Tags ASCII ([node], [rust], [!]...) no lugar dos ícones emoji; automático fora de um terminal UTF-8 (FAXINA_ICONS=emoji/ascii desliga a detecção)
+
+    #[arg(long)]
+    summary_json_stderr: bool, // Escreve também um resumo JSON de uma linha no stderr
 }
 
 fn main() -> Result<()> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-    let result = run();
+    let cli = Cli::parse();
+    let result = logging::init(cli.log_file.as_deref()).and_then(|_| run(cli));
     pause_on_windows();
     result
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+fn run(mut cli: Cli) -> Result<()> {
+    if cli.aggressive {
+        apply_aggressive(&mut cli);
+    }
+
+    if cli.json_schema {
+        println!("{}", serde_json::to_string_pretty(&json::schema())?);
+        return Ok(());
+    }
+
+    if cli.init_config {
+        let path = match &cli.config {
+            Some(path) => path.clone(),
+            None => Config::default_path()?,
+        };
+        Config::write_default(&path, cli.force)?;
+        println!("  {} Configuração criada em {}", display::icon("✅"), path.display().to_string().bold());
+        return Ok(());
+    }
+
+    if cli.restore_staged || cli.empty_staged {
+        let staging_dir = staging::default_dir()
+            .context("Diretório de cache não encontrado para a área de staging")?;
+        if cli.restore_staged {
+            let (restored, errors) = staging::restore_all(&staging_dir);
+            println!("  {}  {} pastas devolvidas ao lugar original.", display::icon("↩️"), restored.to_string().bold().green());
+            for (path, err) in &errors {
+                println!("    {} {} — {}", display::icon("✗").red(), path.display(), err);
+            }
+        } else {
+            let (removed, freed) = staging::empty(&staging_dir)?;
+            println!(
+                "  {}  Staging esvaziado: {} pastas, {} liberados.",
+                display::icon("🗑️"),
+                removed.to_string().bold(),
+                display::format_size(freed).bold().green()
+            );
+        }
+        return Ok(());
+    }
+
+    // O que vai para o stdout em cada combinação de --format e --quiet:
+    //
+    // | --format     | --quiet | stdout                                                |
+    // |--------------|---------|-------------------------------------------------------|
+    // | text / table | não     | listagem e resumo                                     |
+    // | text / table | sim     | só o total liberado (um número) após limpar/simular;  |
+    // |              |         | nada sem projetos, com --stats ou se cancelada        |
+    // | json         | ignorado| só JSON: um documento (JSON Lines na limpeza real),   |
+    // |              |         | vazio se nada sobrou para limpar; nada se cancelada   |
+    // | (--print0)   | ignorado| só os caminhos das pastas separados por NUL; não limpa|
+    //
+    // JSON vence --quiet: a saída humana entra em modo quiet e o número do
+    // --quiet não é impresso. Avisos, logs e prompts vão para o stderr em todos
+    // os modos. Comandos avulsos (--list-types, --size-only, --json-schema,
+    // --restore-staged, --empty-staged) têm saída própria e ignoram os dois.
+    let json_output = cli.format == display::OutputFormat::Json;
+    if json_output || cli.print0 {
+        cli.quiet = true;
+    }
 
-    let root = cli.path.canonicalize()
-        .with_context(|| format!("Não foi possível acessar '{}'", cli.path.display()))?;
+    display::set_size_format(display::SizeFormat {
+        unit: cli.size_unit,
+        precision: cli.size_precision,
+    });
+    display::set_ascii_icons(cli.ascii || display::detect_ascii_icons());
+
+    if cli.list_types {
+        println!();
+        print!("{}", display::render_project_types());
+        println!();
+        return Ok(());
+    }
+
+    if !cli.size_only.is_empty() {
+        let sizes = size_only(&cli.size_only, &types::WalkJobs::new(cli.jobs));
+        display::print_sizes(&sizes);
+        return Ok(());
+    }
+
+    if cli.history {
+        let entries = history::default_path()
+            .map(|p| history::load(&p))
+            .unwrap_or_default();
+        display::print_history(&entries);
+        return Ok(());
+    }
+
+    // Com --root-glob, a parte fixa do padrão faz o papel de PATH para o que é
+    // guardado por raiz (retomada, seleção, histórico)
+    let glob_roots = match &cli.root_glob {
+        Some(pattern) => {
+            let pattern = config::expand_path(pattern)
+                .with_context(|| format!("Variável de ambiente não definida em '{}'", pattern))?;
+            cli.path = glob_base(&pattern);
+            Some(expand_root_glob(&pattern, cli.follow_symlinks)?)
+        }
+        // O `requires` do clap não pega este caso: com PATH presente, o conflito
+        // dele com --root-glob dispensa a exigência
+        None if cli.parallel_roots.is_some() => bail!("--parallel-roots só vale com --root-glob."),
+        None => None,
+    };
+
+    // Antes de canonicalizar, que resolveria o link e esconderia a recusa
+    if cli.path.is_symlink() && !cli.follow_symlinks {
+        bail!("'{}' é um symlink; use --follow-symlinks para varrê-lo.", cli.path.display());
+    }
+
+    let root = match cli.path.canonicalize() {
+        Ok(p) => p,
+        Err(e) => {
+            // Montagens de rede e ancestrais com symlinks quebrados: segue com o
+            // caminho absoluto sem resolver, em vez de abortar.
+            let lexical = scanner::lexical_absolute(&cli.path);
+            if !lexical.is_dir() {
+                return Err(e).with_context(|| format!("Não foi possível acessar '{}'", cli.path.display()));
+            }
+            warn!("Não foi possível resolver '{}' ({}); usando '{}'.", cli.path.display(), e, lexical.display());
+            lexical
+        }
+    };
+
+    // Destino de --move-to: criado já, para um caminho inválido falhar antes da varredura
+    let move_to = match &cli.move_to {
+        Some(dir) if !cli.dry_run => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Não foi possível criar o diretório de --move-to '{}'", dir.display()))?;
+            Some(dir.canonicalize().unwrap_or_else(|_| dir.clone()))
+        }
+        other => other.clone(),
+    };
+
+    // Sem diretório de cache, --stage viraria remoção definitiva
+    let stage_dir = if cli.stage {
+        let dir = staging::default_dir()
+            .context("Não foi possível determinar o diretório de cache para o staging de --stage")?;
+        Some(dir)
+    } else {
+        None
+    };
 
     // Carrega configuração com tratamento de erro robusto
     let config_result = if let Some(path) = &cli.config {
-        Config::load_from_path(path)
+        Config::load_from_path(path, cli.profile.as_deref())
     } else {
-        Config::load()
+        Config::load(cli.profile.as_deref())
     };
 
     let config = match config_result {
         Ok(c) => c,
         Err(e) => match e {
             ConfigError::NotFound => {
-                if cli.config.is_some() {
-                    bail!("Arquivo de configuração especificado não encontrado: {}", cli.config.unwrap().display());
+                if let Some(path) = &cli.config {
+                    bail!("Arquivo de configuração especificado não encontrado: {}", path.display());
+                }
+                if let Some(name) = &cli.profile {
+                    bail!("Perfil '{}' não encontrado: nenhum arquivo de configuração.", name);
                 }
                 debug!("Arquivo de configuração não encontrado, usando defaults.");
-                Config { days: None, excluded_dirs: None, auto_confirm: None }
+                Config::default()
             },
             ConfigError::ParseError(path, msg) => {
                 error!("Erro fatal no arquivo de configuração '{}': {}", path.display(), msg);
@@ -87,45 +458,89 @@ fn run() -> Result<()> {
             },
             ConfigError::IoError(err) => {
                 error!("Erro de I/O ao ler configuração: {}", err);
-                Config { days: None, excluded_dirs: None, auto_confirm: None }
+                // Sem o arquivo o perfil pedido não existe: os defaults podem
+                // ser mais agressivos que ele
+                if let Some(name) = &cli.profile {
+                    bail!("Perfil '{}' indisponível: erro ao ler a configuração: {}", name, err);
+                }
+                Config::default()
             }
+            e @ ConfigError::UnknownProfile(..) => bail!("{}", e),
         }
     };
 
-    let days = cli.days.or(config.days).unwrap_or(30);
+    let default_days = if cli.aggressive { AGGRESSIVE_DAYS } else { 30 };
+    let mut days = cli.days.or(config.days).unwrap_or(default_days);
+
+    // --since-last-run: o limite é a última limpeza desta raiz; sem histórico, --days
+    let stale_before = if cli.since_last_run {
+        let last = history::default_path().and_then(|p| history::last_run(&history::load(&p), &root));
+        match last {
+            Some(when) => {
+                days = SystemTime::now().duration_since(when).map(|d| d.as_secs() / (24 * 3600)).unwrap_or(0);
+                let shown = jiff::Timestamp::try_from(when).map(|t| t.to_string()).unwrap_or_default();
+                info!("--since-last-run: inativo = sem modificação desde {}", shown);
+            }
+            None => warn!("Nenhuma execução anterior registrada para '{}'; usando {} dias.", root.display(), days),
+        }
+        last
+    } else {
+        None
+    };
     let auto_confirm = cli.yes || config.auto_confirm.unwrap_or(false);
+    let confirm_default_yes = cli.confirm_default_yes || config.confirm_default_yes.unwrap_or(false);
+    let confirm_policies = config::ConfirmPolicies::from_config(config.confirm);
 
-    let mut ignored_paths: Vec<PathBuf> = config.excluded_dirs
+    let ignored_paths: Vec<PathBuf> = config.excluded_dirs
         .unwrap_or_default()
-        .iter()
-        .map(|p| PathBuf::from(p))
+        .into_iter()
+        .chain(cli.excluded_dirs.unwrap_or_default())
+        .filter_map(|raw| match config::expand_path(&raw) {
+            Some(p) => Some(PathBuf::from(p)),
+            None => {
+                warn!("Variável de ambiente não definida em '{}'; exclusão ignorada.", raw);
+                None
+            }
+        })
+        .map(absolutize)
         .collect();
 
-    if let Some(cli_excludes) = cli.excluded_dirs {
-        for p in cli_excludes {
-            ignored_paths.push(PathBuf::from(p));
-        }
-    }
-
-    let ignored_paths: Vec<PathBuf> = ignored_paths.into_iter()
-        .map(|p| if p.is_absolute() { p } else { std::env::current_dir().unwrap_or_default().join(p) })
+    let keep_rules: Vec<scanner::KeepRule> = cli.keep_rules.into_iter()
+        .map(|r| scanner::KeepRule { prefix: absolutize(r.prefix), ..r })
         .collect();
 
     if !root.is_dir() {
         bail!("'{}' não é um diretório.", root.display());
     }
 
-    if !cli.quiet {
+    // A safe-list padrão é opcional; uma passada em --safe-list precisa existir
+    let safe_list: Vec<PathBuf> = match &cli.safe_list {
+        Some(path) => config::load_safe_list(path)
+            .with_context(|| format!("Não foi possível ler a safe-list '{}'", path.display()))?,
+        None => config::default_safe_list_path()
+            .and_then(|p| config::load_safe_list(&p).ok())
+            .unwrap_or_default(),
+    };
+    let safe_list: Vec<PathBuf> = safe_list.into_iter().map(absolutize).collect();
+
+    if !cli.prune_dirs.is_empty() {
+        warn!("--prune-dir ativo para: {}", cli.prune_dirs.join(", "));
+    }
+
+    // Com --quiet-if-empty o cabeçalho só aparece depois de sabermos que há algo a mostrar
+    let print_preamble = || {
         display::print_header();
+        if !cli.prune_dirs.is_empty() {
+            display::print_prune_warning(&cli.prune_dirs);
+        }
+    };
+    let show_progress = !cli.quiet && !cli.quiet_if_empty;
+    if show_progress {
+        print_preamble();
     }
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::with_template("  {spinner:.green} {msg}")
-            .unwrap()
-            .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
-    );
-    if !cli.quiet {
+    let spinner = display::new_spinner(cli.progress);
+    if show_progress {
         spinner.enable_steady_tick(Duration::from_millis(80));
         spinner.set_message(format!(
             "Varrendo {} (projetos inativos há {}+ dias)...",
@@ -141,94 +556,358 @@ fn run() -> Result<()> {
     let checked_files_clone = checked_files.clone();
 
     let on_progress = move || {
-        let count = checked_files_clone.fetch_add(1, Ordering::Relaxed);
-        if count % 500 == 0 {
-            progress_spinner.set_message(format!(
-                "Varrendo {} (projetos inativos há {}+ dias)... {} arquivos",
-                root_display.bold(),
-                days_display.bold(),
-                count.to_string().dimmed()
-            ));
+        let count = checked_files_clone.fetch_add(1, Ordering::Relaxed) + 1;
+        if display::scan_progress_due(count) {
+            progress_spinner.set_message(display::scan_progress_message(&root_display, &days_display, count));
         }
     };
+    let root_display = root.display().to_string();
+    let finish_scan = || {
+        display::finish_scan_spinner(&spinner, &root_display, checked_files.load(Ordering::Relaxed), show_progress);
+    };
 
-    let mut projects = scanner::scan_projects(&root, days, &ignored_paths, Some(on_progress));
-    spinner.finish_and_clear();
+    let mut mtime_ignore: Vec<glob::Pattern> = Vec::new();
+    for raw in scanner::DEFAULT_MTIME_IGNORE.iter().map(|s| s.to_string())
+        .chain(config.mtime_ignore.clone().unwrap_or_default())
+    {
+        match glob::Pattern::new(&raw) {
+            Ok(p) => mtime_ignore.push(p),
+            Err(e) => warn!("Padrão inválido em mtime_ignore '{}': {}", raw, e),
+        }
+    }
+    mtime_ignore.extend(cli.mtime_ignore.iter().cloned());
+
+    let scan_options = scanner::ScanOptions {
+        days,
+        stale_before,
+        ignored_paths,
+        keep_rules,
+        min_age_days: cli.min_age,
+        prune_dirs: cli.prune_dirs.clone(),
+        protect_active_siblings: cli.protect_active_siblings,
+        protect_active_within: cli.protect_active_within,
+        protect_dirty: cli.protect_dirty,
+        skip_submodules: cli.skip_submodules,
+        protect_manifest_newer: cli.protect_if_manifest_newer_than_deps,
+        collect_protected: cli.stats || cli.verbose > 0,
+        jobs: types::WalkJobs::new(cli.jobs.or(config.jobs)),
+        scan_timeout: cli.scan_timeout.map(Duration::from_secs),
+        mtime_ignore,
+        include_dir_mtime: cli.include_dir_mtime,
+        one_file_system: cli.one_file_system,
+        ignore_case: cli.ignore_case,
+        safe_list,
+        show_newest: cli.show_newest,
+        include_dist: cli.include_dist,
+        only_kinds: cli.only.clone(),
+        follow_symlinks: cli.follow_symlinks,
+        probe: cli.probe.then(Default::default),
+        no_descend_into_stale: cli.no_descend_into_stale,
+    };
+    let probe = scan_options.probe.clone();
+
+    let scan_roots = glob_roots.unwrap_or_else(|| vec![root.clone()]);
+
+    if cli.list_all {
+        let infos: Vec<_> = scan_roots.iter()
+            .flat_map(|r| scanner::discover_projects(r, &scan_options, Some(on_progress.clone())))
+            .collect();
+        finish_scan();
+        display::print_project_list(&infos);
+        if let Some(counters) = &probe {
+            display::print_probe(counters);
+        }
+        return Ok(());
+    }
+
+    let outcome = scanner::scan_roots(&scan_roots, &scan_options, on_progress, cli.parallel_roots.unwrap_or(1));
+    if !outcome.safe_listed.is_empty() {
+        info!("{} caminhos protegidos pela safe-list", outcome.safe_listed.len());
+    }
+    let mut projects = outcome.stale;
+    let mut protected = outcome.protected;
+    finish_scan();
+
+    if !cli.quiet {
+        display::print_unreadable(&outcome.unreadable, cli.verbose > 0);
+        display::print_safe_listed(&outcome.safe_listed, cli.verbose > 0);
+        if cli.verbose > 0 {
+            display::print_scan_decisions(&projects, &protected, cli.verbose);
+        }
+    }
+
+    if let Some(min_dirs) = cli.min_dirs {
+        projects.retain(|p| p.dep_dirs().len() >= min_dirs);
+    }
+
+    // Política "never" em [confirm]: essas pastas nem entram na lista
+    if !confirm_policies.is_empty() {
+        let (never, kept) = cleaner::partition_dirs(projects, |d| {
+            confirm_policies.for_kind(&d.kind) == config::ConfirmPolicy::Never
+        });
+        projects = kept;
+        let skipped: usize = never.iter().map(|p| p.dep_dirs().len()).sum();
+        if skipped > 0 && !cli.quiet {
+            println!(
+                "  {} {} pastas mantidas pela política \"never\" em [confirm].",
+                display::icon("🚫"),
+                skipped.to_string().bold()
+            );
+            println!();
+        }
+    }
+
+    let manifest = if cli.dry_run { None } else { resume::manifest_path(&root) };
+    if cli.resume {
+        if let Some(path) = &manifest {
+            let done = resume::load(path);
+            let skipped = resume::skip_completed(&mut projects, &done);
+            if skipped > 0 && !cli.quiet {
+                let remaining: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+                println!(
+                    "  {}  {} pastas já limpas, continuando com {}.",
+                    display::icon("⏯️"),
+                    skipped.to_string().bold(),
+                    remaining.to_string().bold()
+                );
+                println!();
+            }
+        }
+    }
+
+    // --stats: o que a varredura preservou aparece antes de qualquer saída
+    // antecipada, inclusive quando não há nada a limpar (é quando se pergunta)
+    if cli.stats && !cli.quiet {
+        scanner::calculate_sizes(&mut protected, cli.dedup_hardlinks, cli.follow_symlink_deletes, &scan_options.jobs, None, None);
+        display::print_protection_stats(&protected);
+    }
 
     if projects.is_empty() {
-        if !cli.quiet {
+        if let Some(counters) = &probe {
+            display::print_probe(counters);
+        }
+        handle_baselines(cli.compare_baseline.as_deref(), cli.save_baseline.as_deref(), cli.quiet, &projects)?;
+        if json_output {
+            json::print_report(&json::JsonReport::new(&projects, None, cli.dry_run, cli.flatten));
+        } else if !cli.quiet && !cli.quiet_if_empty {
             display::print_no_stale_projects(days);
         }
         return Ok(());
     }
 
-    // Calcula tamanhos dos diretórios de dependência (fase separada para performance)
-    let size_spinner = ProgressBar::new_spinner();
-    size_spinner.set_style(
-        ProgressStyle::with_template("  {spinner:.green} {msg}")
-            .unwrap()
-            .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
-    );
-    if !cli.quiet {
-        size_spinner.enable_steady_tick(Duration::from_millis(80));
-        size_spinner.set_message(format!(
-            "Calculando tamanhos de {} projetos...",
-            projects.len().to_string().bold()
-        ));
+    if !cli.quiet && cli.quiet_if_empty {
+        print_preamble();
+    }
+
+    // --print0 só lista caminhos: mede só se um filtro ou saída usar os tamanhos
+    let needs_sizes = !cli.print0
+        || cli.max_size.is_some()
+        || cli.max_results.is_some()
+        || cli.save_baseline.is_some()
+        || cli.compare_baseline.is_some()
+        || cli.report_html.is_some();
+    if needs_sizes {
+        // Calcula tamanhos dos diretórios de dependência (fase separada para performance)
+        let size_spinner = display::new_spinner(cli.progress);
+        if !cli.quiet {
+            size_spinner.enable_steady_tick(Duration::from_millis(80));
+            size_spinner.set_message(format!(
+                "Calculando tamanhos de {} projetos...",
+                projects.len().to_string().bold()
+            ));
+        }
+        // Com --max-size, basta saber que um projeto passa do limite; o baseline
+        // precisa dos tamanhos exatos de todos
+        let size_cap = cli.max_size.filter(|_| cli.save_baseline.is_none() && cli.compare_baseline.is_none());
+        scanner::calculate_sizes(&mut projects, cli.dedup_hardlinks, cli.follow_symlink_deletes, &scan_options.jobs, probe.as_deref(), size_cap);
+        size_spinner.finish_and_clear();
+    } else {
+        // Sem tamanhos, a ordem por caminho mantém a saída estável
+        projects.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    if let Some(counters) = &probe {
+        display::print_probe(counters);
+    }
+
+    handle_baselines(cli.compare_baseline.as_deref(), cli.save_baseline.as_deref(), cli.quiet, &projects)?;
+
+    if let Some(n) = cli.keep_recent {
+        let kept = scanner::split_keep_recent(&mut projects, n);
+        if !cli.quiet {
+            display::print_kept_recent(&kept);
+        }
+        if projects.is_empty() {
+            if json_output {
+                json::print_report(&json::JsonReport::new(&projects, None, cli.dry_run, cli.flatten));
+            } else if !cli.quiet {
+                display::print_no_stale_projects(days);
+            }
+            return Ok(());
+        }
+    }
+
+    if let Some(limit) = cli.max_size {
+        let (oversized, rest): (Vec<_>, Vec<_>) =
+            projects.into_iter().partition(|p| p.total_size() > limit);
+        projects = rest;
+        if !cli.quiet {
+            display::print_oversized(&oversized, limit);
+        }
+        if projects.is_empty() {
+            if json_output {
+                json::print_report(&json::JsonReport::new(&projects, None, cli.dry_run, cli.flatten));
+            } else if !cli.quiet {
+                display::print_no_stale_projects(days);
+            }
+            return Ok(());
+        }
+    }
+
+    // Já ordenados por tamanho: o recorte define também o que é limpo
+    if let Some(n) = cli.max_results {
+        let total = projects.len();
+        if total > n as usize {
+            projects.truncate(n as usize);
+            if !cli.quiet {
+                display::print_truncated(projects.len(), total, cli.dry_run);
+            }
+        }
+    }
+
+    if let Some(path) = &cli.report_html {
+        report::write_html(path, &projects, cli.dry_run)?;
+        if !cli.quiet {
+            println!("  {} Relatório salvo em {}", display::icon("📄"), path.display().to_string().bold());
+            println!();
+        }
+    }
+
+    if cli.print0 {
+        display::print_null_separated(&projects)?;
+        return Ok(());
     }
-    scanner::calculate_sizes(&mut projects);
-    size_spinner.finish_and_clear();
 
     if cli.stats {
         if !cli.quiet {
             display::print_stats(&projects);
+            if let Some(depth) = cli.group_by_depth {
+                display::print_depth_groups(&projects, &scan_roots, depth as usize);
+            }
+        } else if json_output {
+            json::print_report(&json::JsonReport::new(&projects, None, cli.dry_run, cli.flatten));
         }
         return Ok(());
     }
 
     // Modo Interativo
     if cli.interactive {
-        use dialoguer::{theme::ColorfulTheme, MultiSelect};
+        let selection_file = if cli.no_remember { None } else { interactive::selection_path(&root) };
+        let remembered = selection_file
+            .as_deref()
+            .map(interactive::load_selection)
+            .unwrap_or_default();
+        let selected = interactive::select_projects(&projects, &remembered)?;
+        if let Some(path) = &selection_file {
+            if let Err(e) = interactive::save_selection(path, &selected) {
+                warn!("Não foi possível salvar a seleção em '{}': {}", path.display(), e);
+            }
+        }
 
-        println!();
-        println!("  {}", "Selecione os projetos para limpar (Espaço para selecionar, Enter para confirmar):".bold());
+        if selected.is_empty() {
+            if json_output {
+                json::print_report(&json::JsonReport::new(&selected, None, cli.dry_run, cli.flatten));
+            } else if !cli.quiet {
+                println!();
+                println!("  {} Nenhum projeto selecionado.", display::icon("↩").dimmed());
+                println!();
+            }
+            return Ok(());
+        }
 
-        let project_labels: Vec<String> = projects.iter().map(|p| {
-            let size = p.total_size();
-            let size_str = display::format_size(size);
-            format!("{} ({}) - {}", p.path.display(), size_str, p.dep_dirs.iter().map(|d| d.kind.icon()).collect::<Vec<_>>().join(" "))
-        }).collect();
+        projects = selected;
+    }
 
-        let selections = MultiSelect::with_theme(&ColorfulTheme::default())
-            .items(&project_labels)
-            .interact()?;
+    if !cli.quiet {
+        match cli.format {
+            display::OutputFormat::Text if cli.flatten => display::print_flat_results(&projects, cli.show_counts),
+            display::OutputFormat::Text => display::print_scan_results(&projects, cli.collapse_below, cli.show_counts, cli.show_newest),
+            display::OutputFormat::Table => display::print_table(&projects, cli.flatten),
+            display::OutputFormat::Json => {}
+        }
+    }
 
-        if selections.is_empty() {
-             println!();
-             println!("  {} Nenhum projeto selecionado.", "↩".dimmed());
-             println!();
-             return Ok(());
+    if let Some(depth) = cli.group_by_depth {
+        if !cli.quiet && cli.format != display::OutputFormat::Json {
+            display::print_depth_groups(&projects, &scan_roots, depth as usize);
         }
+    }
 
-        projects = selections.into_iter().map(|i| projects[i].clone()).collect();
+    if cli.preview && !cli.quiet {
+        display::print_preview(&projects);
     }
 
-    if !cli.quiet {
-        display::print_scan_results(&projects);
+    if cli.with_stats && !cli.quiet {
+        display::print_stats(&projects);
     }
 
-    if !auto_confirm {
-        if !display::confirm_cleanup(cli.dry_run) {
-            println!();
-            println!("  {} Limpeza cancelada.", "↩".dimmed());
+    // --ensure-free: espaço livre atual + tudo o que vai ser liberado. Com
+    // --stage/--move-to as pastas só mudam de lugar: no mesmo disco, nada sai
+    if let Some(target) = cli.ensure_free {
+        let destination = if cli.stage { stage_dir.clone() } else { move_to.clone() };
+        let reclaimable: u64 = match destination {
+            Some(dest) if cleaner::same_filesystem(&root, &dest) => 0,
+            _ => projects.iter().map(|p| p.total_size()).sum(),
+        };
+        match cleaner::free_space(&root) {
+            None => warn!("Não foi possível consultar o espaço livre em '{}'; --ensure-free ignorado.", root.display()),
+            Some(free) if free.saturating_add(reclaimable) < target => {
+                display::print_free_space_shortfall(free, reclaimable, target);
+                if !cli.dry_run {
+                    if auto_confirm {
+                        bail!("Limpeza abortada: a meta de --ensure-free não seria alcançada.");
+                    }
+                    if !display::confirm_short_of_goal() {
+                        if !cli.quiet {
+                            println!();
+                            println!("  {} Limpeza cancelada.", display::icon("↩").dimmed());
+                            println!();
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    // Política "always" em [confirm]: essas pastas dispensam a pergunta
+    let is_always = |d: &types::DepDir| confirm_policies.for_kind(&d.kind) == config::ConfirmPolicy::Always;
+    let all_always = projects.iter().flat_map(|p| p.dep_dirs()).all(is_always);
+    // Dry-run não pergunta nada: só o aviso abaixo, que respeita --quiet
+    if !auto_confirm && !all_always && !cli.dry_run {
+        if !display::confirm_cleanup(confirm_default_yes) {
+            let (always, _) = cleaner::partition_dirs(projects, is_always);
+            if always.is_empty() {
+                if !cli.quiet {
+                    println!();
+                    println!("  {} Limpeza cancelada.", display::icon("↩").dimmed());
+                    println!();
+                }
+                return Ok(());
+            }
+            if !cli.quiet {
+                println!();
+                println!("  {} Limpando só as pastas com política \"always\" em [confirm].", display::icon("↩").dimmed());
+            }
+            projects = always;
+        }
+        if !cli.quiet {
             println!();
-            return Ok(());
         }
-        println!();
     } else if cli.dry_run && !cli.quiet {
         println!(
             "  {}",
-            "🔍 Modo dry-run: nenhum arquivo será deletado."
+            format!("{} Modo dry-run: nenhum arquivo será deletado.", display::icon("🔍"))
                 .bold()
                 .blue()
         );
@@ -237,12 +916,204 @@ fn run() -> Result<()> {
 
 
 
-    let result = cleaner::clean_projects(&projects, cli.dry_run, cli.verbose);
-    display::print_summary(&result, cli.dry_run, cli.quiet);
+    if cli.dry_run && cli.verbose > 0 && !cli.quiet {
+        display::print_dry_run_breakdown(&projects);
+    }
+
+    if !cli.dry_run && !cli.quiet {
+        display::print_cleanup_recap(&projects);
+    }
+
+    if let Some(path) = &manifest {
+        let started = if cli.resume && path.exists() { Ok(()) } else { resume::start(path) };
+        if let Err(e) = started {
+            warn!("Não foi possível criar o manifesto de progresso '{}': {}", path.display(), e);
+        }
+    }
+
+    let clean_options = cleaner::CleanOptions {
+        dry_run: cli.dry_run,
+        verbose: cli.verbose > 0,
+        follow_symlinks: cli.follow_symlink_deletes,
+        progress_manifest: manifest,
+        protect_open: cli.protect_open,
+        hide_progress: cli.progress == display::ProgressMode::Off,
+        order: cli.clean_order,
+        stage_dir,
+        move_to: move_to.clone(),
+        check_writable: cli.check_writable,
+        atomic_per_project: cli.atomic_per_project,
+        verify_markers: cli.verify_markers,
+        remove_lockfiles: cli.remove_lockfiles,
+        repeat: cli.repeat.unwrap_or(0),
+        prune_empty_parents: cli.prune_empty_parents,
+        quiet: cli.quiet,
+        delete_rates: history::throughput_path().map(|p| history::load_throughput(&p)).unwrap_or_default(),
+    };
+    // Limpeza real em JSON: uma linha por pasta à medida que termina
+    let streaming = json_output && !cli.dry_run;
+    let result = if streaming {
+        cleaner::clean_projects_streaming(&projects, &clean_options, json::print_dir_event)
+    } else {
+        cleaner::clean_projects(&projects, &clean_options)
+    };
+    if streaming {
+        json::print_report_line(&json::JsonReport::new(&projects, Some(&result), cli.dry_run, cli.flatten));
+    } else if json_output {
+        json::print_report(&json::JsonReport::new(&projects, Some(&result), cli.dry_run, cli.flatten));
+    } else {
+        display::print_summary(&result, cli.dry_run, cli.quiet, move_to.as_deref());
+    }
+    if cli.summary_json_stderr {
+        display::print_summary_json(&result, projects.len());
+    }
+
+    if let Some(template) = &cli.post_clean_cmd {
+        if !cli.dry_run && (result.errors.is_empty() || cli.post_clean_always) {
+            match hook::run_post_clean(template, &result, &root) {
+                Ok(status) if !status.success() => {
+                    warn!("Comando pós-limpeza terminou com {}", status);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Não foi possível executar o comando pós-limpeza: {}", e),
+            }
+        }
+    }
+
+    if cli.stage && !cli.dry_run && !cli.quiet {
+        println!(
+            "  {} As pastas foram movidas para a área de staging. Confira seus projetos e depois rode {} para apagar de vez ou {} para desfazer.",
+            display::icon("📥"),
+            "--empty-staged".bold(),
+            "--restore-staged".bold()
+        );
+        println!();
+    }
+
+    // Pastas em staging ou movidas ainda ocupam disco: não entram no histórico
+    if !cli.dry_run && !cli.stage && move_to.is_none() {
+        if let Some(path) = history::default_path() {
+            if let Err(e) = history::append(&path, &history::HistoryEntry::from_result(&root, &result)) {
+                warn!("Não foi possível gravar o histórico em '{}': {}", path.display(), e);
+            }
+        }
+        if let Some(path) = history::throughput_path().filter(|_| !result.timings.is_empty()) {
+            if let Err(e) = history::record_throughput(&path, &result.timings) {
+                warn!("Não foi possível gravar as velocidades de remoção em '{}': {}", path.display(), e);
+            }
+        }
+    }
     
     Ok(())
 }
 
+/// Pastas de cache que `--aggressive` limpa por nome (como `--prune-dir`).
+const AGGRESSIVE_PRUNE_DIRS: &[&str] = &["__pycache__", ".pytest_cache", ".mypy_cache", ".ruff_cache"];
+
+/// `--days` de `--aggressive` quando nem a flag nem o config definem outro.
+const AGGRESSIVE_DAYS: u64 = 14;
+
+/// `--aggressive` só liga outras flags: nada é apagado sem a confirmação de
+/// sempre. O que já foi passado explicitamente é mantido (e `--days`/config
+/// têm precedência sobre `AGGRESSIVE_DAYS`).
+fn apply_aggressive(cli: &mut Cli) {
+    cli.include_dist = true;
+    cli.prune_empty_parents = true;
+    for name in AGGRESSIVE_PRUNE_DIRS {
+        if !cli.prune_dirs.iter().any(|n| n == name) {
+            cli.prune_dirs.push(name.to_string());
+        }
+    }
+}
+
+/// `--compare-baseline` (antes) e `--save-baseline` (depois), sobre os projetos
+/// inativos da varredura completa, antes de filtros de exibição/seleção.
+fn handle_baselines(
+    compare: Option<&Path>,
+    save: Option<&Path>,
+    quiet: bool,
+    projects: &[types::StaleProject],
+) -> Result<()> {
+    if compare.is_none() && save.is_none() {
+        return Ok(());
+    }
+    let current = baseline::Baseline::from_projects(projects);
+
+    if let Some(path) = compare {
+        let previous = baseline::Baseline::load(path)?;
+        if !quiet {
+            display::print_baseline_diff(&baseline::compare(&previous, &current));
+        }
+    }
+    if let Some(path) = save {
+        current.save(path)?;
+        if !quiet {
+            println!("  {} Baseline salvo em {}", display::icon("💾"), path.display().to_string().bold());
+            println!();
+        }
+    }
+    Ok(())
+}
+
+/// `--size-only`: mede cada caminho em paralelo, sem detecção de projetos.
+fn size_only(paths: &[PathBuf], jobs: &types::WalkJobs) -> Vec<(PathBuf, u64)> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .iter()
+            .filter(|p| {
+                let exists = p.exists();
+                if !exists {
+                    warn!("Caminho não encontrado, ignorado: {}", p.display());
+                }
+                exists
+            })
+            .map(|p| scope.spawn(move || (p.clone(), types::dir_size(p, None, jobs))))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+/// Parte do padrão antes do primeiro componente com curinga: `~/code/*/` → `~/code`.
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+        .collect()
+}
+
+/// Pastas que casam com `--root-glob`, canônicas e sem repetição. Arquivos e
+/// caminhos ilegíveis são ignorados com aviso; nenhuma pasta é erro.
+/// Symlinks só entram com `--follow-symlinks`. Uma pasta dentro de outra que
+/// também casa é descartada: a varredura de fora já a cobre, e varrê-la à
+/// parte perderia a proteção que um projeto ativo de fora dá aos de dentro.
+fn expand_root_glob(pattern: &str, follow_symlinks: bool) -> Result<Vec<PathBuf>> {
+    let matches = glob::glob(pattern).with_context(|| format!("Padrão inválido em --root-glob: '{}'", pattern))?;
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for entry in matches {
+        match entry {
+            Ok(path) if path.is_symlink() && !follow_symlinks => {
+                info!("'{}' é um symlink; ignorado em --root-glob (use --follow-symlinks).", path.display())
+            }
+            Ok(path) if path.is_dir() => roots.push(path.canonicalize().unwrap_or(path)),
+            Ok(path) => warn!("'{}' não é um diretório; ignorado em --root-glob.", path.display()),
+            Err(e) => warn!("Não foi possível ler '{}': {}", e.path().display(), e.error()),
+        }
+    }
+    // Ordenadas, as pastas de dentro vêm logo depois da que as contém
+    roots.sort();
+    roots.dedup_by(|inner, outer| inner.starts_with(outer));
+
+    if roots.is_empty() {
+        bail!("Nenhuma pasta corresponde a --root-glob '{}'.", pattern);
+    }
+    Ok(roots)
+}
+
+fn absolutize(p: PathBuf) -> PathBuf {
+    if p.is_absolute() { p } else { std::env::current_dir().unwrap_or_default().join(p) }
+}
+
 fn pause_on_windows() {
     #[cfg(target_os = "windows")]
     {
@@ -1,20 +1,78 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use crate::types::DepKind;
 
 pub trait ProjectType: Send + Sync {
-    #[allow(dead_code)]
     fn name(&self) -> &'static str;
     fn dep_kind(&self) -> DepKind;
+
+    // Nomes de pasta que este tipo gerencia. O scanner filtra por nome antes
+    // de chamar `is_dependency_dir`, que faz a validação (manifesto etc.).
+    fn dependency_dir_names(&self) -> &'static [&'static str];
+
+    // Como as pastas aparecem para o usuário (`--list-types`), relativas à
+    // raiz do projeto. Por padrão os próprios nomes; tipos com pastas
+    // aninhadas ou nomes variáveis sobrescrevem.
+    fn dir_labels(&self) -> &'static [&'static str] {
+        self.dependency_dir_names()
+    }
+
+    // Pré-filtro por nome usado pelo scanner. Por padrão, nome exato em
+    // `dependency_dir_names`; tipos com nomes variáveis (ex: `*.egg-info`)
+    // sobrescrevem.
+    fn matches_dir_name(&self, name: &str) -> bool {
+        self.dependency_dir_names().contains(&name)
+    }
+
+    // true quando `matches_dir_name` aceita nomes fora de
+    // `dependency_dir_names`: o scanner não consegue descartar esses tipos
+    // pelo conjunto de nomes conhecidos e sempre os consulta.
+    fn has_variable_dir_names(&self) -> bool {
+        false
+    }
     
     // Retorna true se o diretório passado (ex: path/to/node_modules)
     // é uma pasta de dependência válida deste tipo de projeto.
     fn is_dependency_dir(&self, dir_path: &Path) -> bool;
+
+    // Raiz do projeto dono da pasta de dependência. Por padrão o diretório
+    // pai; tipos com pastas aninhadas (ex: Carthage/Build) sobrescrevem.
+    fn project_root(&self, dir_path: &Path) -> Option<PathBuf> {
+        dir_path.parent().map(Path::to_path_buf)
+    }
+
+    // Manifestos do projeto (relativos a `project_root`) cuja edição costuma
+    // levar a uma reinstalação das dependências. Usados por
+    // `--protect-if-manifest-newer-than-deps`; vazio = sem manifesto a comparar.
+    fn manifest_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    // Arquivos que `is_dependency_dir` procura para reconhecer a pasta, como
+    // aparecem em `--list-types`: relativos à raiz do projeto, ou a `<pasta>`
+    // quando o sinal fica dentro da própria pasta de dependência.
+    fn detection_markers(&self) -> &'static [&'static str];
+
+    // Lockfiles da instalação das dependências (relativos a `project_root`),
+    // apagados junto com a pasta por `--remove-lockfiles`. Vazio = nenhum.
+    fn lockfile_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    // Lockfiles que `--remove-lockfiles` apaga neste projeto. Por padrão todos
+    // de `lockfile_names`; tipos em que depende do projeto sobrescrevem.
+    fn lockfiles(&self, project_root: &Path) -> Vec<PathBuf> {
+        self.lockfile_names().iter().map(|name| project_root.join(name)).collect()
+    }
 }
 
 pub struct NodeProject;
 impl ProjectType for NodeProject {
     fn name(&self) -> &'static str { "Node.js" }
     fn dep_kind(&self) -> DepKind { DepKind::NodeModules }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["node_modules"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["package.json"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["package.json"] }
+    fn lockfile_names(&self) -> &'static [&'static str] { &["package-lock.json", "yarn.lock", "pnpm-lock.yaml", "bun.lockb"] }
     fn is_dependency_dir(&self, dir_path: &Path) -> bool {
         dir_path.file_name() == Some(std::ffi::OsStr::new("node_modules"))
             && dir_path.parent()
@@ -27,6 +85,20 @@ pub struct RustProject;
 impl ProjectType for RustProject {
     fn name(&self) -> &'static str { "Rust" }
     fn dep_kind(&self) -> DepKind { DepKind::Target }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["target"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["Cargo.toml"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["Cargo.toml"] }
+    fn lockfile_names(&self) -> &'static [&'static str] { &["Cargo.lock"] }
+    // O `Cargo.lock` só sai de crates com binário: num binário ele é regerado
+    // na próxima build, mas numa biblioteca é o que fixa as versões dos
+    // testes e do CI. Workspaces virtuais também mantêm o seu.
+    fn lockfiles(&self, project_root: &Path) -> Vec<PathBuf> {
+        if cargo_builds_binary(project_root) {
+            vec![project_root.join("Cargo.lock")]
+        } else {
+            Vec::new()
+        }
+    }
     fn is_dependency_dir(&self, dir_path: &Path) -> bool {
         dir_path.file_name() == Some(std::ffi::OsStr::new("target"))
             && dir_path.parent()
@@ -35,35 +107,122 @@ impl ProjectType for RustProject {
     }
 }
 
+/// O crate em `root` gera um binário: `src/main.rs`, `src/bin/` ou um
+/// `[[bin]]` no `Cargo.toml`.
+fn cargo_builds_binary(root: &Path) -> bool {
+    root.join("src/main.rs").is_file()
+        || root.join("src/bin").is_dir()
+        || std::fs::read_to_string(root.join("Cargo.toml"))
+            .is_ok_and(|manifest| manifest.lines().any(|l| l.trim() == "[[bin]]"))
+}
+
+pub struct MavenProject;
+impl ProjectType for MavenProject {
+    fn name(&self) -> &'static str { "Maven" }
+    fn dep_kind(&self) -> DepKind { DepKind::MavenTarget }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["target"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["pom.xml"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["pom.xml"] }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        dir_path.file_name() == Some(std::ffi::OsStr::new("target"))
+            && dir_path.parent()
+                .map(|p| p.join("pom.xml").exists())
+                .unwrap_or(false)
+    }
+}
+
+/// `next.config.*` ao lado da pasta: o sinal de um projeto Next.js.
+fn has_next_config(dir: &Path) -> bool {
+    ["js", "mjs", "ts"].iter().any(|ext| dir.join(format!("next.config.{}", ext)).exists())
+}
+
+// `.next` é o cache de build; `out` é a saída de `next export`/`output: 'export'`.
+// `out` é um nome genérico demais, então só conta com `next.config.*`.
 pub struct NextProject;
 impl ProjectType for NextProject {
     fn name(&self) -> &'static str { "Next.js" }
     fn dep_kind(&self) -> DepKind { DepKind::NextBuild }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &[".next", "out"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["package.json", "next.config.js", "next.config.mjs", "next.config.ts"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["package.json", "next.config.*"] }
     fn is_dependency_dir(&self, dir_path: &Path) -> bool {
-        if dir_path.file_name() != Some(std::ffi::OsStr::new(".next")) {
-            return false;
-        }
         let parent = match dir_path.parent() {
             Some(p) => p,
             None => return false,
         };
-        
-        if parent.join("package.json").exists() {
-            return true;
+
+        match dir_path.file_name().and_then(|n| n.to_str()) {
+            Some(".next") => parent.join("package.json").exists() || has_next_config(parent),
+            Some("out") => has_next_config(parent),
+            _ => false,
         }
-        for ext in &["js", "mjs", "ts"] {
-            if parent.join(format!("next.config.{}", ext)).exists() {
-                return true;
-            }
+    }
+}
+
+// `dist/` costuma ser saída de build, mas há quem versione fonte nela: só entra
+// com `--include-dist` e com um sinal forte de que é gerada (config de bundler,
+// ou um script do package.json que escreve em `dist`).
+pub struct DistProject;
+impl ProjectType for DistProject {
+    fn name(&self) -> &'static str { "JS build (dist)" }
+    fn dep_kind(&self) -> DepKind { DepKind::Dist }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["dist"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["package.json"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["vite.config.*", "rollup.config.*", "package.json (script build* escreve em dist)"] }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        if dir_path.file_name() != Some(std::ffi::OsStr::new("dist")) {
+            return false;
         }
-        false
+        let parent = match dir_path.parent() {
+            Some(p) => p,
+            None => return false,
+        };
+
+        let bundler_config = ["vite", "rollup"].iter().any(|tool| {
+            ["js", "mjs", "cjs", "ts", "mts"]
+                .iter()
+                .any(|ext| parent.join(format!("{}.config.{}", tool, ext)).exists())
+        });
+        bundler_config || build_script_writes_dist(&parent.join("package.json"))
     }
 }
 
+/// Algum script de build do `package.json` (`build`, `build:prod`...) escreve
+/// em `dist` (ex: `tsc --outDir dist`, `esbuild -o dist/`). Os demais scripts
+/// não contam: `"clean": "rm -rf dist"` só menciona a pasta.
+fn build_script_writes_dist(package_json: &Path) -> bool {
+    let Ok(content) = std::fs::read_to_string(package_json) else {
+        return false;
+    };
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return false;
+    };
+    manifest["scripts"].as_object().is_some_and(|scripts| {
+        scripts
+            .iter()
+            .filter(|(name, _)| name.starts_with("build"))
+            .filter_map(|(_, cmd)| cmd.as_str())
+            .any(mentions_dist_dir)
+    })
+}
+
+/// `dist` aparece como caminho no comando (`dist`, `./dist`, `dist/...`,
+/// `--outDir=dist`), não só como parte de uma palavra (`distclean`).
+fn mentions_dist_dir(cmd: &str) -> bool {
+    cmd.split(|c: char| c.is_whitespace() || c == '=')
+        .map(|token| token.trim_matches(|c| c == '"' || c == '\''))
+        .map(|token| token.strip_prefix("./").unwrap_or(token))
+        .any(|token| token == "dist" || token.starts_with("dist/"))
+}
+
 pub struct PythonProject;
 impl ProjectType for PythonProject {
     fn name(&self) -> &'static str { "Python (venv)" }
     fn dep_kind(&self) -> DepKind { DepKind::Venv }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["venv", ".venv"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["requirements.txt", "pyproject.toml", "Pipfile"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["<pasta>/pyvenv.cfg", "<pasta>/bin/python", "<pasta>/Scripts/python.exe"] }
+    fn lockfile_names(&self) -> &'static [&'static str] { &["poetry.lock", "Pipfile.lock", "uv.lock"] }
     fn is_dependency_dir(&self, dir_path: &Path) -> bool {
         let name = match dir_path.file_name().and_then(|n| n.to_str()) {
             Some(n) => n,
@@ -85,6 +244,9 @@ pub struct GoProject;
 impl ProjectType for GoProject {
     fn name(&self) -> &'static str { "Go" }
     fn dep_kind(&self) -> DepKind { DepKind::Vendor }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["vendor"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["go.mod"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["go.mod"] }
     fn is_dependency_dir(&self, dir_path: &Path) -> bool {
         dir_path.file_name() == Some(std::ffi::OsStr::new("vendor"))
             && dir_path.parent()
@@ -93,10 +255,69 @@ impl ProjectType for GoProject {
     }
 }
 
+/// Prefixo das pastas de build que IDEs como o CLion criam (`cmake-build-debug`).
+const CMAKE_BUILD_PREFIX: &str = "cmake-build-";
+
+/// Fonte registrado no `CMakeCache.txt` (`CMAKE_HOME_DIRECTORY`).
+fn cmake_home_directory(cache: &str) -> Option<PathBuf> {
+    cache.lines().find_map(|l| l.strip_prefix("CMAKE_HOME_DIRECTORY:INTERNAL=")).map(PathBuf::from)
+}
+
+// Pastas de build do CMake (`build/`, `cmake-build-*/`). O `CMakeLists.txt`
+// pode estar longe (build fora da árvore), então o sinal é o `CMakeCache.txt`
+// que o CMake grava *dentro* da pasta. Um build dentro do próprio fonte
+// (`cmake .`) também grava o cache, mas ali a pasta é o projeto: quando ela tem
+// `CMakeLists.txt` ou o cache aponta para ela mesma, não é artefato. O `build`
+// do Gradle não tem esse arquivo.
+pub struct CMakeProject;
+impl ProjectType for CMakeProject {
+    fn name(&self) -> &'static str { "CMake" }
+    fn dep_kind(&self) -> DepKind { DepKind::CMakeBuild }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["build"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["CMakeLists.txt"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["<pasta>/CMakeCache.txt"] }
+    fn dir_labels(&self) -> &'static [&'static str] { &["build", "cmake-build-*"] }
+    fn matches_dir_name(&self, name: &str) -> bool {
+        self.dependency_dir_names().contains(&name)
+            || (name.len() > CMAKE_BUILD_PREFIX.len() && name.starts_with(CMAKE_BUILD_PREFIX))
+    }
+    fn has_variable_dir_names(&self) -> bool { true }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        if !dir_path.file_name().and_then(|n| n.to_str()).is_some_and(|n| self.matches_dir_name(n)) {
+            return false;
+        }
+        let Ok(cache) = std::fs::read_to_string(dir_path.join("CMakeCache.txt")) else {
+            return false;
+        };
+        if dir_path.join("CMakeLists.txt").exists() {
+            return false;
+        }
+        match cmake_home_directory(&cache) {
+            Some(source) => {
+                let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+                canonical(&source) != canonical(dir_path)
+            }
+            None => true,
+        }
+    }
+    // Build mais fundo que a pasta pai (ex: `src/out/build`): o projeto é o
+    // fonte registrado no cache, quando ele contém a pasta de build
+    fn project_root(&self, dir_path: &Path) -> Option<PathBuf> {
+        std::fs::read_to_string(dir_path.join("CMakeCache.txt"))
+            .ok()
+            .and_then(|cache| cmake_home_directory(&cache))
+            .filter(|source| source.is_dir() && dir_path.starts_with(source) && dir_path != source)
+            .or_else(|| dir_path.parent().map(Path::to_path_buf))
+    }
+}
+
 pub struct GradleProject;
 impl ProjectType for GradleProject {
     fn name(&self) -> &'static str { "Gradle" }
     fn dep_kind(&self) -> DepKind { DepKind::Build }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["build"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["build.gradle", "build.gradle.kts"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["build.gradle", "build.gradle.kts"] }
     fn is_dependency_dir(&self, dir_path: &Path) -> bool {
         dir_path.file_name() == Some(std::ffi::OsStr::new("build"))
             && dir_path.parent()
@@ -105,15 +326,186 @@ impl ProjectType for GradleProject {
     }
 }
 
+/// Sufixo das pastas de metadados do setuptools (`meu_pacote.egg-info`).
+const EGG_INFO_SUFFIX: &str = ".egg-info";
+
+fn is_egg_info(name: &str) -> bool {
+    name.len() > EGG_INFO_SUFFIX.len() && name.ends_with(EGG_INFO_SUFFIX)
+}
+
+// Artefatos de empacotamento Python: `build/`, `dist/` e `*.egg-info/`.
+// `*.egg-info` basta estar ao lado de `setup.py` ou `pyproject.toml`. Já
+// `build` e `dist` são nomes comuns demais (há quem versione fonte em `dist`,
+// e `pyproject.toml` aparece só para configurar ferramentas): exigem sinal do
+// setuptools, um `setup.py` ou um `*.egg-info` ao lado do `pyproject.toml`.
+// Também são nomes de outros tipos (Gradle, `--include-dist`), que exigem o
+// seu próprio manifesto.
+pub struct PyPackagingProject;
+impl ProjectType for PyPackagingProject {
+    fn name(&self) -> &'static str { "Python (empacotamento)" }
+    fn dep_kind(&self) -> DepKind { DepKind::PyBuild }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["build", "dist"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["setup.py", "pyproject.toml"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["setup.py", "pyproject.toml"] }
+    fn dir_labels(&self) -> &'static [&'static str] { &["build", "dist", "*.egg-info"] }
+    fn matches_dir_name(&self, name: &str) -> bool {
+        self.dependency_dir_names().contains(&name) || is_egg_info(name)
+    }
+    fn has_variable_dir_names(&self) -> bool { true }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        let (Some(name), Some(parent)) = (dir_path.file_name().and_then(|n| n.to_str()), dir_path.parent()) else {
+            return false;
+        };
+        if !self.matches_dir_name(name) {
+            return false;
+        }
+        if is_egg_info(name) {
+            parent.join("setup.py").exists() || parent.join("pyproject.toml").exists()
+        } else {
+            parent.join("setup.py").exists() || (parent.join("pyproject.toml").exists() && has_egg_info(parent))
+        }
+    }
+}
+
+/// `dir` tem uma pasta `*.egg-info` (o setuptools já empacotou o projeto).
+fn has_egg_info(dir: &Path) -> bool {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten().any(|e| {
+                e.file_type().is_ok_and(|t| t.is_dir()) && e.file_name().to_str().is_some_and(is_egg_info)
+            })
+        })
+        .unwrap_or(false)
+}
+
+// CocoaPods: remove `Pods/` inteiro (ao lado do `Podfile`).
+// Carthage: remove apenas `Carthage/Build/` (ao lado do `Cartfile`), preservando
+// `Carthage/Checkouts/`, que pode conter fontes versionadas.
+pub struct IosDepsProject;
+impl ProjectType for IosDepsProject {
+    fn name(&self) -> &'static str { "iOS (CocoaPods/Carthage)" }
+    fn dep_kind(&self) -> DepKind { DepKind::IosDeps }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["Pods", "Build"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["Podfile", "Cartfile"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["Podfile", "Cartfile"] }
+    fn lockfile_names(&self) -> &'static [&'static str] { &["Podfile.lock", "Cartfile.resolved"] }
+    fn dir_labels(&self) -> &'static [&'static str] { &["Pods", "Carthage/Build"] }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        let name = dir_path.file_name().and_then(|n| n.to_str());
+        match name {
+            Some("Pods") => dir_path.parent()
+                .map(|p| p.join("Podfile").exists())
+                .unwrap_or(false),
+            Some("Build") => dir_path.parent()
+                .filter(|p| p.file_name() == Some(std::ffi::OsStr::new("Carthage")))
+                .and_then(|p| p.parent())
+                .map(|p| p.join("Cartfile").exists())
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+    fn project_root(&self, dir_path: &Path) -> Option<PathBuf> {
+        let parent = dir_path.parent()?;
+        if parent.file_name() == Some(std::ffi::OsStr::new("Carthage")) {
+            parent.parent().map(Path::to_path_buf)
+        } else {
+            Some(parent.to_path_buf())
+        }
+    }
+}
+
+pub struct ZigProject;
+impl ProjectType for ZigProject {
+    fn name(&self) -> &'static str { "Zig" }
+    fn dep_kind(&self) -> DepKind { DepKind::ZigCache }
+    // `zig-cache` é o nome antigo; a partir do Zig 0.13 é `.zig-cache`
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["zig-cache", ".zig-cache", "zig-out"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["build.zig", "build.zig.zon"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["build.zig"] }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        dir_path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| self.dependency_dir_names().contains(&n))
+            && dir_path.parent()
+                .map(|p| p.join("build.zig").exists())
+                .unwrap_or(false)
+    }
+}
+
+/// `dir_path` é `<projeto>/<tool_dir>/cache` e o projeto tem `manifest`.
+fn is_tool_cache(dir_path: &Path, tool_dir: &str, manifest: &str) -> bool {
+    dir_path.file_name() == Some(std::ffi::OsStr::new("cache"))
+        && dir_path.parent()
+            .filter(|p| p.file_name() == Some(std::ffi::OsStr::new(tool_dir)))
+            .and_then(|p| p.parent())
+            .map(|p| p.join(manifest).exists())
+            .unwrap_or(false)
+}
+
+// Angular CLI e Nx: remove só a subpasta `cache/`, não `.angular/` ou `.nx/`
+// inteiras — o Nx guarda em `.nx/` também o estado do workspace
+// (`workspace-data`), e o conteúdo fora de `cache/` pode mudar entre versões.
+pub struct AngularProject;
+impl ProjectType for AngularProject {
+    fn name(&self) -> &'static str { "Angular" }
+    fn dep_kind(&self) -> DepKind { DepKind::AngularCache }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["cache"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["angular.json", "package.json"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["angular.json"] }
+    fn dir_labels(&self) -> &'static [&'static str] { &[".angular/cache"] }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        is_tool_cache(dir_path, ".angular", "angular.json")
+    }
+    fn project_root(&self, dir_path: &Path) -> Option<PathBuf> {
+        dir_path.parent()?.parent().map(Path::to_path_buf)
+    }
+}
+
+pub struct NxProject;
+impl ProjectType for NxProject {
+    fn name(&self) -> &'static str { "Nx" }
+    fn dep_kind(&self) -> DepKind { DepKind::NxCache }
+    fn dependency_dir_names(&self) -> &'static [&'static str] { &["cache"] }
+    fn manifest_names(&self) -> &'static [&'static str] { &["nx.json", "package.json"] }
+    fn detection_markers(&self) -> &'static [&'static str] { &["nx.json"] }
+    fn dir_labels(&self) -> &'static [&'static str] { &[".nx/cache"] }
+    fn is_dependency_dir(&self, dir_path: &Path) -> bool {
+        is_tool_cache(dir_path, ".nx", "nx.json")
+    }
+    fn project_root(&self, dir_path: &Path) -> Option<PathBuf> {
+        dir_path.parent()?.parent().map(Path::to_path_buf)
+    }
+}
+
+/// Todos os tipos, incluindo os opt-in.
 pub fn all_project_types() -> Vec<Box<dyn ProjectType>> {
-    vec![
+    project_types(true)
+}
+
+// A ordem de registro é a prioridade de detecção: quando mais de um tipo
+// reconhece a mesma pasta (ex: `target` com `Cargo.toml` e `pom.xml`), vence
+// o primeiro da lista. Tipos com manifesto mais específico vêm antes.
+// Os opt-in (`dist`) só entram com `include_dist`.
+pub fn project_types(include_dist: bool) -> Vec<Box<dyn ProjectType>> {
+    let mut types: Vec<Box<dyn ProjectType>> = vec![
         Box::new(NodeProject),
         Box::new(RustProject),
+        Box::new(MavenProject),
         Box::new(NextProject),
         Box::new(PythonProject),
         Box::new(GoProject),
+        Box::new(CMakeProject),
         Box::new(GradleProject),
-    ]
+        Box::new(PyPackagingProject),
+        Box::new(IosDepsProject),
+        Box::new(ZigProject),
+        Box::new(AngularProject),
+        Box::new(NxProject),
+    ];
+    if include_dist {
+        types.push(Box::new(DistProject));
+    }
+    types
 }
 
 #[cfg(test)]
@@ -125,6 +517,38 @@ mod tests {
 
     static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+    #[test]
+    fn test_cargo_lock_removed_only_for_binaries() {
+        let proj = RustProject;
+        let crate_dir = |files: &[(&str, &str)]| {
+            let dir = make_temp_dir();
+            for (file, content) in files {
+                fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+                fs::write(dir.join(file), content).unwrap();
+            }
+            dir
+        };
+
+        let bin = crate_dir(&[("Cargo.toml", "[package]"), ("src/main.rs", "")]);
+        assert_eq!(proj.lockfiles(&bin), vec![bin.join("Cargo.lock")]);
+        let tools = crate_dir(&[("Cargo.toml", "[package]"), ("src/lib.rs", ""), ("src/bin/tool.rs", "")]);
+        assert_eq!(proj.lockfiles(&tools), vec![tools.join("Cargo.lock")]);
+        let declared = crate_dir(&[("Cargo.toml", "[package]\n\n[[bin]]\nname = \"app\"\npath = \"app.rs\"\n")]);
+        assert_eq!(proj.lockfiles(&declared), vec![declared.join("Cargo.lock")]);
+
+        let lib = crate_dir(&[("Cargo.toml", "[package]\n[lib]\n"), ("src/lib.rs", "")]);
+        assert!(proj.lockfiles(&lib).is_empty());
+        let workspace = crate_dir(&[("Cargo.toml", "[workspace]\nmembers = [\"app\"]\n"), ("app/src/main.rs", "")]);
+        assert!(proj.lockfiles(&workspace).is_empty());
+
+        // Os outros tipos seguem `lockfile_names`
+        assert_eq!(NodeProject.lockfiles(&lib).len(), NodeProject.lockfile_names().len());
+
+        for dir in [bin, tools, declared, lib, workspace] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
     fn make_temp_dir() -> PathBuf {
         let id = COUNTER.fetch_add(1, Ordering::SeqCst);
         let dir = std::env::temp_dir().join(format!(
@@ -223,4 +647,337 @@ mod tests {
         
         fs::remove_dir_all(dir).unwrap();
     }
+
+    #[test]
+    fn test_next_static_export_out() {
+        let dir = make_temp_dir();
+        fs::create_dir(dir.join("out")).unwrap();
+        fs::write(dir.join("package.json"), "{}").unwrap();
+
+        // `out` só com next.config.*: package.json sozinho não basta
+        let proj = NextProject;
+        assert!(!proj.is_dependency_dir(&dir.join("out")));
+        fs::write(dir.join("next.config.mjs"), "").unwrap();
+        assert!(proj.is_dependency_dir(&dir.join("out")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_dist_requires_build_signal() {
+        let dir = make_temp_dir();
+        fs::create_dir(dir.join("dist")).unwrap();
+        fs::write(dir.join("package.json"), r#"{"scripts": {"test": "jest"}}"#).unwrap();
+
+        let proj = DistProject;
+        assert!(!proj.is_dependency_dir(&dir.join("dist")), "package.json without a dist build is not enough");
+
+        fs::write(dir.join("package.json"), r#"{"scripts": {"build": "tsc --outDir dist"}}"#).unwrap();
+        assert!(proj.is_dependency_dir(&dir.join("dist")));
+        fs::write(dir.join("package.json"), r#"{"scripts": {"build:lib": "esbuild src/index.ts -o ./dist/"}}"#).unwrap();
+        assert!(proj.is_dependency_dir(&dir.join("dist")));
+
+        // Só mencionar a pasta, ou uma palavra com "dist", não é build
+        for scripts in [r#"{"clean": "rm -rf dist"}"#, r#"{"build": "make distclean"}"#, r#"{"build": "tsc --outDir distribution"}"#] {
+            fs::write(dir.join("package.json"), format!(r#"{{"scripts": {}}}"#, scripts)).unwrap();
+            assert!(!proj.is_dependency_dir(&dir.join("dist")), "{}", scripts);
+        }
+
+        fs::write(dir.join("package.json"), "{}").unwrap();
+        fs::write(dir.join("vite.config.ts"), "").unwrap();
+        assert!(proj.is_dependency_dir(&dir.join("dist")));
+
+        // Opt-in: fora da lista padrão da varredura
+        assert!(!project_types(false).iter().any(|t| t.dep_kind() == DepKind::Dist));
+        assert!(project_types(true).iter().any(|t| t.dep_kind() == DepKind::Dist));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ios_pods() {
+        let dir = make_temp_dir();
+        fs::create_dir(dir.join("Pods")).unwrap();
+        fs::write(dir.join("Podfile"), "").unwrap();
+
+        let proj = IosDepsProject;
+        assert!(proj.is_dependency_dir(&dir.join("Pods")));
+        assert_eq!(proj.project_root(&dir.join("Pods")), Some(dir.clone()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ios_carthage_build() {
+        let dir = make_temp_dir();
+        fs::create_dir_all(dir.join("Carthage/Build")).unwrap();
+        fs::create_dir_all(dir.join("Carthage/Checkouts")).unwrap();
+        fs::write(dir.join("Cartfile"), "").unwrap();
+
+        let proj = IosDepsProject;
+        assert!(proj.is_dependency_dir(&dir.join("Carthage/Build")));
+        assert!(!proj.is_dependency_dir(&dir.join("Carthage")));
+        assert!(!proj.is_dependency_dir(&dir.join("Carthage/Checkouts")));
+        assert_eq!(proj.project_root(&dir.join("Carthage/Build")), Some(dir.clone()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_maven_project() {
+        let dir = make_temp_dir();
+        fs::create_dir(dir.join("target")).unwrap();
+
+        let proj = MavenProject;
+        assert!(!proj.is_dependency_dir(&dir.join("target")));
+        fs::write(dir.join("pom.xml"), "<project/>").unwrap();
+        assert!(proj.is_dependency_dir(&dir.join("target")));
+        assert!(!RustProject.is_dependency_dir(&dir.join("target")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_zig_project() {
+        let dir = make_temp_dir();
+        fs::write(dir.join("build.zig"), "").unwrap();
+
+        let proj = ZigProject;
+        for name in ["zig-cache", ".zig-cache", "zig-out"] {
+            fs::create_dir(dir.join(name)).unwrap();
+            assert!(proj.is_dependency_dir(&dir.join(name)), "{} should be detected", name);
+        }
+        fs::create_dir(dir.join("src")).unwrap();
+        assert!(!proj.is_dependency_dir(&dir.join("src")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_zig_project_without_build_zig() {
+        let dir = make_temp_dir();
+        fs::create_dir(dir.join("zig-cache")).unwrap();
+        fs::create_dir(dir.join("zig-out")).unwrap();
+
+        let proj = ZigProject;
+        assert!(!proj.is_dependency_dir(&dir.join("zig-cache")));
+        assert!(!proj.is_dependency_dir(&dir.join("zig-out")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cmake_build_keyed_on_cmake_cache() {
+        let dir = make_temp_dir();
+        let names = ["build", "cmake-build-debug", "cmake-build-release", "out", "_build"];
+        for name in names {
+            fs::create_dir(dir.join(name)).unwrap();
+        }
+
+        let proj = CMakeProject;
+        // Sem CMakeCache.txt, nem um CMakeLists.txt ao lado basta
+        fs::write(dir.join("CMakeLists.txt"), "").unwrap();
+        assert!(!proj.is_dependency_dir(&dir.join("build")));
+        assert!(!proj.is_dependency_dir(&dir.join("cmake-build-debug")));
+
+        for name in names {
+            fs::write(dir.join(name).join("CMakeCache.txt"), "").unwrap();
+        }
+        assert!(proj.is_dependency_dir(&dir.join("build")));
+        assert!(proj.is_dependency_dir(&dir.join("cmake-build-debug")));
+        assert!(proj.is_dependency_dir(&dir.join("cmake-build-release")));
+        // Só os nomes conhecidos
+        assert!(!proj.is_dependency_dir(&dir.join("out")));
+        assert!(!proj.is_dependency_dir(&dir.join("_build")));
+        assert!(!proj.matches_dir_name("cmake-build-"));
+        assert_eq!(proj.project_root(&dir.join("cmake-build-debug")), Some(dir.clone()));
+
+        // Build mais fundo: o projeto é o fonte registrado no cache
+        let nested = dir.join("out/build");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("CMakeCache.txt"), "").unwrap();
+        assert_eq!(proj.project_root(&nested), Some(dir.join("out")));
+        fs::write(
+            nested.join("CMakeCache.txt"),
+            format!("CMAKE_BUILD_TYPE:STRING=Debug\nCMAKE_HOME_DIRECTORY:INTERNAL={}\n", dir.display()),
+        )
+        .unwrap();
+        assert!(proj.is_dependency_dir(&nested));
+        assert_eq!(proj.project_root(&nested), Some(dir.clone()));
+
+        // O `build` do Gradle não tem CMakeCache.txt: continua sendo do Gradle
+        let gradle = dir.join("android");
+        fs::create_dir_all(gradle.join("build")).unwrap();
+        fs::write(gradle.join("build.gradle"), "").unwrap();
+        assert!(!proj.is_dependency_dir(&gradle.join("build")));
+        assert!(GradleProject.is_dependency_dir(&gradle.join("build")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cmake_in_source_build_is_the_project() {
+        let dir = make_temp_dir();
+
+        // `cmake .` num projeto chamado `build`: o fonte não é artefato
+        let in_source = dir.join("build");
+        fs::create_dir_all(in_source.join("src")).unwrap();
+        fs::write(in_source.join("CMakeLists.txt"), "").unwrap();
+        fs::write(in_source.join("CMakeCache.txt"), "").unwrap();
+        fs::write(in_source.join("src/main.c"), "").unwrap();
+        assert!(!CMakeProject.is_dependency_dir(&in_source));
+
+        // Mesmo sem CMakeLists.txt, o cache apontando para a própria pasta basta
+        let self_home = dir.join("cmake-build-debug");
+        fs::create_dir(&self_home).unwrap();
+        fs::write(
+            self_home.join("CMakeCache.txt"),
+            format!("CMAKE_HOME_DIRECTORY:INTERNAL={}\n", self_home.display()),
+        )
+        .unwrap();
+        assert!(!CMakeProject.is_dependency_dir(&self_home));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ios_pods_without_podfile() {
+        let dir = make_temp_dir();
+        fs::create_dir(dir.join("Pods")).unwrap();
+
+        let proj = IosDepsProject;
+        assert!(!proj.is_dependency_dir(&dir.join("Pods")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_py_packaging_artifacts() {
+        let dir = make_temp_dir();
+        for name in ["build", "dist", "my_pkg.egg-info", "src"] {
+            fs::create_dir(dir.join(name)).unwrap();
+        }
+
+        let proj = PyPackagingProject;
+        // Sem setup.py/pyproject.toml nada é artefato
+        assert!(!proj.is_dependency_dir(&dir.join("build")));
+        assert!(!proj.is_dependency_dir(&dir.join("my_pkg.egg-info")));
+
+        // Com setup.py, ou pyproject.toml e um *.egg-info já gerado
+        for manifest in ["setup.py", "pyproject.toml"] {
+            fs::write(dir.join(manifest), "").unwrap();
+            for name in ["build", "dist", "my_pkg.egg-info"] {
+                assert!(proj.is_dependency_dir(&dir.join(name)), "{} should be detected with {}", name, manifest);
+            }
+            assert!(!proj.is_dependency_dir(&dir.join("src")));
+            fs::remove_file(dir.join(manifest)).unwrap();
+        }
+
+        assert!(proj.matches_dir_name("my_pkg.egg-info"));
+        assert!(!proj.matches_dir_name(".egg-info"));
+        assert!(!proj.matches_dir_name("egg-info-notes"));
+
+        // O `build` do Gradle continua sendo do Gradle
+        fs::write(dir.join("build.gradle"), "").unwrap();
+        assert!(!proj.is_dependency_dir(&dir.join("build")));
+        assert!(GradleProject.is_dependency_dir(&dir.join("build")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_pyproject_alone_does_not_claim_build_or_dist() {
+        let dir = make_temp_dir();
+        // Pacote JS com pyproject.toml só para ferramentas e um `dist/` versionado
+        fs::write(dir.join("pyproject.toml"), "[tool.black]").unwrap();
+        fs::write(dir.join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.join("dist")).unwrap();
+        fs::create_dir_all(dir.join("build")).unwrap();
+        fs::write(dir.join("dist/index.js"), "// fonte").unwrap();
+
+        let proj = PyPackagingProject;
+        assert!(!proj.is_dependency_dir(&dir.join("dist")));
+        assert!(!proj.is_dependency_dir(&dir.join("build")));
+        assert!(project_types(false).iter().all(|t| !t.is_dependency_dir(&dir.join("dist"))));
+
+        // Um *.egg-info ao lado é sinal do setuptools
+        fs::create_dir(dir.join("my_pkg.egg-info")).unwrap();
+        assert!(proj.is_dependency_dir(&dir.join("dist")));
+        assert!(proj.is_dependency_dir(&dir.join("my_pkg.egg-info")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_angular_and_nx_caches() {
+        let dir = make_temp_dir();
+        fs::create_dir_all(dir.join(".angular/cache")).unwrap();
+        fs::create_dir_all(dir.join(".nx/cache")).unwrap();
+        fs::create_dir_all(dir.join(".nx/workspace-data")).unwrap();
+
+        // Sem manifesto, nenhum dos dois
+        assert!(!AngularProject.is_dependency_dir(&dir.join(".angular/cache")));
+        assert!(!NxProject.is_dependency_dir(&dir.join(".nx/cache")));
+
+        fs::write(dir.join("angular.json"), "{}").unwrap();
+        fs::write(dir.join("nx.json"), "{}").unwrap();
+        assert!(AngularProject.is_dependency_dir(&dir.join(".angular/cache")));
+        assert!(NxProject.is_dependency_dir(&dir.join(".nx/cache")));
+        assert_eq!(AngularProject.project_root(&dir.join(".angular/cache")), Some(dir.clone()));
+        assert_eq!(NxProject.project_root(&dir.join(".nx/cache")), Some(dir.clone()));
+
+        // Só a subpasta `cache`, e cada uma sob a pasta da sua ferramenta
+        assert!(!AngularProject.is_dependency_dir(&dir.join(".angular")));
+        assert!(!NxProject.is_dependency_dir(&dir.join(".nx")));
+        assert!(!NxProject.is_dependency_dir(&dir.join(".nx/workspace-data")));
+        assert!(!AngularProject.is_dependency_dir(&dir.join(".nx/cache")));
+        assert!(!NxProject.is_dependency_dir(&dir.join(".angular/cache")));
+        fs::create_dir(dir.join("cache")).unwrap();
+        assert!(!AngularProject.is_dependency_dir(&dir.join("cache")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_dependency_dir_names_agree_with_validation() {
+        let dir = make_temp_dir();
+        // Um projeto com todos os manifestos conhecidos
+        for manifest in [
+            "package.json", "Cargo.toml", "pom.xml", "go.mod", "build.gradle", "Podfile", "Cartfile", "build.zig",
+            "next.config.js", "vite.config.ts", "angular.json", "nx.json",
+            "setup.py",
+        ] {
+            fs::write(dir.join(manifest), "").unwrap();
+        }
+        fs::create_dir(dir.join("Carthage")).unwrap();
+
+        // Pastas aninhadas ficam sob a pasta da ferramenta
+        let candidate = |kind: DepKind, name: &str| {
+            let parent = match (kind, name) {
+                (_, "Build") => dir.join("Carthage"),
+                (DepKind::AngularCache, _) => dir.join(".angular"),
+                (DepKind::NxCache, _) => dir.join(".nx"),
+                _ => dir.clone(),
+            };
+            let path = parent.join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("pyvenv.cfg"), "").unwrap();
+            fs::write(path.join("CMakeCache.txt"), "").unwrap();
+            path
+        };
+
+        for proj in all_project_types() {
+            assert!(!proj.dependency_dir_names().is_empty(), "{} has no dir names", proj.name());
+
+            // Todo nome declarado valida num layout completo
+            for name in proj.dependency_dir_names() {
+                assert!(proj.is_dependency_dir(&candidate(proj.dep_kind(), name)), "{} should accept {}", proj.name(), name);
+            }
+
+            // Nenhum nome fora da lista é aceito, mesmo com manifestos presentes
+            assert!(!proj.is_dependency_dir(&candidate(proj.dep_kind(), "not_a_dep")), "{} accepted an undeclared name", proj.name());
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,143 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::display::{days_ago, format_size, kind_stats};
+use crate::types::StaleProject;
+
+/// Quantos projetos entram na tabela de "mais pesados".
+const TOP_PROJECTS: usize = 20;
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:960px;color:#222;background:#fafafa}\
+h1{font-size:1.6rem}h2{font-size:1.2rem;margin-top:2rem}\
+.cards{display:flex;gap:1rem}.card{background:#fff;border:1px solid #ddd;border-radius:8px;padding:1rem;flex:1}\
+.card b{display:block;font-size:1.4rem}\
+table{border-collapse:collapse;width:100%;background:#fff}\
+th,td{text-align:left;padding:.4rem .6rem;border-bottom:1px solid #eee}td.num{text-align:right}\
+.projected{color:#1565c0;font-weight:bold}";
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Gera o HTML autocontido (CSS e SVG inline, sem assets externos).
+pub fn render_html(projects: &[StaleProject], dry_run: bool) -> String {
+    let total_size: u64 = projects.iter().map(|p| p.total_size()).sum();
+    let total_dirs: usize = projects.iter().map(|p| p.dep_dirs().len()).sum();
+    let label = if dry_run { " <span class=\"projected\">(projetado)</span>" } else { "" };
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"pt-BR\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Faxina CLI — Relatório</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <h1>🧹 Faxina CLI — Relatório</h1>\n",
+        STYLE
+    );
+
+    let _ = writeln!(
+        html,
+        "<div class=\"cards\">\
+         <div class=\"card\">Projetos inativos<b>{}</b></div>\
+         <div class=\"card\">Pastas<b>{}</b></div>\
+         <div class=\"card\">Espaço recuperável{}<b>{}</b></div>\
+         </div>",
+        projects.len(),
+        total_dirs,
+        label,
+        format_size(total_size)
+    );
+
+    // Gráfico de barras por tipo (SVG inline)
+    let stats = kind_stats(projects);
+    let max = stats.iter().map(|s| s.2).max().unwrap_or(0).max(1);
+    let _ = write!(html, "<h2>Por tipo{}</h2>\n<svg width=\"100%\" height=\"{}\" viewBox=\"0 0 900 {}\">\n", label, stats.len() * 30, stats.len() * 30);
+    for (i, (kind, count, size)) in stats.iter().enumerate() {
+        let y = i * 30;
+        let width = (*size as f64 / max as f64 * 560.0).max(1.0);
+        let _ = writeln!(
+            html,
+            "<text x=\"0\" y=\"{}\" font-size=\"14\">{} {}</text>\
+             <rect x=\"170\" y=\"{}\" width=\"{:.0}\" height=\"20\" fill=\"#26a69a\"/>\
+             <text x=\"{:.0}\" y=\"{}\" font-size=\"13\">{} ({} pastas)</text>",
+            y + 16, kind.icon(), escape(&kind.to_string()),
+            y + 2, width,
+            180.0 + width, y + 16, format_size(*size), count
+        );
+    }
+    html.push_str("</svg>\n");
+
+    // Projetos mais pesados (a lista já vem ordenada por tamanho)
+    let _ = write!(
+        html,
+        "<h2>Projetos mais pesados</h2>\n<table>\n\
+         <tr><th>Projeto</th><th>Caminho</th><th>Tipos</th><th>Tamanho{}</th><th>Última modificação</th></tr>\n",
+        label
+    );
+    for project in projects.iter().take(TOP_PROJECTS) {
+        let kinds: Vec<String> = project.dep_dirs().iter().map(|d| escape(&d.kind.to_string())).collect();
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+            escape(&project.name),
+            escape(&project.path.display().to_string()),
+            kinds.join(", "),
+            format_size(project.total_size()),
+            escape(&days_ago(project.last_modified))
+        );
+    }
+    if projects.len() > TOP_PROJECTS {
+        let _ = writeln!(html, "<tr><td colspan=\"5\">… e mais {} projetos</td></tr>", projects.len() - TOP_PROJECTS);
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+pub fn write_html(path: &Path, projects: &[StaleProject], dry_run: bool) -> Result<()> {
+    fs::write(path, render_html(projects, dry_run))
+        .with_context(|| format!("Não foi possível gravar o relatório em '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DepDir, DepKind};
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_write_html_contains_projects() {
+        let temp = std::env::temp_dir().join(format!("test_report_html_{}", std::process::id()));
+        fs::create_dir_all(&temp).unwrap();
+
+        let projects: Vec<StaleProject> = ["alpha", "beta<script>"].iter().map(|name| StaleProject::new(
+            name.to_string(),
+            PathBuf::from("/code").join(name),
+            vec![DepDir {
+                path: PathBuf::from("/code").join(name).join("target"),
+                size: 4096,
+                files: 0,
+                kind: DepKind::Target,
+            }],
+            SystemTime::now(),
+        )).collect();
+
+        let out = temp.join("report.html");
+        write_html(&out, &projects, true).unwrap();
+
+        let html = fs::read_to_string(&out).unwrap();
+        assert!(html.contains("alpha"));
+        assert!(html.contains("beta&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("projetado"));
+        assert!(!html.contains("http"), "Report must not reference external assets");
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
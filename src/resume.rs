@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::history;
+use crate::types::StaleProject;
+
+/// Manifesto de progresso da limpeza de `root`, no diretório de cache.
+/// Uma pasta removida por linha (ver `history::encode_path`); apagado quando
+/// a limpeza termina sem erros.
+pub fn manifest_path(root: &Path) -> Option<PathBuf> {
+    history::root_cache_path("resume", root)
+}
+
+/// Começa um manifesto vazio (descarta o de uma execução anterior).
+pub fn start(path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, "")
+}
+
+pub fn record(path: &Path, dep_path: &Path) -> std::io::Result<()> {
+    let line = history::encode_path(dep_path).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "caminho não pode ser registrado sem perdas")
+    })?;
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(f, "{}", line)
+}
+
+/// Pastas já concluídas. Manifesto ausente = nada concluído.
+pub fn load(path: &Path) -> HashSet<PathBuf> {
+    fs::read_to_string(path)
+        .map(|c| c.lines().filter(|l| !l.trim().is_empty()).filter_map(history::decode_path).collect())
+        .unwrap_or_default()
+}
+
+/// Remove dos projetos as pastas já concluídas (e projetos que ficarem vazios).
+/// Retorna quantas pastas foram puladas.
+pub fn skip_completed(projects: &mut Vec<StaleProject>, done: &HashSet<PathBuf>) -> usize {
+    let mut skipped = 0;
+    for project in projects.iter_mut() {
+        let before = project.dep_dirs().len();
+        project.dep_dirs_mut().retain(|d| !done.contains(&d.path));
+        skipped += before - project.dep_dirs().len();
+    }
+    projects.retain(|p| !p.dep_dirs().is_empty());
+    skipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DepDir, DepKind};
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_resume_skips_completed_dirs() {
+        let temp = std::env::temp_dir().join(format!("test_resume_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let manifest = temp.join("resume/manifest.txt");
+
+        // Execução interrompida: só a primeira pasta foi registrada
+        start(&manifest).unwrap();
+        record(&manifest, Path::new("/code/a/node_modules")).unwrap();
+
+        let dep = |p: &str| DepDir { path: PathBuf::from(p), size: 10, files: 0, kind: DepKind::NodeModules };
+        let mut projects = vec![
+            StaleProject::new("a".to_string(), PathBuf::from("/code/a"), vec![dep("/code/a/node_modules")], SystemTime::now()),
+            StaleProject::new(
+                "b".to_string(),
+                PathBuf::from("/code/b"),
+                vec![dep("/code/b/node_modules"), dep("/code/b/.next")],
+                SystemTime::now(),
+            ),
+        ];
+
+        let done = load(&manifest);
+        assert_eq!(skip_completed(&mut projects, &done), 1);
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "b");
+        assert_eq!(projects[0].total_size(), 20);
+
+        // Um novo início descarta o progresso anterior
+        start(&manifest).unwrap();
+        assert!(load(&manifest).is_empty());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
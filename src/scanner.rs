@@ -1,13 +1,166 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use jwalk::WalkDir;
 use log::{debug, warn};
 
-use crate::types::{DepDir, StaleProject, dir_size};
-use crate::projects::all_project_types;
+use crate::types::{DepDir, DepKind, DirUsage, ProtectionReason, SeenInodes, StaleProject, WalkJobs, dir_usage, dir_usage_capped};
+use crate::config::{self, ProjectConfig};
+use crate::projects::{all_project_types, project_types, ProjectType};
+
+/// Protects dep dirs of `kind` for projects under `prefix` (`--keep <kind>@<path>`).
+///
+/// Rules only ever remove dirs from the cleanup set: if several rules match the
+/// same project, every matching kind is kept. Ignored paths are applied before
+/// discovery, so a keep rule never brings back something already excluded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeepRule {
+    pub kind: DepKind,
+    pub prefix: PathBuf,
+}
+
+impl std::str::FromStr for KeepRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind_name, prefix) = s.split_once('@')
+            .ok_or_else(|| format!("formato esperado <tipo>@<caminho>, recebido '{}'", s))?;
+        let kind: DepKind = kind_name.parse()?;
+        if prefix.is_empty() {
+            return Err(format!("caminho vazio na regra '{}'", s));
+        }
+        Ok(KeepRule { kind, prefix: PathBuf::from(prefix) })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    pub days: u64,
+    /// Absolute staleness cutoff that replaces `days` (`--since-last-run`):
+    /// projects untouched since this instant are stale.
+    pub stale_before: Option<SystemTime>,
+    pub ignored_paths: Vec<PathBuf>,
+    pub keep_rules: Vec<KeepRule>,
+    /// Skip projects whose dep dir was created less than this many days ago.
+    pub min_age_days: Option<u64>,
+    /// Directory names cleaned wherever found, without a manifest (`--prune-dir`).
+    /// Staleness is anchored on the directory's own mtime.
+    pub prune_dirs: Vec<String>,
+    /// Protect stale projects that have an active sibling under the same parent.
+    /// Only discovered projects count as siblings (arbitrary dirs are not walked).
+    pub protect_active_siblings: bool,
+    /// Window (days) for what counts as "active" in the parent/child/sibling
+    /// protection. Defaults to `days`, so both concepts move together unless set.
+    pub protect_active_within: Option<u64>,
+    /// Protect stale projects inside a git repo with uncommitted changes, or
+    /// whose `git status` fails.
+    pub protect_dirty: bool,
+    /// Protect projects inside a git submodule (`--skip-submodules`): their
+    /// dep dirs follow the submodule's lifecycle, not the superproject's.
+    pub skip_submodules: bool,
+    /// Protect stale projects whose manifest (`ProjectType::manifest_names`) is
+    /// newer than one of their dep dirs: the deps are out of date and likely
+    /// about to be reinstalled (`--protect-if-manifest-newer-than-deps`).
+    pub protect_manifest_newer: bool,
+    /// Also return the projects kept by a protection rule (`ScanOutcome::protected`).
+    pub collect_protected: bool,
+    /// Walk parallelism (`--jobs`), shared by discovery, mtime and sizing walks.
+    pub jobs: WalkJobs,
+    /// Abort discovery after this long (`--scan-timeout`) and keep only the
+    /// projects that were fully discovered, mtime included. The mtime walks
+    /// get a budget of their own, so the projects discovery did reach are
+    /// not all lost to a deadline that has already passed.
+    pub scan_timeout: Option<Duration>,
+    /// File-name globs that don't count towards a project's mtime (`--mtime-ignore`).
+    pub mtime_ignore: Vec<glob::Pattern>,
+    /// Also count the project directory's own mtime (`--include-dir-mtime`), so a
+    /// rename or entry removal at the root keeps it active. Directory mtime
+    /// semantics vary by platform/filesystem, hence opt-in.
+    pub include_dir_mtime: bool,
+    /// Don't descend into directories on a different filesystem than the root
+    /// (`--one-file-system`, like `find -xdev`).
+    pub one_file_system: bool,
+    /// Compare ignored paths case-insensitively (`--ignore-case`), for
+    /// case-insensitive filesystems (macOS/Windows defaults). Off by default so
+    /// `Foo` and `foo` stay distinct on Linux.
+    pub ignore_case: bool,
+    /// Path prefixes never touched (`--safe-list`), whatever their age: dep
+    /// dirs under one, or containing one, are always kept.
+    pub safe_list: Vec<PathBuf>,
+    /// Remember which file set each project's mtime (`--show-newest`).
+    pub show_newest: bool,
+    /// Also detect `dist/` build output (`--include-dist`); off by default
+    /// because some projects commit sources there.
+    pub include_dist: bool,
+    /// Only detect these dep kinds (`--only`); empty = every type. Dirs from
+    /// `prune_dirs` are not affected. Naming `dist` enables it like `include_dist`.
+    pub only_kinds: Vec<DepKind>,
+    /// Descend into symlinked directories and accept symlinked project dirs
+    /// (`--follow-symlinks`). Off by default: a link under `~` can point at a
+    /// mounted volume or somewhere else entirely.
+    pub follow_symlinks: bool,
+    /// Performance counters (`--probe`), shared by every walk of the run.
+    pub probe: Option<Arc<ScanCounters>>,
+    /// Don't re-walk nested projects inside a stale one (`--no-descend-into-stale`).
+    ///
+    /// Ordering: the bidirectional protection needs every project's mtime
+    /// before active roots can be identified, so discovery still walks the
+    /// whole tree and nothing is skipped there. What is skipped is the mtime
+    /// walk of nested projects. Projects are walked parents first, and when a
+    /// parent's walk covers its whole tree without reaching the active cutoff,
+    /// the parent is stale with no active descendant. Its walk has then already
+    /// seen every nested file, so each nested project gets its mtime from that
+    /// walk instead of walking itself again. Results are identical either way.
+    pub no_descend_into_stale: bool,
+}
+
+/// What each phase of a scan walked and how long it took (`--probe`).
+/// Phases running in parallel (`--parallel-roots`) add up their times.
+#[derive(Debug, Default)]
+pub struct ScanCounters {
+    /// Directories yielded by the discovery walk (dep dirs aren't descended).
+    pub dirs_visited: AtomicU64,
+    /// Non-directory entries seen during discovery.
+    pub files_seen: AtomicU64,
+    pub dep_dirs_found: AtomicU64,
+    /// Per-type dep dir checks (`matches_dir_name`, then the manifest check)
+    /// run on directories that got past the name-set pre-filter.
+    pub type_checks: AtomicU64,
+    /// Files stat'd by the mtime walks.
+    pub mtime_files: AtomicU64,
+    /// Directories read by the mtime walks.
+    pub mtime_dirs: AtomicU64,
+    /// Files counted by the sizing walks.
+    pub sized_files: AtomicU64,
+    discovery_nanos: AtomicU64,
+    mtime_nanos: AtomicU64,
+    sizing_nanos: AtomicU64,
+}
+
+impl ScanCounters {
+    fn add_time(counter: &AtomicU64, since: Instant) {
+        counter.fetch_add(since.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn discovery_time(&self) -> Duration {
+        Duration::from_nanos(self.discovery_nanos.load(Ordering::Relaxed))
+    }
+
+    pub fn mtime_time(&self) -> Duration {
+        Duration::from_nanos(self.mtime_nanos.load(Ordering::Relaxed))
+    }
+
+    pub fn sizing_time(&self) -> Duration {
+        Duration::from_nanos(self.sizing_nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Transient files ignored by default when computing a project's mtime.
+pub const DEFAULT_MTIME_IGNORE: &[&str] = &["*.log", "*.tmp", "*.swp"];
 
 // System paths to protect from accidental deletion
 const PROTECTED_PATHS: &[&str] = &[
@@ -28,10 +181,43 @@ const PROTECTED_PATHS: &[&str] = &[
     "C:\\Program Files (x86)",
 ];
 
+/// Makes `path` absolute and folds `.`/`..` without touching the filesystem.
+/// Fallback for roots that can't be canonicalized (network mounts, dangling
+/// symlink ancestors).
+pub fn lexical_absolute(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut out = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
 fn is_safe_to_scan(path: &Path) -> bool {
+    // Degrade to the lexical path when canonicalization fails; the protection
+    // checks below are component-based and still apply.
     let canonical = match path.canonicalize() {
         Ok(p) => p,
-        Err(_) => return false, // Path doesn't exist or can't be read
+        Err(_) => {
+            let lexical = lexical_absolute(path);
+            if !lexical.exists() {
+                return false; // Path doesn't exist or can't be read
+            }
+            lexical
+        }
     };
 
     for protected in PROTECTED_PATHS {
@@ -76,68 +262,360 @@ fn is_safe_to_scan(path: &Path) -> bool {
     true
 }
 
-fn latest_source_mtime(project_dir: &Path) -> Option<SystemTime> {
+/// Creation time of a dep dir, falling back to mtime where the filesystem
+/// doesn't record birth time.
+fn dep_dir_created(path: &Path) -> Option<SystemTime> {
+    let meta = std::fs::metadata(path).ok()?;
+    meta.created().or_else(|_| meta.modified()).ok()
+}
+
+/// The first manifest of `dep`'s project type, under `project`, modified after
+/// `dep` itself. Pruned dirs (`DepKind::Generic`) have no manifest.
+fn manifest_newer_than_dep(project: &Path, dep: &DepDir, types: &[Box<dyn ProjectType>]) -> Option<PathBuf> {
+    let proj_type = types.iter().find(|t| t.dep_kind() == dep.kind)?;
+    let dep_mtime = std::fs::metadata(&dep.path).and_then(|m| m.modified()).ok()?;
+    proj_type.manifest_names().iter()
+        .map(|name| project.join(name))
+        .find(|manifest| {
+            std::fs::metadata(manifest)
+                .and_then(|m| m.modified())
+                .is_ok_and(|t| t > dep_mtime)
+        })
+}
+
+/// Nearest ancestor (or self) containing `.git` — a dir, or a file for worktrees/submodules.
+fn git_repo_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|p| p.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Whether `path` lies inside a git submodule. Two signs, checked on every
+/// ancestor: a `.git` *file* pointing into a superproject's `.git/modules/`
+/// (worktrees also use a `.git` file, but point to `.git/worktrees/`, which
+/// may itself sit under an unrelated `modules` dir), or a
+/// `.gitmodules` listing a `path = ...` that contains `path`.
+fn in_submodule(path: &Path) -> bool {
+    path.ancestors().any(|dir| {
+        let git_file = std::fs::read_to_string(dir.join(".git"))
+            .ok()
+            .and_then(|c| c.strip_prefix("gitdir:").map(|g| g.trim().to_string()));
+        let into_modules = |gitdir: String| {
+            let components: Vec<_> = Path::new(&gitdir).components().map(|c| c.as_os_str().to_owned()).collect();
+            components.windows(2).any(|pair| pair[0] == ".git" && pair[1] == "modules")
+        };
+        if git_file.is_some_and(into_modules) {
+            return true;
+        }
+        std::fs::read_to_string(dir.join(".gitmodules")).is_ok_and(|content| {
+            content
+                .lines()
+                .filter_map(|l| l.trim().strip_prefix("path"))
+                .filter_map(|l| l.trim_start().strip_prefix('='))
+                .any(|sub| path.starts_with(dir.join(sub.trim())))
+        })
+    })
+}
+
+/// `git status --porcelain` non-empty. `None` if git isn't available or fails.
+fn repo_is_dirty(repo_root: &Path) -> Option<bool> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["status", "--porcelain"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(!output.stdout.is_empty())
+}
+
+/// Newest source mtime of a project and, when asked for, the file that set it.
+#[derive(Debug, Clone, PartialEq)]
+struct SourceMtime {
+    time: SystemTime,
+    /// Only filled with `track_newest`; the project dir itself when its own
+    /// mtime wins (`include_dir_mtime`).
+    newest: Option<PathBuf>,
+    /// Files stat'd by the walk (`--probe`).
+    checked: u64,
+    /// Directories read by the walk (`--probe`).
+    dirs: u64,
+    /// Mtimes of the nested projects the walk went through in full, keyed by
+    /// path (`--no-descend-into-stale`). Empty unless the walk completed.
+    nested: HashMap<PathBuf, SourceMtime>,
+}
+
+impl SourceMtime {
+    fn file(time: SystemTime, newest: Option<PathBuf>) -> Self {
+        SourceMtime { time, newest, checked: 0, dirs: 0, nested: HashMap::new() }
+    }
+}
+
+/// Settings shared by every mtime walk of a scan.
+struct MtimeWalk<'a> {
+    ignore: &'a [glob::Pattern],
+    /// Abandon the walk (result `None`) once past it.
+    deadline: Option<Instant>,
+    jobs: &'a WalkJobs,
+    include_dir_mtime: bool,
+    /// Record which file is newest (`--show-newest`); off, no path is cloned.
+    track_newest: bool,
+}
+
+/// Returns `None` if the walk hits `deadline`: a partial mtime could miss newer
+/// files and wrongly mark the project stale.
+///
+/// With `stop_at`, the walk short-circuits on the first file at or after it: the
+/// project is active either way, so the rest of the tree doesn't matter.
+///
+/// Files under one of the `nested` project dirs also count towards that
+/// project's own mtime. When the walk completes, each nested project it read
+/// is returned in `SourceMtime::nested` with exactly what walking it on its
+/// own would give: same filters, and no file could have short-circuited it.
+fn latest_source_mtime(
+    project_dir: &Path,
+    walk: &MtimeWalk,
+    stop_at: Option<SystemTime>,
+    nested: &[PathBuf],
+) -> Option<SourceMtime> {
     let skip_dirs: &[&str] = &[
         "node_modules", "target", ".next", "dist", "build",
-        ".git", "venv", ".venv", "vendor",
+        ".git", "venv", ".venv", "vendor", "Pods", "Carthage",
+        "zig-cache", ".zig-cache", "zig-out", ".angular", ".nx",
     ];
 
-    let latest = Arc::new(Mutex::new(None::<SystemTime>));
+    let latest = Arc::new(Mutex::new(None::<SourceMtime>));
     let latest_clone = latest.clone();
+    // Per nested project: newest file so far, and whether its dir was read
+    let nested_latest = Arc::new(Mutex::new(vec![(None::<SourceMtime>, false); nested.len()]));
+    let nested_clone = nested_latest.clone();
+    let nested_dirs = nested.to_vec();
+    let ignore = walk.ignore.to_vec();
+    let deadline = walk.deadline;
+    let track_newest = walk.track_newest;
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let timed_out_clone = timed_out.clone();
+    let found_active = Arc::new(AtomicBool::new(false));
+    let found_active_clone = found_active.clone();
+    let checked = Arc::new(AtomicU64::new(0));
+    let checked_clone = checked.clone();
+    let dirs = Arc::new(AtomicU64::new(0));
+    let dirs_clone = dirs.clone();
 
     // Use process_read_dir to effectively skip descending into ignored directories
     // avoiding the overhead of walking huge dependency trees just to ignore them later.
     WalkDir::new(project_dir)
+        .parallelism(walk.jobs.parallelism())
         .skip_hidden(false)
         .follow_links(false)
-        .process_read_dir(move |_depth, _path, _read_dir_state, children| {
+        .process_read_dir(move |depth, path, _read_dir_state, children| {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                timed_out_clone.store(true, Ordering::Relaxed);
+                children.clear();
+                return;
+            }
+            if found_active_clone.load(Ordering::Relaxed) {
+                children.clear();
+                return;
+            }
+            // The root entry is the project dir itself (a symlink, with
+            // --follow-symlinks): its own mtime is include_dir_mtime's call
+            if depth.is_none() {
+                return;
+            }
+            dirs_clone.fetch_add(1, Ordering::Relaxed);
+
+            // Nested projects this dir belongs to
+            let owners: Vec<usize> = (0..nested_dirs.len()).filter(|&i| path.starts_with(&nested_dirs[i])).collect();
+            if !owners.is_empty() {
+                let mut guard = nested_clone.lock().unwrap();
+                for &i in &owners {
+                    if path == nested_dirs[i] {
+                        guard[i].1 = true;
+                    }
+                }
+            }
+
             // 1. Process files in current directory to update mtime
-            for entry_result in children.iter() {
-                if let Ok(entry) = entry_result {
-                    if !entry.file_type().is_dir() {
-                        if let Ok(meta) = entry.metadata() {
-                            if let Ok(mtime) = meta.modified() {
-                                let mut guard = latest_clone.lock().unwrap();
-                                *guard = Some(match *guard {
-                                    Some(current) => current.max(mtime),
-                                    None => mtime,
-                                });
+            for entry in children.iter().flatten() {
+                if !entry.file_type().is_dir() {
+                    let name = entry.file_name().to_string_lossy();
+                    if ignore.iter().any(|p| p.matches(&name)) {
+                        continue;
+                    }
+                    checked_clone.fetch_add(1, Ordering::Relaxed);
+                    if let Ok(meta) = entry.metadata() {
+                        if let Ok(mtime) = meta.modified() {
+                            let newer = |current: &Option<SourceMtime>| current.as_ref().is_none_or(|c| mtime > c.time);
+                            let mut guard = latest_clone.lock().unwrap();
+                            if newer(&guard) {
+                                *guard = Some(SourceMtime::file(mtime, track_newest.then(|| entry.path())));
+                            }
+                            drop(guard);
+                            if !owners.is_empty() {
+                                let mut guard = nested_clone.lock().unwrap();
+                                for &i in &owners {
+                                    if newer(&guard[i].0) {
+                                        guard[i].0 = Some(SourceMtime::file(mtime, track_newest.then(|| entry.path())));
+                                    }
+                                }
+                            }
+                            if stop_at.is_some_and(|cutoff| mtime >= cutoff) {
+                                found_active_clone.store(true, Ordering::Relaxed);
                             }
                         }
                     }
                 }
             }
 
+            // Short-circuit: don't descend once the project is known active
+            if found_active_clone.load(Ordering::Relaxed) {
+                children.clear();
+                return;
+            }
+
             // 2. Filter directories to descend into
             children.retain(|dir_entry_result| {
                  dir_entry_result.as_ref().map(|e| {
                     if !e.file_type().is_dir() { return true; }
                     let name = e.file_name().to_string_lossy();
-                    !skip_dirs.contains(&name.as_ref())
+                    // CMake build dirs get a per-config name (`cmake-build-debug`)
+                    !skip_dirs.contains(&name.as_ref()) && !name.starts_with("cmake-build-")
                 }).unwrap_or(false)
             });
         })
         .into_iter()
         .for_each(|_| {}); 
 
-    let res = *latest.lock().unwrap();
-    res
+    if timed_out.load(Ordering::Relaxed) {
+        return None;
+    }
+    let mut res = with_dir_mtime(latest.lock().unwrap().take(), project_dir, walk)?;
+    res.checked = checked.load(Ordering::Relaxed);
+    res.dirs = dirs.load(Ordering::Relaxed);
+    if !found_active.load(Ordering::Relaxed) {
+        let nested_latest = std::mem::take(&mut *nested_latest.lock().unwrap());
+        res.nested = nested.iter()
+            .zip(nested_latest)
+            .filter(|(_, (_, read))| *read)
+            .filter_map(|(dir, (mtime, _))| Some((dir.clone(), with_dir_mtime(mtime, dir, walk)?)))
+            .collect();
+    }
+    Some(res)
+}
+
+/// Folds the project dir's own mtime into the walk's result (`include_dir_mtime`).
+fn with_dir_mtime(files: Option<SourceMtime>, project_dir: &Path, walk: &MtimeWalk) -> Option<SourceMtime> {
+    if !walk.include_dir_mtime {
+        return files;
+    }
+    let own = std::fs::metadata(project_dir)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|time| SourceMtime::file(time, walk.track_newest.then(|| project_dir.to_path_buf())));
+    match (files, own) {
+        (Some(files), Some(dir)) if dir.time > files.time => Some(dir),
+        (files, own) => files.or(own),
+    }
+}
+
+/// Lowercased copy of `path` for `--ignore-case` comparisons.
+fn fold_case(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().to_lowercase())
+}
+
+/// Identifies the filesystem a path lives on, for `--one-file-system`.
+#[cfg(unix)]
+type DeviceId = u64;
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<DeviceId> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::symlink_metadata(path).ok().map(|m| m.dev())
+}
+
+/// No cheap device id on Windows: approximated by the drive letter / UNC share,
+/// so mounted folders (junctions to other volumes) are not detected.
+#[cfg(not(unix))]
+type DeviceId = std::ffi::OsString;
+
+#[cfg(not(unix))]
+fn device_id(path: &Path) -> Option<DeviceId> {
+    match path.components().next()? {
+        std::path::Component::Prefix(prefix) => Some(prefix.as_os_str().to_os_string()),
+        _ => None,
+    }
+}
+
+/// A project found during discovery, before any staleness filtering.
+#[derive(Debug, Clone)]
+pub struct ProjectInfo {
+    pub path: PathBuf,
+    pub deps: Vec<DepDir>,
+    pub last_modified: SystemTime,
+    /// File behind `last_modified`, with `ScanOptions::show_newest`.
+    pub newest_file: Option<PathBuf>,
+    /// The project's own `.faxina.toml`, if it has one.
+    pub project_config: Option<ProjectConfig>,
+}
+
+impl ProjectInfo {
+    /// `--prune-dir` entries are their own "project" (path == the dir itself).
+    fn is_pruned(&self) -> bool {
+        matches!(self.deps.as_slice(), [DepDir { kind: DepKind::Generic(_), .. }])
+    }
+}
+
+/// Pass 1 + 2: walks `root` and returns every project found (stale or not)
+/// with its dep dirs and source mtime. No staleness filtering, no sizing.
+pub fn discover_projects<F>(root: &Path, options: &ScanOptions, on_progress: Option<F>) -> Vec<ProjectInfo>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    discover(root, options, on_progress, None).0
 }
 
-pub fn scan_projects<F>(root: &Path, days: u64, ignored_paths: &[PathBuf], on_progress: Option<F>) -> Vec<StaleProject>
+/// `discover_projects`, but each mtime walk stops at the first file at or after
+/// `active_cutoff`. The reported mtime of an active project is then only a lower
+/// bound (enough to classify it, not to display it).
+/// Also returns the directories that could not be read (path, error).
+fn discover<F>(
+    root: &Path,
+    options: &ScanOptions,
+    on_progress: Option<F>,
+    active_cutoff: Option<SystemTime>,
+) -> (Vec<ProjectInfo>, Vec<(PathBuf, String)>)
 where
     F: Fn() + Send + Sync + 'static,
 {
     if !is_safe_to_scan(root) {
         warn!("Protected path detected: {}. Scan aborted for safety.", root.display());
-        return Vec::new();
+        return (Vec::new(), Vec::new());
+    }
+    if !options.follow_symlinks && root.is_symlink() {
+        warn!("Skipping symlinked root {} (use --follow-symlinks).", root.display());
+        return (Vec::new(), Vec::new());
     }
 
-    let threshold = SystemTime::now() - Duration::from_secs(days * 24 * 3600);
-    let project_types = Arc::new(all_project_types());
+    let include_dist = options.include_dist || options.only_kinds.contains(&DepKind::Dist);
+    let types = project_types(include_dist);
+    // `--only` filters what is registered, not what is detected: a `target/`
+    // left out of the selection is still a dep dir, pruned like in a full scan
+    let only_kinds = options.only_kinds.clone();
+    // Every name the types manage: one set lookup per directory rules out most
+    // of the tree before any per-type check. Types with variable names
+    // (`*.egg-info`) can't be listed and are always checked.
+    let dir_names: HashSet<&'static str> = types.iter()
+        .flat_map(|t| t.dependency_dir_names().iter().copied())
+        .collect();
+    let project_types = Arc::new(types);
     
-    let ignored_paths_canonical: Vec<PathBuf> = ignored_paths.iter()
+    let ignore_case = options.ignore_case;
+    let ignored_paths_canonical: Vec<PathBuf> = options.ignored_paths.iter()
         .filter_map(|p| p.canonicalize().ok().or_else(|| Some(p.clone())))
+        .map(|p| if ignore_case { fold_case(&p) } else { p })
         .collect();
     let ignored_paths_shared: Arc<Vec<PathBuf>> = Arc::new(ignored_paths_canonical);
 
@@ -146,11 +624,34 @@ where
     let findings_clone = findings.clone();
     let pt_clone = project_types.clone();
     let ign_clone = ignored_paths_shared.clone();
+    let pruned: Arc<Mutex<Vec<DepDir>>> = Arc::new(Mutex::new(Vec::new()));
+    let pruned_clone = pruned.clone();
+    let prune_names = Arc::new(options.prune_dirs.clone());
+    let deadline = options.scan_timeout.map(|t| Instant::now() + t);
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let timed_out_clone = timed_out.clone();
+    let root_device = if options.one_file_system { device_id(root) } else { None };
+    let unreadable: Arc<Mutex<Vec<(PathBuf, String)>>> = Arc::new(Mutex::new(Vec::new()));
+    let unreadable_clone = unreadable.clone();
+    let root_owned = root.to_path_buf();
+    let follow_symlinks = options.follow_symlinks;
+    let probe = options.probe.clone();
+    let probe_clone = probe.clone();
+    let probe_checks = probe.clone();
+    let discovery_start = Instant::now();
     
     WalkDir::new(root)
+        .parallelism(options.jobs.parallelism())
         .skip_hidden(false)
-        .follow_links(false)
+        .follow_links(follow_symlinks)
         .process_read_dir(move |_depth, _path, _read_dir_state, children| {
+             // Past the deadline: stop descending; what was found so far is kept
+             if deadline.is_some_and(|d| Instant::now() >= d) {
+                 timed_out_clone.store(true, Ordering::Relaxed);
+                 children.clear();
+                 return;
+             }
+
              children.retain(|dir_entry_result| {
                 let entry = match dir_entry_result.as_ref() {
                     Ok(e) => e,
@@ -159,29 +660,83 @@ where
                 let entry_path = entry.path();
                 let name = entry.file_name().to_string_lossy();
 
+                let comparable = if ignore_case { fold_case(&entry_path) } else { entry_path.clone() };
                 for ignored in ign_clone.iter() {
-                    if entry_path == *ignored { return false; }
+                    if comparable == *ignored { return false; }
                 }
 
-                if entry.file_type().is_dir() {
+                // Symlinked dep dirs (shared stores) are detected but never descended into
+                let is_dir = entry.file_type().is_dir()
+                    || (entry.file_type().is_symlink() && entry_path.is_dir());
+
+                if is_dir {
+                    // 0. Mount boundary (--one-file-system): nothing across it is touched
+                    if let Some(dev) = &root_device {
+                        if device_id(&entry_path).is_some_and(|d| d != *dev) {
+                            debug!("Skipping {}: different filesystem", entry_path.display());
+                            return false;
+                        }
+                    }
+
                     // 1. Dependency Detection
-                    for proj_type in pt_clone.iter() {
-                        if proj_type.is_dependency_dir(&entry_path) {
-                            if let Some(parent) = entry_path.parent() {
-                                let mut map = findings_clone.lock().unwrap();
-                                map.entry(parent.to_path_buf())
-                                   .or_default()
-                                   .push(DepDir {
-                                       path: entry_path.clone(),
-                                       size: 0,
-                                       kind: proj_type.dep_kind(),
-                                   });
+                    // Cheap name pre-filters before the manifest check: the set of
+                    // managed names first, then each type's own. When several
+                    // types claim the dir (`target` next to Cargo.toml and pom.xml),
+                    // registration order in `all_project_types` decides.
+                    let known_name = dir_names.contains(name.as_ref());
+                    let mut matches = pt_clone.iter()
+                        .filter(|t| known_name || t.has_variable_dir_names())
+                        .filter(|t| {
+                            if let Some(counters) = &probe_checks {
+                                counters.type_checks.fetch_add(1, Ordering::Relaxed);
                             }
-                            return false; // Don't descend into dep dirs
+                            t.matches_dir_name(&name)
+                                && t.is_dependency_dir(&entry_path)
+                        });
+                    if let Some(proj_type) = matches.next() {
+                        let others: Vec<&'static str> = matches.map(|t| t.name()).collect();
+                        if !others.is_empty() {
+                            debug!(
+                                "Ambiguous dep dir {}: detected as {} (also matches {})",
+                                entry_path.display(), proj_type.name(), others.join(", ")
+                            );
+                        }
+                        if !only_kinds.is_empty() && !only_kinds.contains(&proj_type.dep_kind()) {
+                            return false; // Not selected by --only, but still not descended into
+                        }
+                        if let Some(project_root) = proj_type.project_root(&entry_path) {
+                            let mut map = findings_clone.lock().unwrap();
+                            map.entry(project_root)
+                               .or_default()
+                               .push(DepDir {
+                                   path: entry_path.clone(),
+                                   size: 0,
+                                   files: 0,
+                                   kind: proj_type.dep_kind(),
+                               });
                         }
+                        return false; // Don't descend into dep dirs
                     }
 
-                    // 2. Skip common non-project hidden/cache dirs
+                    // 2. Arbitrary named dirs (--prune-dir), no manifest required
+                    if prune_names.iter().any(|n| n == name.as_ref()) {
+                        pruned_clone.lock().unwrap().push(DepDir {
+                            path: entry_path.clone(),
+                            size: 0,
+                            files: 0,
+                            kind: DepKind::Generic(name.to_string()),
+                        });
+                        return false;
+                    }
+
+                    // Other symlinked dirs are only walked with --follow-symlinks
+                    // (then file_type() already reports the target)
+                    if !entry.file_type().is_dir() {
+                        debug!("Skipping symlinked dir {}", entry_path.display());
+                        return false;
+                    }
+
+                    // 3. Skip common non-project hidden/cache dirs
                     const SKIP_DIRS: &[&str] = &[
                         ".git", ".next", ".vscode", ".cursor", ".idea", ".eclipse",
                         ".local", ".cache", ".cargo", ".rustup", ".npm", ".nvm",
@@ -200,60 +755,277 @@ where
              if let Some(cb) = on_progress.as_ref() {
                 cb();
              }
-             let _ = entry;
+             if let (Some(counters), Ok(e)) = (probe_clone.as_ref(), &entry) {
+                 let counter = if e.file_type().is_dir() { &counters.dirs_visited } else { &counters.files_seen };
+                 counter.fetch_add(1, Ordering::Relaxed);
+             }
+             // A dir whose listing failed (permission denied, ...) would otherwise
+             // just vanish from the results
+             let describe = |err: &jwalk::Error| {
+                 err.io_error().map(|e| e.to_string()).unwrap_or_else(|| err.to_string())
+             };
+             let failed = match &entry {
+                 Ok(e) => e.read_children_error.as_ref().map(|err| (e.path(), describe(err))),
+                 Err(err) => Some((
+                     err.path().map(Path::to_path_buf).unwrap_or_else(|| root_owned.clone()),
+                     describe(err),
+                 )),
+             };
+             if let Some((path, message)) = failed {
+                 debug!("Could not read {}: {}", path.display(), message);
+                 unreadable_clone.lock().unwrap().push((path, message));
+             }
         });
 
-    // Pass 2: Calculate mtimes and Identify Active Roots
-    struct ProjectInfo {
-        path: PathBuf,
-        deps: Vec<DepDir>,
-        last_modified: SystemTime,
-    }
-
+    // Pass 2: Calculate mtimes
     let raw_projects = {
         let mut guard = findings.lock().unwrap();
         std::mem::take(&mut *guard)
     };
+    if let Some(counters) = &probe {
+        ScanCounters::add_time(&counters.discovery_nanos, discovery_start);
+        let found = raw_projects.values().map(Vec::len).sum::<usize>() + pruned.lock().unwrap().len();
+        counters.dep_dirs_found.fetch_add(found as u64, Ordering::Relaxed);
+    }
+    let mtime_start = Instant::now();
+    let mtime_deadline = options.scan_timeout.map(|t| mtime_start + t);
 
     let mut project_infos: Vec<ProjectInfo> = Vec::with_capacity(raw_projects.len());
-    let mut active_roots: Vec<PathBuf> = Vec::new();
 
-    for (path, deps) in raw_projects {
-        let last_modified = match latest_source_mtime(&path) {
+    // Parents before their nested projects, which follow them contiguously
+    let mut raw_projects: Vec<(PathBuf, Vec<DepDir>)> = raw_projects.into_iter().collect();
+    raw_projects.sort_by(|a, b| a.0.cmp(&b.0));
+    let all_paths: Vec<PathBuf> = raw_projects.iter().map(|(p, _)| p.clone()).collect();
+    let walk = MtimeWalk {
+        ignore: &options.mtime_ignore,
+        deadline: mtime_deadline,
+        jobs: &options.jobs,
+        include_dir_mtime: options.include_dir_mtime,
+        track_newest: options.show_newest,
+    };
+    // Mtimes of nested projects already covered by an enclosing project's walk
+    let mut inherited: HashMap<PathBuf, SourceMtime> = HashMap::new();
+
+    for (i, (path, deps)) in raw_projects.into_iter().enumerate() {
+        // A symlinked project dir (e.g. the scan root itself) is never a project
+        if !options.follow_symlinks && path.is_symlink() {
+            debug!("Skipping symlinked project dir {}", path.display());
+            continue;
+        }
+        // One extra read per found project, never per visited dir
+        let project_config = config::load_project_config(&path);
+        // A per-project `days` may be stricter than the shared cutoff, so the
+        // walk can't stop at a file that is only "active" by the global one
+        let stop_at = if project_config.as_ref().is_some_and(|c| c.days.is_some()) { None } else { active_cutoff };
+        let walked = match inherited.remove(&path) {
+            Some(mtime) => {
+                debug!("Reusing mtime of {} from an enclosing stale project's walk", path.display());
+                Some(mtime)
+            }
+            // Only a walk that could stop at `active_cutoff` and didn't proves
+            // every nested file older than it, so nested results are only
+            // collected with a cutoff
+            None if options.no_descend_into_stale && stop_at.is_some() => {
+                let nested = all_paths[i + 1..].iter().take_while(|p| p.starts_with(&path)).count();
+                latest_source_mtime(&path, &walk, stop_at, &all_paths[i + 1..i + 1 + nested])
+            }
+            None => latest_source_mtime(&path, &walk, stop_at, &[]),
+        };
+        let mut last_modified = match walked {
             Some(t) => t,
+            None if mtime_deadline.is_some_and(|d| Instant::now() >= d) => {
+                timed_out.store(true, Ordering::Relaxed);
+                debug!("Scan timeout before mtime of {} was known; skipping.", path.display());
+                continue;
+            }
             None => {
                 debug!("Could not read mtime for {}; ignoring.", path.display());
                 continue; 
             }
         };
 
-        if last_modified >= threshold {
-            active_roots.push(path.clone());
+        if let Some(counters) = &probe {
+            counters.mtime_files.fetch_add(last_modified.checked, Ordering::Relaxed);
+            counters.mtime_dirs.fetch_add(last_modified.dirs, Ordering::Relaxed);
         }
-
+        inherited.extend(std::mem::take(&mut last_modified.nested));
         project_infos.push(ProjectInfo {
             path,
             deps,
+            last_modified: last_modified.time,
+            newest_file: last_modified.newest,
+            project_config,
+        });
+    }
+    if let Some(counters) = &probe {
+        ScanCounters::add_time(&counters.mtime_nanos, mtime_start);
+    }
+
+    if timed_out.load(Ordering::Relaxed) {
+        warn!(
+            "Scan timeout reached: results are PARTIAL ({} fully discovered projects); unvisited dirs were skipped.",
+            project_infos.len()
+        );
+    }
+
+    // Pruned dirs are their own "project", anchored on the dir's own mtime.
+    let pruned = std::mem::take(&mut *pruned.lock().unwrap());
+    for dep in pruned {
+        let last_modified = match std::fs::metadata(&dep.path).and_then(|m| m.modified()) {
+            Ok(t) => t,
+            Err(_) => {
+                debug!("Could not read mtime for {}; ignoring.", dep.path.display());
+                continue;
+            }
+        };
+        project_infos.push(ProjectInfo {
+            path: dep.path.clone(),
+            deps: vec![dep],
             last_modified,
+            newest_file: None,
+            project_config: None,
         });
     }
 
+    project_infos.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut unreadable = std::mem::take(&mut *unreadable.lock().unwrap());
+    unreadable.sort();
+    (project_infos, unreadable)
+}
+
+/// Result of a scan: cleanable projects plus, when `ScanOptions::collect_protected`
+/// is set, the stale-looking ones that were kept and why.
+#[derive(Debug, Default)]
+pub struct ScanOutcome {
+    pub stale: Vec<StaleProject>,
+    /// Each entry has `is_protected_reason()` set. Not sized by the scan.
+    pub protected: Vec<StaleProject>,
+    /// Directories discovery could not list (path, error): the scan is incomplete.
+    pub unreadable: Vec<(PathBuf, String)>,
+    /// Stale dep dirs kept because of the safe-list.
+    pub safe_listed: Vec<PathBuf>,
+}
+
+impl ScanOutcome {
+    /// Folds in the outcome of another root (`--root-glob`). A project reached
+    /// from two overlapping roots is kept once.
+    pub fn merge(&mut self, other: ScanOutcome) {
+        self.stale.extend(other.stale);
+        self.protected.extend(other.protected);
+        self.unreadable.extend(other.unreadable);
+        self.safe_listed.extend(other.safe_listed);
+
+        for list in [&mut self.stale, &mut self.protected] {
+            list.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+            list.dedup_by(|a, b| a.path == b.path);
+        }
+        self.unreadable.sort();
+        self.unreadable.dedup();
+        self.safe_listed.sort();
+        self.safe_listed.dedup();
+    }
+}
+
+fn display_name(proj: &ProjectInfo) -> String {
+    let mut name = proj.path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| proj.path.display().to_string());
+    if proj.is_pruned() {
+        // e.g. "app/.cache" is more useful than a bare ".cache"
+        if let Some(parent) = proj.path.parent().and_then(|p| p.file_name()) {
+            name = format!("{}/{}", parent.to_string_lossy(), name);
+        }
+    }
+    name
+}
+
+pub fn scan_projects<F>(root: &Path, options: &ScanOptions, on_progress: Option<F>) -> ScanOutcome
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    let threshold = options.stale_before
+        .unwrap_or_else(|| SystemTime::now() - Duration::from_secs(options.days * 24 * 3600));
+    let keep_rules: Vec<KeepRule> = options.keep_rules.iter()
+        .map(|r| KeepRule {
+            kind: r.kind.clone(),
+            prefix: r.prefix.canonicalize().unwrap_or_else(|_| r.prefix.clone()),
+        })
+        .collect();
+    let safe_list: Vec<PathBuf> = options.safe_list.iter()
+        .map(|p| p.canonicalize().unwrap_or_else(|_| p.clone()))
+        .collect();
+    let min_age_cutoff = options.min_age_days
+        .map(|d| SystemTime::now() - Duration::from_secs(d * 24 * 3600));
+
+    let active_threshold = match options.protect_active_within {
+        Some(d) => SystemTime::now() - Duration::from_secs(d * 24 * 3600),
+        None => threshold,
+    };
+
+    // Any file newer than both windows settles every activity check below
+    let (project_infos, unreadable) =
+        discover(root, options, on_progress, Some(threshold.max(active_threshold)));
+
+    // A project's `.faxina.toml` `days` replaces the threshold for it, in the
+    // staleness check and in deciding whether it protects its neighbours
+    let own_threshold = |proj: &ProjectInfo| match &proj.project_config {
+        Some(ProjectConfig { days: Some(d), .. }) => SystemTime::now() - Duration::from_secs(d * 24 * 3600),
+        _ => threshold,
+    };
+
+    // Identify Active Roots. Pruned dirs never count as active roots,
+    // but are still protected by active ones.
+    let active_roots: Vec<PathBuf> = project_infos.iter()
+        .filter(|p| {
+            let window = if options.protect_active_within.is_some() { active_threshold } else { own_threshold(p) };
+            p.last_modified >= window && !p.is_pruned()
+        })
+        .map(|p| p.path.clone())
+        .collect();
+
     // Pass 3: Filter Stale Projects (Bidirectional Protection)
     // - Protect if project itself is active (already handled by mtime check)
     // - Protect if project is child of Active Root
     // - Protect if project contains an Active Root
     
     let mut stale: Vec<StaleProject> = Vec::new();
+    let mut protected: Vec<StaleProject> = Vec::new();
+    let mut safe_listed: Vec<PathBuf> = Vec::new();
+    let mut protect = |proj: ProjectInfo, reason: ProtectionReason| {
+        if options.collect_protected {
+            protected.push(
+                StaleProject::new(display_name(&proj), proj.path, proj.deps, proj.last_modified)
+                    .with_newest_file(proj.newest_file)
+                    .with_protection(reason),
+            );
+        }
+    };
+    let known_types = all_project_types();
+    // One `git status` per repo, however many projects a monorepo holds
+    let mut dirty_repos: HashMap<PathBuf, bool> = HashMap::new();
+
+    for mut proj in project_infos {
+        // Condition 0: The project's own `.faxina.toml`. `protected` always
+        // wins; `days` replaces the threshold for this project only.
+        let project_threshold = match &proj.project_config {
+            Some(c) if c.protected => {
+                debug!("Protected by {}: {}", config::PROJECT_CONFIG_FILE, proj.path.display());
+                protect(proj, ProtectionReason::ProjectConfig);
+                continue;
+            }
+            _ => own_threshold(&proj),
+        };
 
-    for proj in project_infos {
         // Condition 1: Must be old
-        if proj.last_modified >= threshold {
+        if proj.last_modified >= project_threshold {
+            protect(proj, ProtectionReason::ActiveSelf);
             continue;
         }
 
         // Condition 2: Must NOT be inside an Active Root (active parent protects child)
         if let Some(parent_root) = active_roots.iter().find(|root| proj.path.starts_with(root) && &proj.path != *root) {
             debug!("Protected child project: {} (Parent {} is active)", proj.path.display(), parent_root.display());
+            protect(proj, ProtectionReason::ActiveParent);
             continue;
         }
 
@@ -261,33 +1033,241 @@ where
         // e.g. Monorepo (Stale) -> Package (Active). don't delete Monorepo node_modules.
         if let Some(child_root) = active_roots.iter().find(|root| root.starts_with(&proj.path) && *root != &proj.path) {
              debug!("Protected parent project: {} (Child {} is active)", proj.path.display(), child_root.display());
+             protect(proj, ProtectionReason::ActiveChild);
              continue;
         }
 
-        let name = proj.path.file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| proj.path.display().to_string());
+        // Condition 4 (opt-in): Must NOT have an active sibling project
+        if options.protect_active_siblings {
+            if let Some(sibling) = active_roots.iter().find(|root| root.parent() == proj.path.parent() && *root != &proj.path) {
+                debug!("Protected sibling project: {} (Sibling {} is active)", proj.path.display(), sibling.display());
+                protect(proj, ProtectionReason::ActiveSibling);
+                continue;
+            }
+        }
 
-        stale.push(StaleProject {
-            name,
-            path: proj.path,
-            dep_dirs: proj.deps,
-            last_modified: proj.last_modified,
-        });
+        // Condition 5: Freshly installed deps (--min-age), even under old sources
+        if let Some(cutoff) = min_age_cutoff {
+            if let Some(fresh) = proj.deps.iter().find(|d| dep_dir_created(&d.path).is_none_or(|t| t >= cutoff)) {
+                debug!("Protected fresh deps: {} (created within min-age window)", fresh.path.display());
+                protect(proj, ProtectionReason::FreshDeps);
+                continue;
+            }
+        }
+
+        // Condition 5b (opt-in): Manifest edited after the deps were built
+        if options.protect_manifest_newer {
+            let newer = proj.deps.iter().find_map(|d| manifest_newer_than_dep(&proj.path, d, &known_types).map(|m| (m, &d.path)));
+            if let Some((manifest, dep)) = newer {
+                debug!("Protected outdated deps: {} is newer than {}", manifest.display(), dep.display());
+                protect(proj, ProtectionReason::ManifestNewer);
+                continue;
+            }
+        }
+
+        // Condition 6 (opt-in): Repo has uncommitted work (--protect-dirty)
+        if options.protect_dirty {
+            if let Some(repo) = git_repo_root(&proj.path) {
+                let dirty = *dirty_repos.entry(repo.clone()).or_insert_with(|| {
+                    // Fail closed: if git can't tell, the work may be uncommitted.
+                    repo_is_dirty(&repo).unwrap_or_else(|| {
+                        debug!("git status failed for {}; treating as dirty", repo.display());
+                        true
+                    })
+                });
+                if dirty {
+                    debug!("Protected dirty repo: {} (uncommitted changes in {})", proj.path.display(), repo.display());
+                    protect(proj, ProtectionReason::DirtyRepo);
+                    continue;
+                }
+            }
+        }
+
+        // Condition 6b (opt-in): Inside a git submodule (--skip-submodules)
+        if options.skip_submodules && in_submodule(&proj.path) {
+            debug!("Protected submodule project: {}", proj.path.display());
+            protect(proj, ProtectionReason::Submodule);
+            continue;
+        }
+
+        // Condition 7: Safe-list (--safe-list). Removing a dep dir that contains
+        // a listed path would touch it too, so both directions count.
+        let (safe, deps): (Vec<DepDir>, Vec<DepDir>) = std::mem::take(&mut proj.deps)
+            .into_iter()
+            .partition(|dep| safe_list.iter().any(|s| dep.path.starts_with(s) || s.starts_with(&dep.path)));
+        for dep in &safe {
+            debug!("Kept by safe-list: {}", dep.path.display());
+            safe_listed.push(dep.path.clone());
+        }
+        proj.deps = deps;
+        if !safe.is_empty() {
+            let info = ProjectInfo { deps: safe, ..proj.clone() };
+            protect(info, ProtectionReason::SafeList);
+        }
+        if proj.deps.is_empty() {
+            continue;
+        }
+
+        // Condition 8: Path-scoped kind protection (--keep <kind>@<path>)
+        let (kept, deps): (Vec<DepDir>, Vec<DepDir>) = std::mem::take(&mut proj.deps)
+            .into_iter()
+            .partition(|dep| keep_rules.iter().any(|r| r.kind == dep.kind && proj.path.starts_with(&r.prefix)));
+        for dep in &kept {
+            debug!("Kept by rule: {} ({} under protected prefix)", dep.path.display(), dep.kind);
+        }
+        proj.deps = deps;
+        if !kept.is_empty() {
+            // Only the kept dirs are reported as protected; the rest is still cleaned
+            let info = ProjectInfo { deps: kept, ..proj.clone() };
+            protect(info, ProtectionReason::KeepRule);
+        }
+        if proj.deps.is_empty() {
+            continue;
+        }
+
+        let name = display_name(&proj);
+
+        stale.push(
+            StaleProject::new(name, proj.path, proj.deps, proj.last_modified)
+                .with_newest_file(proj.newest_file),
+        );
     }
 
     stale.sort_by(|a, b| a.name.cmp(&b.name));
-    stale
+    protected.sort_by(|a, b| a.name.cmp(&b.name));
+    safe_listed.sort();
+    ScanOutcome { stale, protected, unreadable, safe_listed }
+}
+
+/// Scans each root with `scan_projects` and merges the outcomes. Up to
+/// `parallel` roots are scanned at once (`--parallel-roots`); 0 or 1 is serial.
+/// Each root is protected on its own: an active project in one root does not
+/// protect a parent/child/sibling found from another root.
+pub fn scan_roots<F>(roots: &[PathBuf], options: &ScanOptions, on_progress: F, parallel: usize) -> ScanOutcome
+where
+    F: Fn() + Clone + Send + Sync + 'static,
+{
+    let mut outcome = ScanOutcome::default();
+    if parallel <= 1 || roots.len() <= 1 {
+        for root in roots {
+            outcome.merge(scan_projects(root, options, Some(on_progress.clone())));
+        }
+        return outcome;
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, ScanOutcome)>> = Mutex::new(Vec::with_capacity(roots.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.min(roots.len()) {
+            let on_progress = on_progress.clone();
+            let (next, results) = (&next, &results);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(root) = roots.get(i) else { break };
+                let scanned = scan_projects(root, options, Some(on_progress.clone()));
+                results.lock().unwrap().push((i, scanned));
+            });
+        }
+    });
+
+    // Merge in root order so the result doesn't depend on thread timing
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    for (_, scanned) in results {
+        outcome.merge(scanned);
+    }
+    outcome
 }
 
-/// Calculate sizes lazily
-pub fn calculate_sizes(projects: &mut [StaleProject]) {
+/// Calculate sizes (and file counts) lazily.
+/// With `dedup_hardlinks`, files hard-linked across dep dirs (pnpm store) are counted once.
+/// With `probe`, the files walked and the time spent are added to it.
+/// With `cap` (`--max-size`), a project stops being measured once its total
+/// passes the cap: its sizes are then only a lower bound, enough to set it
+/// aside. Ignored with `dedup_hardlinks`, which needs every file seen.
+/// Symlinked dep dirs count as 0, unless `follow_symlink_deletes` will remove
+/// their target too (`--follow-symlink-deletes`): then the target is sized.
+pub fn calculate_sizes(
+    projects: &mut [StaleProject],
+    dedup_hardlinks: bool,
+    follow_symlink_deletes: bool,
+    jobs: &WalkJobs,
+    probe: Option<&ScanCounters>,
+    cap: Option<u64>,
+) {
+    let start = Instant::now();
+    let seen: Option<SeenInodes> = dedup_hardlinks.then(SeenInodes::default);
+    let cap = cap.filter(|_| !dedup_hardlinks);
     for project in projects.iter_mut() {
-        for dep in &mut project.dep_dirs {
-            dep.size = dir_size(&dep.path);
+        let mut total = 0u64;
+        for dep in project.dep_dirs_mut() {
+            // Removing a symlink frees nothing; its target isn't ours to count
+            // unless it is deleted along with the link
+            let measured: Option<Cow<Path>> = if !dep.path.is_symlink() {
+                Some(Cow::Borrowed(&dep.path))
+            } else if follow_symlink_deletes {
+                std::fs::canonicalize(&dep.path).ok().map(Cow::Owned)
+            } else {
+                None
+            };
+            let usage = match (measured, cap) {
+                (None, _) => DirUsage::default(),
+                (Some(_), Some(cap)) if total > cap => DirUsage::default(),
+                (Some(path), Some(cap)) => dir_usage_capped(&path, cap - total, jobs),
+                (Some(path), None) => dir_usage(&path, seen.as_ref(), jobs),
+            };
+            total += usage.bytes;
+            dep.size = usage.bytes;
+            dep.files = usage.files;
+            if let Some(counters) = probe {
+                counters.sized_files.fetch_add(usage.files, Ordering::Relaxed);
+            }
+        }
+    }
+    if let Some(counters) = probe {
+        ScanCounters::add_time(&counters.sizing_nanos, start);
+    }
+    sort_by_size(projects);
+}
+
+/// Largest first. Ties are broken by path, then by oldest `last_modified`,
+/// so equal-size projects always come out in the same order.
+pub fn sort_by_size(projects: &mut [StaleProject]) {
+    projects.sort_by(|a, b| {
+        b.total_size().cmp(&a.total_size())
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.last_modified.cmp(&b.last_modified))
+    });
+}
+
+/// Removes from `projects` the `n` most recently modified projects of each kind
+/// (`--keep-recent`) and returns them. A project with several kinds counts
+/// toward each of them, and is kept whole if it ranks in any.
+pub fn split_keep_recent(projects: &mut Vec<StaleProject>, n: usize) -> Vec<StaleProject> {
+    let mut by_kind: HashMap<DepKind, Vec<usize>> = HashMap::new();
+    for (i, project) in projects.iter().enumerate() {
+        for dep in project.dep_dirs() {
+            let entry = by_kind.entry(dep.kind.clone()).or_default();
+            if !entry.contains(&i) {
+                entry.push(i);
+            }
+        }
+    }
+
+    let mut keep = vec![false; projects.len()];
+    for indices in by_kind.values_mut() {
+        indices.sort_by_key(|&i| std::cmp::Reverse(projects[i].last_modified));
+        for &i in indices.iter().take(n) {
+            keep[i] = true;
         }
     }
-    projects.sort_by(|a, b| b.total_size().cmp(&a.total_size()));
+
+    let (kept, rest): (Vec<_>, Vec<_>) = std::mem::take(projects)
+        .into_iter()
+        .zip(keep)
+        .partition(|(_, k)| *k);
+    *projects = rest.into_iter().map(|(p, _)| p).collect();
+    kept.into_iter().map(|(p, _)| p).collect()
 }
 
 #[cfg(test)]
@@ -310,6 +1290,15 @@ mod tests {
         dir
     }
 
+    fn opts(days: u64) -> ScanOptions {
+        ScanOptions { days, ..Default::default() }
+    }
+
+    fn set_age(path: &Path, days: u64) {
+        let past = SystemTime::now() - Duration::from_secs(days * 24 * 3600 + 3600); // +1h margin
+        set_file_mtime(path, FileTime::from_system_time(past)).unwrap();
+    }
+
     #[test]
     fn test_nested_bidirectional_protection() {
         let root = make_temp_dir();
@@ -326,19 +1315,11 @@ mod tests {
         fs::create_dir_all(child.join("node_modules")).unwrap();
         fs::write(child.join("package.json"), "{}").unwrap();
         
-        // Helper to set time
-        let set_age = |path: &Path, days: u64| {
-            let now = SystemTime::now();
-            let past = now - Duration::from_secs(days * 24 * 3600 + 3600); // +1h margin
-            let ft = FileTime::from_system_time(past);
-            set_file_mtime(path, ft).unwrap();
-        };
-
         // Case 1: Parent Active, Child Stale -> Child Protected
         set_age(&parent.join("package.json"), 1); // 1 day old (Active)
         set_age(&child.join("package.json"), 60); // 60 days old (Stale)
         
-        let projects = scan_projects(&root, 30, &[], None::<fn()>);
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
         // Expect: ZERO projects because parent is active (not stale) and child is protected by parent.
         assert_eq!(projects.len(), 0, "Child should be protected by active parent");
 
@@ -346,7 +1327,7 @@ mod tests {
         set_age(&parent.join("package.json"), 60); // Stale
         set_age(&child.join("package.json"), 1);   // Active
         
-        let projects = scan_projects(&root, 30, &[], None::<fn()>);
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
         // Expect: ZERO projects because child is active (not stale) and parent is protected by child.
         assert_eq!(projects.len(), 0, "Parent should be protected by active child");
 
@@ -354,12 +1335,19 @@ mod tests {
         set_age(&parent.join("package.json"), 60);
         set_age(&child.join("package.json"), 60);
         
-        let projects = scan_projects(&root, 30, &[], None::<fn()>);
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
         assert_eq!(projects.len(), 2, "Both should be stale");
 
         fs::remove_dir_all(root).unwrap();
     }
 
+    #[test]
+    fn test_lexical_absolute() {
+        assert_eq!(lexical_absolute(Path::new("/a/b/../c/./d")), PathBuf::from("/a/c/d"));
+        assert_eq!(lexical_absolute(Path::new("/usr/x/..")), PathBuf::from("/usr"));
+        assert!(!is_safe_to_scan(Path::new("/tmp/../usr")));
+    }
+
     #[test]
     fn test_is_safe_to_scan() {
         assert!(!is_safe_to_scan(Path::new("/")));
@@ -391,7 +1379,7 @@ mod tests {
         // "node_modules" is a file, not a directory. Should be ignored as a dependency.
         fs::write(proj.join("node_modules"), "not a dir").unwrap();
 
-        let projects = scan_projects(&root, 0, &[], None::<fn()>);
+        let projects = scan_projects(&root, &opts(0), None::<fn()>).stale;
         assert_eq!(projects.len(), 0, "Project with file as dependency folder should be ignored");
         
         fs::remove_dir_all(&root).unwrap();
@@ -406,10 +1394,954 @@ mod tests {
         
         // No dependency folder created
         
-        let projects = scan_projects(&root, 0, &[], None::<fn()>);
+        let projects = scan_projects(&root, &opts(0), None::<fn()>).stale;
         assert_eq!(projects.len(), 0, "Project with no dependency folders should be ignored");
 
         fs::remove_dir_all(&root).unwrap();
     }
-}
 
+    #[test]
+    fn test_keep_rule_scoped_to_prefix() {
+        let root = make_temp_dir();
+        let work = root.join("active-rust-work");
+
+        for proj in [work.join("kept"), root.join("other")] {
+            fs::create_dir_all(proj.join("target")).unwrap();
+            fs::write(proj.join("Cargo.toml"), "[package]").unwrap();
+            set_age(&proj.join("Cargo.toml"), 60);
+        }
+
+        let mut options = opts(30);
+        options.keep_rules.push(format!("target@{}", work.display()).parse().unwrap());
+
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 1, "Only the project outside the prefix should be stale");
+        assert_eq!(projects[0].name, "other");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_keep_rule_parse() {
+        let rule: KeepRule = "node_modules@/tmp/x".parse().unwrap();
+        assert_eq!(rule.kind, DepKind::NodeModules);
+        assert_eq!(rule.prefix, PathBuf::from("/tmp/x"));
+        assert!("node_modules".parse::<KeepRule>().is_err());
+        assert!("bogus@/tmp".parse::<KeepRule>().is_err());
+    }
+
+    #[test]
+    fn test_split_keep_recent_per_kind() {
+        let project = |name: &str, days: u64, kinds: &[DepKind]| StaleProject::new(
+            name.to_string(),
+            PathBuf::from(format!("/p/{}", name)),
+            kinds.iter().map(|k| DepDir {
+                path: PathBuf::from(format!("/p/{}/{}", name, k)),
+                size: 0,
+                files: 0,
+                kind: k.clone(),
+            }).collect(),
+            SystemTime::now() - Duration::from_secs(days * 24 * 3600),
+        );
+
+        let mut projects = vec![
+            project("node-old", 90, &[DepKind::NodeModules]),
+            project("node-new", 40, &[DepKind::NodeModules]),
+            project("node-mid", 60, &[DepKind::NodeModules]),
+            project("rust-old", 80, &[DepKind::Target]),
+            // Conta para os dois tipos: é o mais recente de ambos
+            project("full", 35, &[DepKind::NodeModules, DepKind::Target]),
+            project("rust-new", 50, &[DepKind::Target]),
+        ];
+
+        let kept = split_keep_recent(&mut projects, 2);
+        let mut kept_names: Vec<&str> = kept.iter().map(|p| p.name.as_str()).collect();
+        kept_names.sort();
+        assert_eq!(kept_names, vec!["full", "node-new", "rust-new"]);
+
+        let rest: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(rest, vec!["node-old", "node-mid", "rust-old"]);
+    }
+
+    #[test]
+    fn test_min_age_protects_fresh_dep_dir() {
+        let root = make_temp_dir();
+        let proj = root.join("just-cloned");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        // Source is old (committed mtimes), but node_modules was just installed
+        set_age(&proj.join("package.json"), 60);
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 1, "Without --min-age the project is stale");
+
+        let mut options = opts(30);
+        options.min_age_days = Some(1);
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 0, "Fresh dep dir should be protected by --min-age");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_newer_than_deps_protects() {
+        let root = make_temp_dir();
+        // Cargo.toml edited (40 days ago) after the last build (50 days ago)
+        let edited = root.join("edited");
+        fs::create_dir_all(edited.join("target")).unwrap();
+        fs::write(edited.join("Cargo.toml"), "").unwrap();
+        set_age(&edited.join("Cargo.toml"), 40);
+        set_age(&edited.join("target"), 50);
+        // Built after the last manifest change: deps are up to date
+        let built = root.join("built");
+        fs::create_dir_all(built.join("node_modules")).unwrap();
+        fs::write(built.join("package.json"), "{}").unwrap();
+        set_age(&built.join("package.json"), 50);
+        set_age(&built.join("node_modules"), 40);
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 2, "Opt-in: without the flag both are stale");
+
+        let mut options = opts(30);
+        options.protect_manifest_newer = true;
+        options.collect_protected = true;
+        let outcome = scan_projects(&root, &options, None::<fn()>);
+        let stale: Vec<&str> = outcome.stale.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(stale, vec!["built"]);
+        assert_eq!(outcome.protected.len(), 1);
+        assert_eq!(outcome.protected[0].name, "edited");
+        assert_eq!(outcome.protected[0].is_protected_reason(), Some(ProtectionReason::ManifestNewer));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_protect_active_within_decoupled_from_threshold() {
+        let root = make_temp_dir();
+        let mono = root.join("mono");
+        let pkg = mono.join("packages/api");
+        fs::create_dir_all(mono.join("node_modules")).unwrap();
+        fs::create_dir_all(pkg.join("node_modules")).unwrap();
+        fs::write(mono.join("package.json"), "{}").unwrap();
+        fs::write(pkg.join("package.json"), "{}").unwrap();
+        set_age(&mono.join("package.json"), 60);
+        set_age(&pkg.join("package.json"), 10);
+        set_age(&mono.join("packages"), 60);
+        set_age(&pkg, 60);
+
+        // Clean threshold of 7 days: both are stale, nothing is active
+        let projects = scan_projects(&root, &opts(7), None::<fn()>).stale;
+        assert_eq!(projects.len(), 2);
+
+        // Within a 30-day active window the package is an active root, which
+        // protects the monorepo; the monorepo (whose mtime includes the package)
+        // is active too, protecting the package. Both are still "stale" at 7 days.
+        let mut options = opts(7);
+        options.protect_active_within = Some(30);
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_timeout_returns_partial_results() {
+        let root = make_temp_dir();
+        let proj = root.join("deep/nested/app");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        set_age(&proj.join("package.json"), 60);
+
+        let mut options = opts(30);
+        options.scan_timeout = Some(Duration::from_secs(60));
+        assert_eq!(scan_projects(&root, &options, None::<fn()>).stale.len(), 1);
+
+        // Deadline already passed: the walk stops before reaching the project
+        options.scan_timeout = Some(Duration::ZERO);
+        assert!(scan_projects(&root, &options, None::<fn()>).stale.is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_timeout_keeps_projects_found_before_it() {
+        let root = make_temp_dir();
+        fs::create_dir(root.join("node_modules")).unwrap();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        set_age(&root.join("package.json"), 60);
+        // Far enough down that the deadline passes on the way there
+        let mut deep = root.join("deep");
+        for level in 0..30 {
+            deep = deep.join(format!("d{}", level));
+        }
+        fs::create_dir_all(deep.join("node_modules")).unwrap();
+        fs::write(deep.join("package.json"), "{}").unwrap();
+        set_age(&deep.join("package.json"), 60);
+
+        let mut options = opts(30);
+        options.jobs = WalkJobs::Serial;
+        options.scan_timeout = Some(Duration::from_millis(100));
+        let slow = || std::thread::sleep(Duration::from_millis(10));
+        let stale = scan_projects(&root, &options, Some(slow)).stale;
+        let paths: Vec<&Path> = stale.iter().map(|p| p.path.as_path()).collect();
+        assert_eq!(paths, vec![root.as_path()]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_protect_dirty_repo() {
+        let git_available = std::process::Command::new("git").arg("--version").output().is_ok();
+        if !git_available {
+            return;
+        }
+        let git = |dir: &Path, args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C").arg(dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        let root = make_temp_dir();
+        for name in ["clean", "dirty"] {
+            let repo = root.join(name);
+            fs::create_dir_all(repo.join("node_modules")).unwrap();
+            fs::write(repo.join("package.json"), "{}").unwrap();
+            fs::write(repo.join(".gitignore"), "node_modules\n").unwrap();
+            git(&repo, &["init", "-q"]);
+            git(&repo, &["add", "."]);
+            git(&repo, &["commit", "-q", "-m", "init"]);
+        }
+        fs::write(root.join("dirty/wip.txt"), "todo").unwrap();
+        // A `.git` that git can't read: `git status` fails
+        let broken = root.join("broken");
+        fs::create_dir_all(broken.join("node_modules")).unwrap();
+        fs::write(broken.join("package.json"), "{}").unwrap();
+        fs::write(broken.join(".git"), "gitdir: /nonexistent/faxina-test\n").unwrap();
+        for name in ["clean/package.json", "clean/.gitignore", "dirty/package.json", "dirty/.gitignore", "dirty/wip.txt", "broken/package.json", "broken/.git"] {
+            set_age(&root.join(name), 60);
+        }
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 3, "Without --protect-dirty all are stale");
+
+        let mut options = opts(30);
+        options.protect_dirty = true;
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 1, "Dirty and broken repos are both protected");
+        assert_eq!(projects[0].name, "clean");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_skip_submodules_protects_submodule_deps() {
+        let git_available = std::process::Command::new("git").arg("--version").output().is_ok();
+        if !git_available {
+            return;
+        }
+        let git = |dir: &Path, args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C").arg(dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "protocol.file.allow=always"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        let root = make_temp_dir();
+        let origin = root.join("origin");
+        let superproject = root.join("app");
+        fs::create_dir_all(&origin).unwrap();
+        fs::write(origin.join("README.md"), "lib").unwrap();
+        git(&origin, &["init", "-q"]);
+        git(&origin, &["add", "."]);
+        git(&origin, &["commit", "-q", "-m", "init"]);
+
+        fs::create_dir_all(superproject.join("node_modules")).unwrap();
+        fs::write(superproject.join("package.json"), "{}").unwrap();
+        git(&superproject, &["init", "-q"]);
+        git(&superproject, &["submodule", "add", "-q", origin.to_str().unwrap(), "lib"]);
+
+        let lib = superproject.join("lib");
+        fs::create_dir_all(lib.join("node_modules")).unwrap();
+        fs::write(lib.join("package.json"), "{}").unwrap();
+        for file in ["package.json", ".gitmodules", "lib/package.json", "lib/README.md", "lib/.git"] {
+            set_age(&superproject.join(file), 60);
+        }
+        assert!(in_submodule(&lib));
+        assert!(!in_submodule(&superproject));
+        // A worktree of a repo that lives under `.../modules/...` is not a submodule
+        let worktree = root.join("wt");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join(".git"), "gitdir: /home/me/modules/repo/.git/worktrees/wt\n").unwrap();
+        assert!(!in_submodule(&worktree));
+
+        let projects = scan_projects(&superproject, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 2, "Without --skip-submodules both are stale");
+
+        let mut options = opts(30);
+        options.skip_submodules = true;
+        options.collect_protected = true;
+        let outcome = scan_projects(&superproject, &options, None::<fn()>);
+        assert_eq!(outcome.stale.len(), 1);
+        assert_eq!(outcome.stale[0].path, superproject);
+        assert_eq!(outcome.protected.len(), 1);
+        assert_eq!(outcome.protected[0].is_protected_reason(), Some(ProtectionReason::Submodule));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_protected_reason_tallies() {
+        let root = make_temp_dir();
+        let node = |dir: &Path, age: u64| {
+            fs::create_dir_all(dir.join("node_modules")).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+            set_age(&dir.join("package.json"), age);
+        };
+        // Active monorepo protecting its stale package
+        node(&root.join("mono"), 60);
+        node(&root.join("mono/packages/ui"), 60);
+        fs::write(root.join("mono/README.md"), "wip").unwrap();
+        // Plain active project, stale project, and a kept one
+        node(&root.join("fresh"), 1);
+        node(&root.join("old"), 60);
+        node(&root.join("kept/app"), 60);
+
+        let mut options = opts(30);
+        options.collect_protected = true;
+        options.keep_rules = vec![format!("node_modules@{}", root.join("kept").display()).parse().unwrap()];
+        let outcome = scan_projects(&root, &options, None::<fn()>);
+
+        let stale: Vec<&str> = outcome.stale.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(stale, ["old"]);
+
+        let mut tally: HashMap<ProtectionReason, usize> = HashMap::new();
+        for p in &outcome.protected {
+            *tally.entry(p.is_protected_reason().unwrap()).or_default() += 1;
+        }
+        assert_eq!(tally.get(&ProtectionReason::ActiveSelf), Some(&2), "mono and fresh");
+        assert_eq!(tally.get(&ProtectionReason::ActiveParent), Some(&1), "ui inside mono");
+        assert_eq!(tally.get(&ProtectionReason::KeepRule), Some(&1));
+        assert!(outcome.stale.iter().all(|p| p.is_protected_reason().is_none()));
+
+        // Without the flag nothing is collected
+        options.collect_protected = false;
+        assert!(scan_projects(&root, &options, None::<fn()>).protected.is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_target_with_cargo_and_pom_detected_once() {
+        let root = make_temp_dir();
+        let mixed = root.join("mixed");
+        fs::create_dir_all(mixed.join("target")).unwrap();
+        fs::write(mixed.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(mixed.join("pom.xml"), "<project/>").unwrap();
+        let maven = root.join("maven");
+        fs::create_dir_all(maven.join("target")).unwrap();
+        fs::write(maven.join("pom.xml"), "<project/>").unwrap();
+        for f in ["mixed/Cargo.toml", "mixed/pom.xml", "maven/pom.xml"] {
+            set_age(&root.join(f), 60);
+        }
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 2);
+        let mixed_proj = projects.iter().find(|p| p.name == "mixed").unwrap();
+        assert_eq!(mixed_proj.dep_dirs().len(), 1, "One dir, one DepDir");
+        assert_eq!(mixed_proj.dep_dirs()[0].kind, DepKind::Target, "Rust is registered before Maven");
+        let maven_proj = projects.iter().find(|p| p.name == "maven").unwrap();
+        assert_eq!(maven_proj.dep_dirs()[0].kind, DepKind::MavenTarget);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_serial_walk_matches_default() {
+        let root = make_temp_dir();
+        for i in 0..6 {
+            let proj = root.join(format!("group{}/proj{}", i % 2, i));
+            fs::create_dir_all(proj.join("node_modules/pkg")).unwrap();
+            fs::write(proj.join("package.json"), "{}").unwrap();
+            fs::write(proj.join("node_modules/pkg/index.js"), vec![b'x'; 100 * (i + 1)]).unwrap();
+            set_age(&proj.join("package.json"), 60);
+        }
+
+        let run = |jobs: WalkJobs| {
+            let mut options = opts(30);
+            options.jobs = jobs.clone();
+            let mut projects = scan_projects(&root, &options, None::<fn()>).stale;
+            calculate_sizes(&mut projects, false, false, &jobs, None, None);
+            projects.iter().map(|p| (p.path.clone(), p.total_size())).collect::<Vec<_>>()
+        };
+
+        let default = run(WalkJobs::Default);
+        assert_eq!(default.len(), 6);
+        assert_eq!(run(WalkJobs::new(Some(1))), default);
+        assert_eq!(run(WalkJobs::new(Some(2))), default);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_mtime_ignore_skips_transient_files() {
+        let root = make_temp_dir();
+        let proj = root.join("noisy");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        set_age(&proj.join("package.json"), 60);
+        // Freshly written log file keeps the project "active"
+        fs::write(proj.join("debug.log"), "boom").unwrap();
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 0, "Fresh log file counts without ignore patterns");
+
+        let mut options = opts(30);
+        options.mtime_ignore = vec![glob::Pattern::new("*.log").unwrap()];
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 1, "Ignored log file should not mark the project active");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn walk_settings(include_dir_mtime: bool, track_newest: bool) -> MtimeWalk<'static> {
+        MtimeWalk { ignore: &[], deadline: None, jobs: &WalkJobs::Serial, include_dir_mtime, track_newest }
+    }
+
+    #[test]
+    fn test_mtime_walk_stops_at_first_active_file() {
+        let root = make_temp_dir();
+        let proj = root.join("busy");
+        fs::create_dir_all(proj.join("src/deep")).unwrap();
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        let fresh = fs::metadata(proj.join("package.json")).unwrap().modified().unwrap();
+        // A "future" file in a subdir: seen only if the walk goes past the root
+        let future = SystemTime::now() + Duration::from_secs(10 * 24 * 3600);
+        fs::write(proj.join("src/deep/later.rs"), "").unwrap();
+        set_file_mtime(proj.join("src/deep/later.rs"), FileTime::from_system_time(future)).unwrap();
+
+        let cutoff = SystemTime::now() - Duration::from_secs(30 * 24 * 3600);
+        let full = latest_source_mtime(&proj, &walk_settings(false, false), None, &[]).unwrap().time;
+        assert!(full >= future - Duration::from_secs(1), "Full walk sees the nested file");
+
+        let short = latest_source_mtime(&proj, &walk_settings(false, false), Some(cutoff), &[]).unwrap().time;
+        assert_eq!(short, fresh, "Walk should stop at the fresh root file");
+        assert!(short >= cutoff);
+
+        // Classification is unchanged
+        let mut options = opts(30);
+        options.collect_protected = true;
+        let outcome = scan_projects(&root, &options, None::<fn()>);
+        assert!(outcome.stale.is_empty());
+        assert_eq!(outcome.protected.len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_roots_match_serial_scan() {
+        let root = make_temp_dir();
+        let roots: Vec<PathBuf> = ["ws-a", "ws-b", "ws-c"].iter().map(|r| root.join(r)).collect();
+        for (i, ws) in roots.iter().enumerate() {
+            for name in ["api", "web"] {
+                let proj = ws.join(format!("{}-{}", name, i));
+                fs::create_dir_all(proj.join("node_modules")).unwrap();
+                fs::write(proj.join("package.json"), "{}").unwrap();
+                set_age(&proj.join("package.json"), 60);
+            }
+        }
+
+        let paths = |outcome: ScanOutcome| outcome.stale.into_iter().map(|p| p.path).collect::<Vec<_>>();
+        let serial = paths(scan_roots(&roots, &opts(30), || {}, 1));
+        let parallel = paths(scan_roots(&roots, &opts(30), || {}, 2));
+        assert_eq!(serial.len(), 6);
+        assert_eq!(parallel, serial);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_latest_source_mtime_reports_newest_file() {
+        let root = make_temp_dir();
+        let proj = root.join("app");
+        fs::create_dir_all(proj.join("src/deep")).unwrap();
+        for (file, days) in [("package.json", 90), ("src/index.js", 60), ("src/deep/util.js", 45)] {
+            fs::write(proj.join(file), "").unwrap();
+            set_age(&proj.join(file), days);
+        }
+
+        let lean = latest_source_mtime(&proj, &walk_settings(false, false), None, &[]).unwrap();
+        assert_eq!(lean.newest, None);
+
+        let tracked = latest_source_mtime(&proj, &walk_settings(false, true), None, &[]).unwrap();
+        assert_eq!(tracked.newest, Some(proj.join("src/deep/util.js")));
+        assert_eq!(tracked.time, lean.time);
+
+        // The project dir's own mtime wins when it's newer
+        let dir_wins = latest_source_mtime(&proj, &walk_settings(true, true), None, &[]).unwrap();
+        assert_eq!(dir_wins.newest, Some(proj.clone()));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_probe_counts_each_phase() {
+        let root = make_temp_dir();
+        let proj = root.join("app");
+        fs::create_dir_all(proj.join("src")).unwrap();
+        fs::create_dir_all(proj.join("node_modules/pkg")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        fs::write(proj.join("src/index.js"), "").unwrap();
+        fs::write(proj.join("node_modules/pkg/index.js"), "x").unwrap();
+        set_age(&proj.join("package.json"), 60);
+        set_age(&proj.join("src/index.js"), 60);
+
+        let counters = Arc::new(ScanCounters::default());
+        let mut options = opts(30);
+        options.probe = Some(counters.clone());
+        let mut projects = scan_projects(&root, &options, None::<fn()>).stale;
+        calculate_sizes(&mut projects, false, false, &WalkJobs::Serial, Some(&counters), None);
+
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        // root, app and src; node_modules is found but never descended
+        assert_eq!(load(&counters.dirs_visited), 3);
+        assert_eq!(load(&counters.files_seen), 2);
+        assert_eq!(load(&counters.dep_dirs_found), 1);
+        assert_eq!(load(&counters.mtime_files), 2);
+        assert_eq!(load(&counters.sized_files), 1);
+        assert!(counters.discovery_time() > Duration::ZERO);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_only_kinds_same_results_same_pruning() {
+        let root = make_temp_dir();
+        for (proj, manifest, dep) in [
+            ("web", "package.json", "node_modules"),
+            ("api", "package.json", "node_modules"),
+            ("cli", "Cargo.toml", "target"),
+            ("ml", "requirements.txt", "venv"),
+        ] {
+            let dir = root.join(proj);
+            fs::create_dir_all(dir.join(dep)).unwrap();
+            for sub in ["src", "docs", "assets", "tests"] {
+                fs::create_dir_all(dir.join(sub)).unwrap();
+            }
+            fs::write(dir.join(manifest), "").unwrap();
+            set_age(&dir.join(manifest), 60);
+        }
+        fs::write(root.join("ml/venv/pyvenv.cfg"), "").unwrap();
+        // Build output of an unselected kind that looks like a Node project
+        let packaged = root.join("cli/target/package/web-assets");
+        fs::create_dir_all(packaged.join("node_modules")).unwrap();
+        fs::write(packaged.join("package.json"), "").unwrap();
+        set_age(&packaged.join("package.json"), 60);
+
+        let scan = |only: Vec<DepKind>| {
+            let counters = Arc::new(ScanCounters::default());
+            let mut options = opts(30);
+            options.only_kinds = only;
+            options.probe = Some(counters.clone());
+            let mut dirs: Vec<PathBuf> = scan_projects(&root, &options, None::<fn()>).stale
+                .iter()
+                .flat_map(|p| p.dep_dirs().iter().filter(|d| d.kind == DepKind::NodeModules).map(|d| d.path.clone()))
+                .collect();
+            dirs.sort();
+            (dirs, counters.dirs_visited.load(Ordering::Relaxed) + counters.files_seen.load(Ordering::Relaxed))
+        };
+
+        let (all, all_entries) = scan(Vec::new());
+        let (only, only_entries) = scan(vec![DepKind::NodeModules]);
+        assert_eq!(only, all);
+        assert_eq!(only.len(), 2);
+        // `target/` and `venv/` are still pruned, not walked
+        assert_eq!(only_entries, all_entries);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_no_descend_into_stale_reuses_parent_walk() {
+        let root = make_temp_dir();
+        let project = |dir: &Path, days: u64| {
+            fs::create_dir_all(dir.join("node_modules")).unwrap();
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+            fs::write(dir.join("src/index.js"), "").unwrap();
+            set_age(&dir.join("package.json"), days + 10);
+            set_age(&dir.join("src/index.js"), days);
+        };
+        // Stale monorepo: its walk covers both packages
+        let mono = root.join("mono");
+        project(&mono, 60);
+        project(&mono.join("packages/a"), 45);
+        project(&mono.join("packages/b"), 70);
+        // Active parent: its walk stops early, so the nested one walks itself
+        let live = root.join("live");
+        project(&live.join("pkg/c"), 60);
+        fs::create_dir_all(live.join("node_modules")).unwrap();
+        fs::write(live.join("package.json"), "{}").unwrap();
+
+        let run = |optimized: bool| {
+            let counters = Arc::new(ScanCounters::default());
+            let mut options = opts(30);
+            options.collect_protected = true;
+            options.show_newest = true;
+            options.probe = Some(counters.clone());
+            options.no_descend_into_stale = optimized;
+            let outcome = scan_projects(&root, &options, None::<fn()>);
+            let summary = |list: &[StaleProject]| {
+                list.iter()
+                    .map(|p| (p.path.clone(), p.last_modified, p.newest_file().map(Path::to_path_buf)))
+                    .collect::<Vec<_>>()
+            };
+            let result = (summary(&outcome.stale), summary(&outcome.protected));
+            (result, counters.mtime_dirs.load(Ordering::Relaxed))
+        };
+
+        let (naive, naive_dirs) = run(false);
+        let (optimized, optimized_dirs) = run(true);
+        assert_eq!(naive.0.len(), 3, "mono and both packages are stale");
+        assert_eq!(optimized, naive);
+        // a, a/src, b and b/src are read once (by the mono walk) instead of twice
+        assert_eq!(naive_dirs - optimized_dirs, 4, "{} vs {}", naive_dirs, optimized_dirs);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_project_config_overrides() {
+        let root = make_temp_dir();
+        let configs = [("locked", "protected = true"), ("typo", "protect = true"), ("strict", "days = 7"), ("plain", "")];
+        for (name, local) in configs {
+            let proj = root.join(name);
+            fs::create_dir_all(proj.join("node_modules")).unwrap();
+            fs::write(proj.join("package.json"), "{}").unwrap();
+            set_age(&proj.join("package.json"), 20);
+            if !local.is_empty() {
+                fs::write(proj.join(config::PROJECT_CONFIG_FILE), local).unwrap();
+                set_age(&proj.join(config::PROJECT_CONFIG_FILE), 20);
+            }
+        }
+
+        // Stale at any threshold, but never flagged
+        let mut options = opts(1);
+        options.collect_protected = true;
+        let outcome = scan_projects(&root, &options, None::<fn()>);
+        let names: Vec<&str> = outcome.stale.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["plain", "strict"]);
+        // An unknown key is a broken file, which protects too
+        let protected: Vec<&str> = outcome.protected.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(protected, vec!["locked", "typo"]);
+        assert!(outcome.protected.iter().all(|p| p.is_protected_reason() == Some(ProtectionReason::ProjectConfig)));
+
+        // A 7-day project threshold beats the global 30 days
+        let names: Vec<String> = scan_projects(&root, &opts(30), None::<fn()>).stale.into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["strict"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_project_config_days_decides_protection_of_neighbours() {
+        let root = make_temp_dir();
+        let parent = root.join("mono");
+        let child = parent.join("packages/slow");
+        for dir in [&parent, &child] {
+            fs::create_dir_all(dir.join("node_modules")).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+            set_age(&dir.join("package.json"), 60);
+        }
+        fs::write(child.join(config::PROJECT_CONFIG_FILE), "days = 90").unwrap();
+        set_age(&child.join(config::PROJECT_CONFIG_FILE), 60);
+
+        // The package is active by its own 90 days, so it protects the monorepo
+        let mut options = opts(30);
+        options.collect_protected = true;
+        let outcome = scan_projects(&root, &options, None::<fn()>);
+        assert!(outcome.stale.is_empty());
+        let reasons: Vec<_> = outcome.protected.iter().map(|p| (p.name.as_str(), p.is_protected_reason())).collect();
+        assert!(reasons.contains(&("mono", Some(ProtectionReason::ActiveChild))), "{:?}", reasons);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_progress_callback_counts_every_visited_entry() {
+        let root = make_temp_dir();
+        for i in 0..7 {
+            let proj = root.join(format!("p{}", i));
+            fs::create_dir_all(proj.join("src")).unwrap();
+            fs::write(proj.join("package.json"), "{}").unwrap();
+            fs::write(proj.join("src/index.js"), "").unwrap();
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = calls.clone();
+        let counters = Arc::new(ScanCounters::default());
+        let mut options = opts(30);
+        options.probe = Some(counters.clone());
+        scan_projects(&root, &options, Some(move || {
+            calls_clone.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        // root + 7 projects + 7 src dirs, and 14 files: none lost between updates
+        let visited = counters.dirs_visited.load(Ordering::Relaxed) + counters.files_seen.load(Ordering::Relaxed);
+        assert_eq!(visited, 29);
+        assert_eq!(calls.load(Ordering::Relaxed) as u64, visited);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_safe_list_protects_stale_dep_dir() {
+        let root = make_temp_dir();
+        for name in ["keep-me", "clean-me"] {
+            let proj = root.join(name);
+            fs::create_dir_all(proj.join("node_modules")).unwrap();
+            fs::write(proj.join("package.json"), "{}").unwrap();
+            set_age(&proj.join("package.json"), 60);
+        }
+
+        let mut options = opts(30);
+        options.collect_protected = true;
+        options.safe_list = vec![root.join("keep-me/node_modules")];
+        let outcome = scan_projects(&root, &options, None::<fn()>);
+
+        assert_eq!(outcome.stale.len(), 1);
+        assert_eq!(outcome.stale[0].name, "clean-me");
+        assert_eq!(outcome.safe_listed, vec![root.join("keep-me/node_modules").canonicalize().unwrap()]);
+        assert_eq!(outcome.protected[0].is_protected_reason(), Some(ProtectionReason::SafeList));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_ignore_case_matches_mismatched_exclusion() {
+        let root = make_temp_dir();
+        let proj = root.join("Client-App");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        set_age(&proj.join("package.json"), 60);
+
+        let mut options = opts(30);
+        options.ignored_paths = vec![root.join("client-app")];
+        // Case-sensitive by default (only meaningful where the fs is too)
+        if cfg!(target_os = "linux") {
+            assert_eq!(scan_projects(&root, &options, None::<fn()>).stale.len(), 1);
+        }
+
+        options.ignore_case = true;
+        assert!(scan_projects(&root, &options, None::<fn()>).stale.is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_dir_is_reported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = make_temp_dir();
+        let locked = root.join("locked");
+        fs::create_dir_all(locked.join("hidden-proj")).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root ignores permissions: nothing to report there
+        if fs::read_dir(&locked).is_ok() {
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            fs::remove_dir_all(&root).unwrap();
+            return;
+        }
+
+        let outcome = scan_projects(&root, &opts(30), None::<fn()>);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(outcome.unreadable.len(), 1);
+        assert_eq!(outcome.unreadable[0].0, locked);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_one_file_system_device_check() {
+        let root = make_temp_dir();
+        let proj = root.join("app");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        set_age(&proj.join("package.json"), 60);
+
+        let root_dev = device_id(&root).unwrap();
+        assert_eq!(device_id(&proj.join("node_modules")), Some(root_dev));
+        // /proc is always its own filesystem on Linux
+        if let Some(proc_dev) = device_id(Path::new("/proc")) {
+            if cfg!(target_os = "linux") {
+                assert_ne!(proc_dev, root_dev);
+            }
+        }
+
+        // Same-device projects are still found
+        let mut options = opts(30);
+        options.one_file_system = true;
+        assert_eq!(scan_projects(&root, &options, None::<fn()>).stale.len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_project_dir_skipped_by_default() {
+        let root = make_temp_dir();
+        let outside = make_temp_dir();
+        let proj = outside.join("mounted-proj");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        set_age(&proj.join("package.json"), 60);
+        let link = root.join("linked");
+        std::os::unix::fs::symlink(&proj, &link).unwrap();
+
+        // Neither descended into from the parent nor accepted as the root itself
+        assert!(scan_projects(&root, &opts(30), None::<fn()>).stale.is_empty());
+        assert!(scan_projects(&link, &opts(30), None::<fn()>).stale.is_empty());
+
+        let mut options = opts(30);
+        options.follow_symlinks = true;
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].path, link);
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_dep_dir_sized_only_when_target_is_deleted() {
+        let root = make_temp_dir();
+        let store = root.join("store");
+        fs::create_dir_all(&store).unwrap();
+        fs::write(store.join("pkg.js"), vec![b'x'; 500]).unwrap();
+        let proj = root.join("app");
+        fs::create_dir_all(&proj).unwrap();
+        let link = proj.join("node_modules");
+        std::os::unix::fs::symlink(&store, &link).unwrap();
+
+        let sized = |follow: bool| {
+            let dep = DepDir { path: link.clone(), size: 0, files: 0, kind: DepKind::NodeModules };
+            let mut projects = vec![StaleProject::new("app".into(), proj.clone(), vec![dep], SystemTime::now())];
+            calculate_sizes(&mut projects, false, follow, &WalkJobs::Serial, None, None);
+            projects[0].total_size()
+        };
+        assert_eq!(sized(false), 0);
+        assert_eq!(sized(true), 500);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_include_dir_mtime_keeps_touched_project_active() {
+        let root = make_temp_dir();
+        let proj = root.join("renamed");
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        set_age(&proj.join("package.json"), 60);
+        // Only the project dir itself is fresh (its entries just changed)
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 1, "Dir mtime is ignored by default");
+
+        let mut options = opts(30);
+        options.include_dir_mtime = true;
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert!(projects.is_empty(), "Fresh project dir should keep it active");
+
+        set_age(&proj, 60);
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_prune_dir_by_own_mtime() {
+        let root = make_temp_dir();
+        let old = root.join("old-stuff/scratch");
+        let fresh = root.join("fresh-stuff/scratch");
+        fs::create_dir_all(&old).unwrap();
+        fs::create_dir_all(&fresh).unwrap();
+        fs::write(old.join("junk.bin"), "x").unwrap();
+        set_age(&old, 60);
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert!(projects.is_empty(), "Without --prune-dir nothing is detected");
+
+        let mut options = opts(30);
+        options.prune_dirs = vec!["scratch".to_string()];
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "old-stuff/scratch");
+        assert_eq!(projects[0].dep_dirs()[0].kind, DepKind::Generic("scratch".to_string()));
+        assert_eq!(projects[0].dep_dirs()[0].path, old);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_protect_active_siblings() {
+        let root = make_temp_dir();
+        let workspace = root.join("workspace");
+        for (name, days) in [("stale-pkg", 60), ("active-pkg", 1)] {
+            let proj = workspace.join(name);
+            fs::create_dir_all(proj.join("node_modules")).unwrap();
+            fs::write(proj.join("package.json"), "{}").unwrap();
+            set_age(&proj.join("package.json"), days);
+        }
+
+        let projects = scan_projects(&root, &opts(30), None::<fn()>).stale;
+        assert_eq!(projects.len(), 1, "By default only the project itself is considered");
+
+        let mut options = opts(30);
+        options.protect_active_siblings = true;
+        let projects = scan_projects(&root, &options, None::<fn()>).stale;
+        assert!(projects.is_empty(), "Active sibling should protect the stale package");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_sort_by_size_is_deterministic() {
+        let project = |path: &str, size: u64| StaleProject::new(
+            path.to_string(),
+            PathBuf::from(path),
+            vec![DepDir { path: PathBuf::from(path).join("target"), size, files: 0, kind: DepKind::Target }],
+            SystemTime::UNIX_EPOCH,
+        );
+        let expected = vec!["/big", "/a", "/b", "/c", "/small"];
+
+        let orders: [&[&str]; 3] = [
+            &["/c", "/small", "/a", "/big", "/b"],
+            &["/b", "/a", "/c", "/small", "/big"],
+            &["/small", "/big", "/c", "/b", "/a"],
+        ];
+        for order in orders {
+            let mut projects: Vec<StaleProject> = order.iter().map(|p| {
+                let size = match *p { "/big" => 300, "/small" => 10, _ => 100 };
+                project(p, size)
+            }).collect();
+            sort_by_size(&mut projects);
+            let paths: Vec<String> = projects.iter().map(|p| p.path.display().to_string()).collect();
+            assert_eq!(paths, expected);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use directories::ProjectDirs;
+
+use crate::history::{decode_path, encode_path};
+use crate::types::{dir_size, WalkJobs};
+
+/// Registro das pastas na área de staging: `slot\tcaminho original` por linha,
+/// os dois codificados por `history::encode_path`.
+const MANIFEST: &str = "manifest.tsv";
+
+/// Área de staging de `--stage`, no diretório de cache do usuário.
+pub fn default_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "faxina-cli").map(|d| d.cache_dir().join("staged"))
+}
+
+/// Pastas atualmente na área: (caminho dentro do staging, caminho original).
+pub fn entries(staging: &Path) -> Vec<(PathBuf, PathBuf)> {
+    fs::read_to_string(staging.join(MANIFEST))
+        .map(|c| {
+            c.lines()
+                .filter_map(|l| l.split_once('\t'))
+                .filter_map(|(slot, original)| Some((staging.join(decode_path(slot)?), decode_path(original)?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn write_manifest(staging: &Path, entries: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    if entries.is_empty() {
+        return match fs::remove_file(staging.join(MANIFEST)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let lines: Vec<String> = entries
+        .iter()
+        .filter_map(|(slot, original)| {
+            let name = encode_path(Path::new(slot.file_name()?))?;
+            Some(format!("{}\t{}\n", name, encode_path(original)?))
+        })
+        .collect();
+    fs::write(staging.join(MANIFEST), lines.concat())
+}
+
+/// Move `path` para a área de staging e registra o caminho original. Recusa
+/// caminhos que o manifesto não guardaria exatamente (ver `encode_path`): a
+/// pasta não teria como voltar ao mesmo lugar.
+pub fn stage(staging: &Path, path: &Path) -> io::Result<PathBuf> {
+    let original = encode_path(path).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "caminho não pode ser registrado sem perdas no staging")
+    })?;
+    fs::create_dir_all(staging)?;
+
+    let base = path.file_name().unwrap_or_default();
+    let mut n = entries(staging).len();
+    let slot = loop {
+        let mut name = std::ffi::OsString::from(format!("{:05}-", n));
+        name.push(base);
+        let candidate = staging.join(name);
+        if !candidate.exists() {
+            break candidate;
+        }
+        n += 1;
+    };
+
+    move_dir(path, &slot)?;
+    let mut f = OpenOptions::new().create(true).append(true).open(staging.join(MANIFEST))?;
+    let name = encode_path(Path::new(slot.file_name().unwrap_or_default())).unwrap_or_default();
+    writeln!(f, "{}\t{}", name, original)?;
+    Ok(slot)
+}
+
+/// Devolve cada pasta ao lugar original. Não sobrescreve: se o original já
+/// existe (ex: `npm install` rodou de novo), a pasta fica no staging.
+/// Retorna quantas voltaram e os erros; as que falharam continuam registradas.
+pub fn restore_all(staging: &Path) -> (usize, Vec<(PathBuf, io::Error)>) {
+    let mut restored = 0;
+    let mut errors = Vec::new();
+    let mut remaining = Vec::new();
+
+    for (slot, original) in entries(staging) {
+        let outcome = if original.exists() {
+            Err(io::Error::new(io::ErrorKind::AlreadyExists, "o caminho original já existe"))
+        } else {
+            move_dir(&slot, &original)
+        };
+        match outcome {
+            Ok(_) => restored += 1,
+            Err(e) => {
+                errors.push((original.clone(), e));
+                remaining.push((slot, original));
+            }
+        }
+    }
+
+    if let Err(e) = write_manifest(staging, &remaining) {
+        errors.push((staging.join(MANIFEST), e));
+    }
+    (restored, errors)
+}
+
+/// Apaga de vez tudo o que está no staging. Retorna (pastas, bytes liberados).
+pub fn empty(staging: &Path) -> io::Result<(usize, u64)> {
+    let mut removed = 0;
+    let mut freed = 0;
+    let mut remaining = Vec::new();
+    let mut first_err = None;
+
+    for (slot, original) in entries(staging) {
+        let size = dir_size(&slot, None, &WalkJobs::Default);
+        match fs::remove_dir_all(&slot) {
+            Ok(_) => {
+                removed += 1;
+                freed += size;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                remaining.push((slot, original));
+                first_err.get_or_insert(e);
+            }
+        }
+    }
+
+    write_manifest(staging, &remaining)?;
+    match first_err {
+        Some(e) => Err(e),
+        None => Ok((removed, freed)),
+    }
+}
+
+/// Move `path` para dentro de `archive` (`--move-to`), recriando lá o caminho
+/// absoluto de origem para pastas de projetos diferentes não colidirem. Se o
+/// destino já existe (ex: uma execução anterior), ganha um sufixo `-1`, `-2`...
+/// Retorna onde a pasta foi parar.
+pub fn move_into(archive: &Path, path: &Path) -> io::Result<PathBuf> {
+    let relative: PathBuf = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_os_string()),
+            // `C:` vira `C`, para o caminho continuar relativo ao arquivo
+            Component::Prefix(prefix) => {
+                Some(prefix.as_os_str().to_string_lossy().replace([':', '\\', '?'], "").into())
+            }
+            _ => None,
+        })
+        .collect();
+    let base = archive.join(relative);
+
+    let mut target = base.clone();
+    let mut n = 1;
+    while target.exists() {
+        let mut name = base.file_name().unwrap_or_default().to_os_string();
+        name.push(format!("-{}", n));
+        target = base.with_file_name(name);
+        n += 1;
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_dir(path, &target)?;
+    Ok(target)
+}
+
+/// `rename`, com cópia + remoção quando origem e destino estão em discos diferentes.
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_tree(from, to)?;
+            fs::remove_dir_all(from)
+        }
+        other => other,
+    }
+}
+
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_tree(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    let target = fs::read_link(from)?;
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str) -> (PathBuf, PathBuf) {
+        let temp = std::env::temp_dir().join(format!("test_staging_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&temp);
+        let dep = temp.join("proj/node_modules");
+        fs::create_dir_all(dep.join("pkg")).unwrap();
+        fs::write(dep.join("pkg/index.js"), "module.exports = 1").unwrap();
+        (temp.clone(), temp.join("staged"))
+    }
+
+    #[test]
+    fn test_stage_and_restore() {
+        let (temp, staging) = setup("restore");
+        let dep = temp.join("proj/node_modules");
+
+        let slot = stage(&staging, &dep).unwrap();
+        assert!(!dep.exists());
+        assert!(slot.join("pkg/index.js").exists());
+        assert_eq!(entries(&staging), vec![(slot.clone(), dep.clone())]);
+
+        let (restored, errors) = restore_all(&staging);
+        assert_eq!(restored, 1);
+        assert!(errors.is_empty());
+        assert_eq!(fs::read_to_string(dep.join("pkg/index.js")).unwrap(), "module.exports = 1");
+        assert!(entries(&staging).is_empty());
+
+        // Original recriado nesse meio tempo: não sobrescreve e mantém no staging
+        stage(&staging, &dep).unwrap();
+        fs::create_dir_all(&dep).unwrap();
+        let (restored, errors) = restore_all(&staging);
+        assert_eq!((restored, errors.len()), (0, 1));
+        assert_eq!(entries(&staging).len(), 1);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stage_and_restore_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let (temp, staging) = setup("bytes");
+        // Nome fora de UTF-8 e nome com tab e quebra de linha: voltam idênticos
+        let latin1 = temp.join(std::ffi::OsStr::from_bytes(b"proj-\xe7\xe3o/node_modules"));
+        let control = temp.join("a\tb\nc/node_modules");
+        for dep in [&latin1, &control] {
+            fs::create_dir_all(dep.join("pkg")).unwrap();
+            fs::write(dep.join("pkg/index.js"), "x").unwrap();
+            stage(&staging, dep).unwrap();
+            assert!(!dep.exists());
+        }
+        let originals: Vec<PathBuf> = entries(&staging).into_iter().map(|(_, original)| original).collect();
+        assert_eq!(originals, vec![latin1.clone(), control.clone()]);
+
+        let (restored, errors) = restore_all(&staging);
+        assert_eq!((restored, errors.len()), (2, 0));
+        assert!(latin1.join("pkg/index.js").exists());
+        assert!(control.join("pkg/index.js").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_empty_deletes_permanently() {
+        let (temp, staging) = setup("empty");
+        let dep = temp.join("proj/node_modules");
+
+        let slot = stage(&staging, &dep).unwrap();
+        let (removed, freed) = empty(&staging).unwrap();
+        assert_eq!(removed, 1);
+        assert!(freed > 0);
+        assert!(!slot.exists());
+        assert!(!dep.exists());
+        assert!(entries(&staging).is_empty());
+        assert_eq!(restore_all(&staging).0, 0);
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_copy_tree_fallback() {
+        let (temp, _) = setup("copy");
+        let dep = temp.join("proj/node_modules");
+        let dest = temp.join("elsewhere/node_modules");
+
+        copy_tree(&dep, &dest).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("pkg/index.js")).unwrap(), "module.exports = 1");
+        assert!(dep.exists(), "copy_tree alone keeps the source");
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
@@ -1,27 +1,117 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::SystemTime;
-use jwalk::WalkDir;
+use jwalk::rayon::{ThreadPool, ThreadPoolBuilder};
+use jwalk::{Parallelism, WalkDir};
 use serde::Serialize;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum DepKind {
     NodeModules,
     Target,
+    MavenTarget,
     NextBuild,
     Venv,
     Vendor,
     Build,
+    IosDeps,
+    ZigCache,
+    /// `build/`, `dist/` e `*.egg-info/` de empacotamento Python
+    PyBuild,
+    /// `.angular/cache` do Angular CLI
+    AngularCache,
+    /// `.nx/cache` de monorepos Nx
+    NxCache,
+    /// Pasta de build do CMake (`build`, `cmake-build-*`), com `CMakeCache.txt` dentro
+    CMakeBuild,
+    /// Saída de build `dist/` de projetos JS (opt-in, `--include-dist`)
+    Dist,
+    /// Pasta sem tipo de projeto, escolhida por nome via `--prune-dir`.
+    Generic(String),
 }
 
 impl DepKind {
+    /// Tipos com identificador fixo (todos menos `Generic`).
+    pub const KNOWN: [DepKind; 14] = [
+        DepKind::NodeModules,
+        DepKind::Target,
+        DepKind::MavenTarget,
+        DepKind::NextBuild,
+        DepKind::Venv,
+        DepKind::Vendor,
+        DepKind::Build,
+        DepKind::IosDeps,
+        DepKind::ZigCache,
+        DepKind::PyBuild,
+        DepKind::AngularCache,
+        DepKind::NxCache,
+        DepKind::CMakeBuild,
+        DepKind::Dist,
+    ];
+
+    /// Identificador estável e único, usado em flags e na configuração.
+    /// Difere do `Display` só onde o rótulo é ambíguo ou tem espaços:
+    /// `target` é sempre o do Rust; o do Maven é `maven-target`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            DepKind::NodeModules => "node_modules",
+            DepKind::Target => "target",
+            DepKind::MavenTarget => "maven-target",
+            DepKind::NextBuild => ".next",
+            DepKind::Venv => "venv",
+            DepKind::Vendor => "vendor",
+            DepKind::Build => "build",
+            DepKind::IosDeps => "ios-deps",
+            DepKind::ZigCache => "zig-cache",
+            DepKind::PyBuild => "py-build",
+            DepKind::AngularCache => "angular-cache",
+            DepKind::NxCache => "nx-cache",
+            DepKind::CMakeBuild => "cmake-build",
+            DepKind::Dist => "dist",
+            DepKind::Generic(name) => name,
+        }
+    }
+
     pub fn icon(&self) -> &'static str {
         match self {
             DepKind::NodeModules => "📦",
             DepKind::Target => "🦀",
+            DepKind::MavenTarget => "🪶",
             DepKind::NextBuild => "▲ ",
             DepKind::Venv => "🐍",
             DepKind::Vendor => "🐹",
             DepKind::Build => "☕",
+            DepKind::IosDeps => "🍏",
+            DepKind::ZigCache => "⚡",
+            DepKind::PyBuild => "🥚",
+            DepKind::AngularCache => "🔺",
+            DepKind::NxCache => "🔷",
+            DepKind::CMakeBuild => "🔧",
+            DepKind::Dist => "📤",
+            DepKind::Generic(_) => "📁",
+        }
+    }
+
+    /// Alternativa ASCII de `icon()` para terminais sem emoji (`--ascii`).
+    pub fn ascii_tag(&self) -> &'static str {
+        match self {
+            DepKind::NodeModules => "[node]",
+            DepKind::Target => "[rust]",
+            DepKind::MavenTarget => "[maven]",
+            DepKind::NextBuild => "[next]",
+            DepKind::Venv => "[venv]",
+            DepKind::Vendor => "[go]",
+            DepKind::Build => "[gradle]",
+            DepKind::IosDeps => "[ios]",
+            DepKind::ZigCache => "[zig]",
+            DepKind::PyBuild => "[pybuild]",
+            DepKind::AngularCache => "[angular]",
+            DepKind::NxCache => "[nx]",
+            DepKind::CMakeBuild => "[cmake]",
+            DepKind::Dist => "[dist]",
+            DepKind::Generic(_) => "[dir]",
         }
     }
 }
@@ -31,47 +121,273 @@ impl std::fmt::Display for DepKind {
         match self {
             DepKind::NodeModules => write!(f, "node_modules"),
             DepKind::Target => write!(f, "target"),
+            DepKind::MavenTarget => write!(f, "target (Maven)"),
             DepKind::NextBuild => write!(f, ".next"),
             DepKind::Venv => write!(f, "venv"),
             DepKind::Vendor => write!(f, "vendor"),
             DepKind::Build => write!(f, "build"),
+            DepKind::IosDeps => write!(f, "Pods/Carthage"),
+            DepKind::ZigCache => write!(f, "zig-cache"),
+            DepKind::PyBuild => write!(f, "build (Python)"),
+            DepKind::AngularCache => write!(f, ".angular/cache"),
+            DepKind::NxCache => write!(f, ".nx/cache"),
+            DepKind::CMakeBuild => write!(f, "build (CMake)"),
+            DepKind::Dist => write!(f, "dist"),
+            DepKind::Generic(name) => write!(f, "{}", name),
         }
     }
 }
 
+/// Aceita o identificador de `as_str()` ou o rótulo do `Display`.
+/// Nomes de `Generic` não são reconhecidos (vêm só de `--prune-dir`).
+impl std::str::FromStr for DepKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DepKind::KNOWN
+            .iter()
+            .find(|k| k.as_str() == s || k.to_string() == s)
+            .cloned()
+            .ok_or_else(|| {
+                let known: Vec<&str> = DepKind::KNOWN.iter().map(|k| k.as_str()).collect();
+                format!("tipo de dependência desconhecido: '{}' (válidos: {})", s, known.join(", "))
+            })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DepDir {
     pub path: PathBuf,
     /// Tamanho em bytes. Inicializado como `0` pelo scanner — valor real
     /// preenchido por `scanner::calculate_sizes()` antes de qualquer exibição.
     pub size: u64,
+    /// Quantidade de arquivos, preenchida junto com `size`.
+    pub files: u64,
     pub kind: DepKind,
 }
 
+/// Por que um projeto inativo foi preservado pela varredura.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProtectionReason {
+    /// O próprio projeto foi modificado dentro do limite de dias
+    ActiveSelf,
+    /// Está dentro de um projeto ativo
+    ActiveParent,
+    /// Contém um projeto ativo
+    ActiveChild,
+    /// Tem um irmão ativo (`--protect-active-siblings`)
+    ActiveSibling,
+    /// Dependências instaladas há pouco (`--min-age`)
+    FreshDeps,
+    /// Manifesto editado depois das dependências (`--protect-if-manifest-newer-than-deps`)
+    ManifestNewer,
+    /// Repositório git com alterações pendentes (`--protect-dirty`)
+    DirtyRepo,
+    /// Dentro de um submódulo git (`--skip-submodules`)
+    Submodule,
+    /// Tipo preservado sob o prefixo (`--keep`)
+    KeepRule,
+    /// Caminho na safe-list (`--safe-list`)
+    SafeList,
+    /// `protected = true` no `.faxina.toml` do projeto
+    ProjectConfig,
+}
+
+impl std::fmt::Display for ProtectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ProtectionReason::ActiveSelf => "ativo",
+            ProtectionReason::ActiveParent => "pai ativo",
+            ProtectionReason::ActiveChild => "filho ativo",
+            ProtectionReason::ActiveSibling => "irmão ativo",
+            ProtectionReason::FreshDeps => "dependências recentes (--min-age)",
+            ProtectionReason::ManifestNewer => "manifesto mais novo que as dependências",
+            ProtectionReason::DirtyRepo => "alterações não commitadas",
+            ProtectionReason::Submodule => "submódulo git",
+            ProtectionReason::KeepRule => "regra --keep",
+            ProtectionReason::SafeList => "safe-list",
+            ProtectionReason::ProjectConfig => ".faxina.toml",
+        };
+        write!(f, "{}", label)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StaleProject {
     pub name: String,
     pub path: PathBuf,
-    pub dep_dirs: Vec<DepDir>,
+    dep_dirs: Vec<DepDir>,
     pub last_modified: SystemTime,
+    /// Soma de `dep_dirs[..].size`, calculada na primeira chamada de
+    /// `total_size()` e descartada por `dep_dirs_mut()`.
+    total_size: OnceLock<u64>,
+    /// Preenchido só nos projetos preservados de `ScanOutcome::protected`.
+    protected: Option<ProtectionReason>,
+    /// Arquivo mais recente do projeto, o que definiu `last_modified` (`--show-newest`).
+    newest_file: Option<PathBuf>,
 }
 
 impl StaleProject {
+    pub fn new(name: String, path: PathBuf, dep_dirs: Vec<DepDir>, last_modified: SystemTime) -> Self {
+        StaleProject { name, path, dep_dirs, last_modified, total_size: OnceLock::new(), protected: None, newest_file: None }
+    }
+
+    pub fn with_protection(mut self, reason: ProtectionReason) -> Self {
+        self.protected = Some(reason);
+        self
+    }
+
+    pub fn with_newest_file(mut self, path: Option<PathBuf>) -> Self {
+        self.newest_file = path;
+        self
+    }
+
+    pub fn newest_file(&self) -> Option<&Path> {
+        self.newest_file.as_deref()
+    }
+
+    /// Motivo pelo qual a varredura preservou o projeto; `None` se ele é limpável.
+    pub fn is_protected_reason(&self) -> Option<ProtectionReason> {
+        self.protected
+    }
+
+    pub fn dep_dirs(&self) -> &[DepDir] {
+        &self.dep_dirs
+    }
+
+    /// Acesso mutável às pastas; invalida o total em cache.
+    pub fn dep_dirs_mut(&mut self) -> &mut Vec<DepDir> {
+        self.total_size = OnceLock::new();
+        &mut self.dep_dirs
+    }
+
     pub fn total_size(&self) -> u64 {
-        self.dep_dirs.iter().map(|d| d.size).sum()
+        *self.total_size.get_or_init(|| self.dep_dirs.iter().map(|d| d.size).sum())
     }
 }
 
-pub fn dir_size(path: &Path) -> u64 {
+/// Conjunto compartilhado de pares `(dev, inode)` já contabilizados.
+/// Usado para não contar duas vezes arquivos com hard links (ex: store do pnpm).
+pub type SeenInodes = Arc<Mutex<HashSet<(u64, u64)>>>;
+
+/// Paralelismo das varreduras com jwalk (`--jobs`). Vale para os três pontos
+/// que percorrem o disco: descoberta de projetos, mtime dos fontes e `dir_size`.
+#[derive(Debug, Clone, Default)]
+pub enum WalkJobs {
+    /// Pool padrão do rayon (um thread por núcleo)
+    #[default]
+    Default,
+    /// Tudo no thread atual — resultados reprodutíveis para profiling
+    Serial,
+    /// Pool próprio com N threads, compartilhado entre todas as varreduras
+    Pool(Arc<ThreadPool>),
+}
+
+impl WalkJobs {
+    pub fn new(jobs: Option<usize>) -> Self {
+        match jobs {
+            None | Some(0) => WalkJobs::Default,
+            Some(1) => WalkJobs::Serial,
+            Some(n) => match ThreadPoolBuilder::new().num_threads(n).build() {
+                Ok(pool) => WalkJobs::Pool(Arc::new(pool)),
+                Err(_) => WalkJobs::Default,
+            },
+        }
+    }
+
+    pub fn parallelism(&self) -> Parallelism {
+        let busy_timeout = std::time::Duration::from_secs(1);
+        match self {
+            WalkJobs::Default => Parallelism::RayonDefaultPool { busy_timeout },
+            WalkJobs::Serial => Parallelism::Serial,
+            WalkJobs::Pool(pool) => Parallelism::RayonExistingPool {
+                pool: pool.clone(),
+                busy_timeout: Some(busy_timeout),
+            },
+        }
+    }
+}
+
+/// Bytes e quantidade de arquivos sob uma pasta (`dir_usage`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirUsage {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// Soma o tamanho dos arquivos sob `path`. Quando `seen` é informado, cada
+/// arquivo com hard links só é somado na primeira vez que seu `(dev, inode)`
+/// aparece. No Windows a deduplicação é ignorada (semântica de inode diferente).
+pub fn dir_size(path: &Path, seen: Option<&SeenInodes>, jobs: &WalkJobs) -> u64 {
+    dir_usage(path, seen, jobs).bytes
+}
+
+/// `dir_size` contando também os arquivos, na mesma passada. Hard links
+/// deduplicados contam como um arquivo só.
+pub fn dir_usage(path: &Path, seen: Option<&SeenInodes>, jobs: &WalkJobs) -> DirUsage {
     WalkDir::new(path)
+        .parallelism(jobs.parallelism())
         .skip_hidden(false)
         .follow_links(false)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| !e.file_type().is_dir())
         .filter_map(|e| e.metadata().ok())
-        .map(|m| m.len())
-        .sum()
+        .filter(|m| seen.is_none_or(|s| first_sighting(s, m)))
+        .fold(DirUsage::default(), |acc, m| DirUsage { bytes: acc.bytes + m.len(), files: acc.files + 1 })
+}
+
+/// `dir_usage` para quando só importa saber se a pasta passa de `cap`
+/// (`--max-size`): a varredura para assim que o total passa do limite e o
+/// resultado vira só um piso (`bytes > cap`). Abaixo do limite é exato.
+/// Sem deduplicação de hard links.
+pub fn dir_usage_capped(path: &Path, cap: u64, jobs: &WalkJobs) -> DirUsage {
+    let bytes = Arc::new(AtomicU64::new(0));
+    let files = Arc::new(AtomicU64::new(0));
+    let over = Arc::new(AtomicBool::new(false));
+    let (bytes_clone, files_clone, over_clone) = (bytes.clone(), files.clone(), over.clone());
+
+    WalkDir::new(path)
+        .parallelism(jobs.parallelism())
+        .skip_hidden(false)
+        .follow_links(false)
+        .process_read_dir(move |_depth, _path, _read_dir_state, children| {
+            if over_clone.load(Ordering::Relaxed) {
+                children.clear();
+                return;
+            }
+            let (sum, count) = children
+                .iter()
+                .flatten()
+                .filter(|e| !e.file_type().is_dir())
+                .filter_map(|e| e.metadata().ok())
+                .fold((0, 0), |(sum, count), m| (sum + m.len(), count + 1));
+            files_clone.fetch_add(count, Ordering::Relaxed);
+            if bytes_clone.fetch_add(sum, Ordering::Relaxed) + sum > cap {
+                over_clone.store(true, Ordering::Relaxed);
+                children.clear();
+            }
+        })
+        .into_iter()
+        .for_each(drop);
+
+    DirUsage { bytes: bytes.load(Ordering::Relaxed), files: files.load(Ordering::Relaxed) }
+}
+
+#[cfg(unix)]
+fn first_sighting(seen: &SeenInodes, meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    // Arquivos sem hard links extras não precisam entrar no conjunto
+    if meta.nlink() <= 1 {
+        return true;
+    }
+    seen.lock().unwrap().insert((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn first_sighting(_seen: &SeenInodes, _meta: &std::fs::Metadata) -> bool {
+    true
 }
 
 #[cfg(test)]
@@ -80,6 +396,50 @@ mod tests {
     use std::fs;
     use std::io::Write;
 
+    #[test]
+    fn test_dep_kind_round_trip() {
+        for kind in DepKind::KNOWN {
+            assert_eq!(kind.as_str().parse::<DepKind>(), Ok(kind.clone()));
+            assert_eq!(kind.to_string().parse::<DepKind>(), Ok(kind.clone()));
+        }
+        assert_eq!("target".parse::<DepKind>(), Ok(DepKind::Target));
+        assert_eq!("maven-target".parse::<DepKind>(), Ok(DepKind::MavenTarget));
+
+        // Ids são únicos
+        let mut ids: Vec<&str> = DepKind::KNOWN.iter().map(|k| k.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), DepKind::KNOWN.len());
+    }
+
+    #[test]
+    fn test_dep_kind_rejects_unknown() {
+        for bad in ["", "bogus", "Target", "node-modules", "tmp"] {
+            let err = bad.parse::<DepKind>().unwrap_err();
+            assert!(err.contains("desconhecido"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_total_size_cache() {
+        let dep = |size: u64| DepDir { path: PathBuf::from("/p/node_modules"), size, files: 0, kind: DepKind::NodeModules };
+        let mut project = StaleProject::new(
+            "p".to_string(),
+            PathBuf::from("/p"),
+            vec![dep(100), dep(250)],
+            SystemTime::now(),
+        );
+
+        // Mudança antes do cache entra no total
+        project.dep_dirs_mut()[0].size = 300;
+        assert_eq!(project.total_size(), 550);
+        assert_eq!(project.total_size(), project.dep_dirs().iter().map(|d| d.size).sum::<u64>());
+
+        // Mudança depois do cache o invalida
+        project.dep_dirs_mut().push(dep(50));
+        assert_eq!(project.total_size(), 600);
+    }
+
     #[test]
     fn test_dir_size() {
         let id = std::process::id();
@@ -101,12 +461,86 @@ mod tests {
             f.write_all(&[0u8; 200]).unwrap(); // 200 bytes
         }
 
-        let size = dir_size(&temp_dir);
+        let size = dir_size(&temp_dir, None, &WalkJobs::Default);
         assert_eq!(size, 300, "Expected 300 bytes, got {}", size);
 
-        let sub_size = dir_size(&subdir);
+        let sub_size = dir_size(&subdir, None, &WalkJobs::Default);
         assert_eq!(sub_size, 200, "Expected 200 bytes for subdir, got {}", sub_size);
 
         fs::remove_dir_all(&temp_dir).unwrap();
     }
+
+    #[test]
+    fn test_dir_usage_capped_stops_past_cap() {
+        let temp_dir = std::env::temp_dir().join(format!("test_dir_usage_capped_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp_dir);
+        for i in 0..50 {
+            let dir = temp_dir.join(format!("pkg{:02}", i));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("index.js"), [0u8; 1000]).unwrap();
+        }
+        let exact = dir_usage(&temp_dir, None, &WalkJobs::Serial);
+        assert_eq!(exact, DirUsage { bytes: 50_000, files: 50 });
+
+        // Até o limite o resultado é exato
+        assert_eq!(dir_usage_capped(&temp_dir, 50_000, &WalkJobs::Serial), exact);
+        assert_eq!(dir_usage_capped(&temp_dir, u64::MAX, &WalkJobs::Default), exact);
+
+        // Passou do limite: para cedo, sem ler o resto da árvore
+        let capped = dir_usage_capped(&temp_dir, 5_000, &WalkJobs::Serial);
+        assert!(capped.bytes > 5_000);
+        assert!(capped.files < 10, "walked {} files", capped.files);
+        assert!(dir_usage_capped(&temp_dir, 5_000, &WalkJobs::Default).bytes > 5_000);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dir_usage_counts_files() {
+        let temp_dir = std::env::temp_dir().join(format!("test_dir_usage_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("pkg/lib")).unwrap();
+        fs::write(temp_dir.join("package.json"), [0u8; 10]).unwrap();
+        fs::write(temp_dir.join("pkg/index.js"), [0u8; 20]).unwrap();
+        fs::write(temp_dir.join("pkg/lib/a.js"), [0u8; 30]).unwrap();
+        fs::write(temp_dir.join("pkg/lib/empty.js"), []).unwrap();
+
+        // Pastas não contam como arquivos; arquivos vazios contam
+        assert_eq!(dir_usage(&temp_dir, None, &WalkJobs::Default), DirUsage { bytes: 60, files: 4 });
+        assert_eq!(dir_usage(&temp_dir.join("pkg/lib"), None, &WalkJobs::Default), DirUsage { bytes: 30, files: 2 });
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dir_size_dedup_hardlinks() {
+        let id = std::process::id();
+        let temp_dir = std::env::temp_dir().join(format!("test_dir_size_hardlinks_{}", id));
+        let _ = fs::remove_dir_all(&temp_dir);
+        let store = temp_dir.join("store");
+        let proj_a = temp_dir.join("a/node_modules");
+        let proj_b = temp_dir.join("b/node_modules");
+        fs::create_dir_all(&store).unwrap();
+        fs::create_dir_all(&proj_a).unwrap();
+        fs::create_dir_all(&proj_b).unwrap();
+
+        fs::write(store.join("pkg.js"), [0u8; 400]).unwrap();
+        fs::hard_link(store.join("pkg.js"), proj_a.join("pkg.js")).unwrap();
+        fs::hard_link(store.join("pkg.js"), proj_b.join("pkg.js")).unwrap();
+        fs::write(proj_b.join("own.js"), [0u8; 50]).unwrap();
+
+        // Sem dedup cada link conta o tamanho cheio
+        assert_eq!(dir_size(&temp_dir, None, &WalkJobs::Default), 1250);
+
+        let seen: SeenInodes = Arc::default();
+        assert_eq!(dir_size(&temp_dir, Some(&seen), &WalkJobs::Default), 450);
+
+        // O conjunto é compartilhado entre chamadas: o segundo projeto só soma o que é seu
+        let seen: SeenInodes = Arc::default();
+        assert_eq!(dir_size(&proj_a, Some(&seen), &WalkJobs::Default), 400);
+        assert_eq!(dir_size(&proj_b, Some(&seen), &WalkJobs::Default), 50);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
 }
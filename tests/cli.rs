@@ -100,9 +100,109 @@ fn test_stats_flag() {
         .arg("--stats")
         .assert()
         .success()
-        .stdout(predicate::str::contains("📊 Estatísticas"))
+        .stdout(predicate::str::contains("[stats] Estatísticas"))
         .stdout(predicate::str::contains("node_modules"))
         .stdout(predicate::str::contains("1 projetos"));
+
+    // Nada inativo: a seção dos preservados ainda explica o porquê
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("30")
+        .arg("--stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Projetos preservados"))
+        .stdout(predicate::str::contains("Nenhum projeto inativo"));
+}
+
+#[test]
+fn test_with_stats_keeps_listing_and_cleanup() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let node_proj = root.join("node-proj");
+    fs::create_dir_all(node_proj.join("node_modules")).unwrap();
+    fs::write(node_proj.join("package.json"), "{}").unwrap();
+    fs::write(node_proj.join("node_modules/lib.js"), "content").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("0")
+        .arg("--with-stats")
+        .arg("--dry-run")
+        .arg("--yes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("node-proj"))
+        .stdout(predicate::str::contains("[stats] Estatísticas"))
+        .stdout(predicate::str::contains("1 projetos"))
+        .stdout(predicate::str::contains("Modo dry-run"));
+}
+
+#[test]
+fn test_aggressive_includes_python_caches() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let cache = root.join("scripts/__pycache__");
+    fs::create_dir_all(&cache).unwrap();
+    fs::write(cache.join("util.cpython-312.pyc"), "bytecode").unwrap();
+
+    let run = |aggressive: bool| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+        cmd.arg(root).arg("--days").arg("0").arg("--dry-run").arg("--yes");
+        if aggressive {
+            cmd.arg("--aggressive");
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    assert!(!run(false).contains("__pycache__"));
+    let out = run(true);
+    assert!(out.contains("scripts/__pycache__"), "{}", out);
+    assert!(out.contains("Modo dry-run"));
+    assert!(cache.exists());
+}
+
+#[test]
+fn test_ascii_replaces_kind_icons() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let node_proj = root.join("web");
+    fs::create_dir_all(node_proj.join("node_modules")).unwrap();
+    fs::write(node_proj.join("package.json"), "{}").unwrap();
+    let rust_proj = root.join("cli");
+    fs::create_dir_all(rust_proj.join("target")).unwrap();
+    fs::write(rust_proj.join("Cargo.toml"), "").unwrap();
+
+    // FAXINA_ICONS=emoji desliga a detecção automática (a saída aqui é um
+    // pipe), então só o --ascii pode trocar os ícones
+    let run = |ascii: bool| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+        cmd.arg(root).arg("--days").arg("0").arg("--with-stats").arg("--dry-run").arg("--yes");
+        if ascii {
+            cmd.arg("--ascii");
+        }
+        let output = cmd.env("FAXINA_ICONS", "emoji").output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let is_emoji = |c: char| {
+        c >= '\u{1F000}'
+            || matches!(c, '\u{2022}' | '\u{2190}'..='\u{21FF}' | '\u{2300}'..='\u{23FF}' | '\u{25A0}'..='\u{27BF}' | '\u{FE0F}')
+    };
+
+    let stdout = run(false);
+    assert!(stdout.contains("📦") && stdout.contains("🦀"), "{}", stdout);
+    assert!(!stdout.contains("[node]"));
+
+    let stdout = run(true);
+    assert!(stdout.contains("[node]") && stdout.contains("[rust]"), "{}", stdout);
+    // Nem os ícones dos tipos nem os decorativos (🧹, 🔍, ✗...) sobram
+    assert!(!stdout.chars().any(is_emoji), "emoji in ASCII output: {}", stdout);
 }
 
 #[test]
@@ -156,3 +256,842 @@ fn test_invalid_days_argument() {
         .failure()
         .stderr(predicate::str::contains("invalid value 'not_a_number'"));
 }
+
+#[test]
+fn test_log_file_records_clean_events() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("projetos");
+
+    let node_proj = root.join("node-proj");
+    fs::create_dir_all(node_proj.join("node_modules")).unwrap();
+    fs::write(node_proj.join("package.json"), "{}").unwrap();
+
+    let log_path = temp.path().join("faxina.log");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(&root)
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--yes")
+        .arg("--log-file").arg(&log_path)
+        .assert()
+        .success()
+        // Registros de debug vão só para o arquivo, não para o terminal
+        .stderr(predicate::str::contains("removendo").not());
+
+    let log = fs::read_to_string(&log_path).unwrap();
+    assert!(log.contains("removendo"), "log file should contain clean events: {}", log);
+    assert!(log.contains("node_modules"));
+}
+
+#[test]
+fn test_quiet_if_empty_prints_nothing() {
+    let temp = TempDir::new().unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+
+    let output = cmd.arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--quiet-if-empty")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty(), "stdout: {}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn test_quiet_if_empty_still_reports_projects() {
+    let temp = TempDir::new().unwrap();
+    let node_proj = temp.path().join("node-proj");
+    fs::create_dir_all(node_proj.join("node_modules")).unwrap();
+    fs::write(node_proj.join("package.json"), "{}").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--yes")
+        .arg("--quiet-if-empty")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Faxina CLI"))
+        .stdout(predicate::str::contains("1 projetos inativos encontrados"));
+}
+
+#[test]
+fn test_list_all_includes_active_projects() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    // Projeto recém-criado: ativo para --days 30
+    let active = root.join("active-proj");
+    fs::create_dir_all(active.join("node_modules")).unwrap();
+    fs::write(active.join("package.json"), "{}").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("30")
+        .arg("--list-all")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 projetos detectados"))
+        .stdout(predicate::str::contains("active-proj"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("30")
+        .arg("--dry-run")
+        .arg("--yes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("active-proj").not());
+
+    assert!(active.join("node_modules").exists());
+}
+
+#[test]
+fn test_summary_json_stderr() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let node_proj = root.join("node-proj");
+    fs::create_dir_all(node_proj.join("node_modules")).unwrap();
+    fs::write(node_proj.join("package.json"), "{}").unwrap();
+    fs::write(node_proj.join("node_modules/index.js"), "data").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(root)
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .arg("--summary-json-stderr")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Limpeza concluída"), "Human summary stays on stdout");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = stderr.lines().rev().find(|l| l.starts_with('{')).expect("JSON line on stderr");
+    let summary: serde_json::Value = serde_json::from_str(line).unwrap();
+    assert_eq!(summary["projects"], 1);
+    assert_eq!(summary["dirs"], 1);
+    assert_eq!(summary["freed"], 4);
+    assert_eq!(summary["errors"], 0);
+}
+
+#[test]
+fn test_dry_run_verbose_shows_sizes() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let proj = root.join("web");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::create_dir_all(proj.join(".next")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    fs::write(proj.join("node_modules/lib.js"), vec![b'x'; 2048]).unwrap();
+    fs::write(proj.join(".next/page.js"), vec![b'x'; 1024]).unwrap();
+
+    let nm = proj.join("node_modules").canonicalize().unwrap();
+    let next = proj.join(".next").canonicalize().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(root)
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--verbose")
+        .arg("--yes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("-> {} 2.00 KB", nm.display())))
+        .stdout(predicate::str::contains(format!("-> {} 1.00 KB", next.display())))
+        .stdout(predicate::str::contains("Subtotal: 3.00 KB"));
+
+    assert!(nm.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scan_path_with_dangling_symlink_ancestor() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let proj = root.join("projects/app");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    std::os::unix::fs::symlink(root.join("missing"), root.join("gone")).unwrap();
+
+    // `gone/..` não pode ser canonicalizado (symlink quebrado), mas o caminho léxico existe
+    let weird = root.join("gone/../projects");
+    assert!(weird.canonicalize().is_err());
+
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&weird)
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--yes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 projetos inativos encontrados"))
+        .stdout(predicate::str::contains("app"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinked_root_needs_follow_symlinks() {
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("real/app");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    let link = temp.path().join("link");
+    std::os::unix::fs::symlink(temp.path().join("real"), &link).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&link)
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow-symlinks"));
+
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&link)
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--follow-symlinks")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 projetos inativos encontrados"));
+}
+
+#[test]
+fn test_json_schema_and_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg("--json-schema")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for key in ["schema_version", "dry_run", "projects", "summary"] {
+        assert!(schema["properties"].get(key).is_some(), "schema missing {}", key);
+    }
+
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("node-proj");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--yes")
+        .arg("--format").arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["schema_version"], schema["properties"]["schema_version"]["const"]);
+    assert_eq!(report["projects"][0]["name"], "node-proj");
+    assert_eq!(report["summary"]["dirs"], 1);
+}
+
+#[test]
+fn test_json_streams_one_status_per_dir() {
+    let temp = TempDir::new().unwrap();
+    for name in ["api", "web"] {
+        let proj = temp.path().join(name);
+        fs::create_dir_all(proj.join("node_modules/pkg")).unwrap();
+        fs::write(proj.join("node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .arg("--format").arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+
+    let mut paths: Vec<&str> = lines[..2].iter().map(|e| e["path"].as_str().unwrap()).collect();
+    paths.sort();
+    for event in &lines[..2] {
+        assert_eq!(event["status"], "removed");
+        assert_eq!(event["kind"], "node_modules");
+        assert!(event.get("error").is_none());
+    }
+    assert_eq!(
+        paths,
+        vec![
+            temp.path().join("api/node_modules").to_str().unwrap(),
+            temp.path().join("web/node_modules").to_str().unwrap(),
+        ]
+    );
+
+    let summary = &lines[2]["summary"];
+    assert_eq!(summary["dirs"], 2);
+    assert!(!temp.path().join("api/node_modules").exists());
+}
+
+#[test]
+fn test_quiet_with_json_prints_only_json() {
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("node-proj");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    fs::write(proj.join("node_modules/blob"), vec![b'x'; 2048]).unwrap();
+
+    // Dry-run sem --yes: nem o aviso de dry-run nem o número do --quiet
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .arg("--quiet")
+        .arg("--format").arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["summary"]["dirs"], 1);
+
+    // Limpeza real: só as linhas JSON, sem a linha "2.00 KB" do --quiet
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .arg("--format").arg("json")
+        .arg("--quiet")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<serde_json::Value> = stdout.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["status"], "removed");
+    assert_eq!(lines[1]["summary"]["dirs"], 1);
+    assert!(!stdout.contains("2.00 KB"));
+
+    // Nada sobra para limpar (nenhum inativo, --keep-recent, --max-size): ainda um documento vazio
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("node_modules/blob"), vec![b'x'; 2048]).unwrap();
+    for filter in [&["--days", "9999"][..], &["--days", "0", "--keep-recent", "1"], &["--days", "0", "--max-size", "1K"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+            .arg(temp.path())
+            .args(filter)
+            .arg("--dry-run")
+            .arg("--quiet")
+            .arg("--format").arg("json")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", filter);
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .unwrap_or_else(|e| panic!("{:?}: {}", filter, e));
+        assert_eq!(report["summary"]["dirs"], 0, "{:?}", filter);
+    }
+}
+
+#[test]
+fn test_print0_lists_paths_without_cleaning() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let node = root.join("node-proj");
+    fs::create_dir_all(node.join("node_modules")).unwrap();
+    fs::write(node.join("package.json"), "{}").unwrap();
+    let rust = root.join("rust-proj");
+    fs::create_dir_all(rust.join("target")).unwrap();
+    fs::write(rust.join("Cargo.toml"), "[package]").unwrap();
+
+    // Mesmo com --yes: é só listagem
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&root)
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .arg("--print0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with('\0'));
+    let mut paths: Vec<&str> = stdout.trim_end_matches('\0').split('\0').collect();
+    paths.sort();
+    let node_modules = node.join("node_modules");
+    let target = rust.join("target");
+    assert_eq!(paths, vec![node_modules.to_str().unwrap(), target.to_str().unwrap()]);
+
+    assert!(node_modules.exists());
+    assert!(target.exists());
+
+    // Só listar não mede nada; --max-results precisa dos tamanhos
+    fs::write(node_modules.join("index.js"), "x").unwrap();
+    let sized_files = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+            .arg(&root)
+            .args(["--days", "0", "--print0", "--probe"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        stderr.lines().find(|l| l.contains("Tamanhos:")).unwrap_or_default().to_string()
+    };
+    assert!(sized_files(&[]).contains("Tamanhos:   0 arquivos"), "{}", sized_files(&[]));
+    assert!(sized_files(&["--max-results", "5"]).contains("Tamanhos:   1 arquivos"), "{}", sized_files(&["--max-results", "5"]));
+}
+
+#[test]
+fn test_size_only_prints_sizes() {
+    let temp = TempDir::new().unwrap();
+    let a = temp.path().join("a");
+    let b = temp.path().join("b/nested");
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    fs::write(a.join("file"), vec![0u8; 1024]).unwrap();
+    fs::write(b.join("file"), vec![0u8; 2048]).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg("--size-only")
+        .arg(&a)
+        .arg(temp.path().join("b"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.00 KB"))
+        .stdout(predicate::str::contains("2.00 KB"))
+        .stdout(predicate::str::contains("Faxina CLI").not());
+}
+
+#[test]
+fn test_cleanup_recap_before_removal() {
+    let temp = TempDir::new().unwrap();
+    for name in ["a", "b"] {
+        let proj = temp.path().join(name);
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        fs::write(proj.join("node_modules/index.js"), vec![b'x'; 1024]).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let recap = stdout.find("Removendo 2 pastas de 2 projetos (2.00 KB)").expect("recap line");
+    let summary = stdout.find("Limpeza concluída").unwrap();
+    assert!(recap < summary);
+
+    // --quiet suprime o resumo
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("c");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .arg("--quiet")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removendo").not());
+}
+
+#[test]
+fn test_min_dirs_filters_projects() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    let solo = root.join("solo");
+    fs::create_dir_all(solo.join("node_modules")).unwrap();
+    fs::write(solo.join("package.json"), "{}").unwrap();
+
+    let duo = root.join("duo");
+    fs::create_dir_all(duo.join("node_modules")).unwrap();
+    fs::create_dir_all(duo.join(".next")).unwrap();
+    fs::write(duo.join("package.json"), "{}").unwrap();
+
+    let trio = root.join("trio");
+    for dir in ["zig-cache", ".zig-cache", "zig-out"] {
+        fs::create_dir_all(trio.join(dir)).unwrap();
+    }
+    fs::write(trio.join("build.zig"), "").unwrap();
+
+    let run = |min: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+            .arg(root)
+            .arg("--days").arg("0")
+            .arg("--dry-run")
+            .arg("--yes")
+            .arg("--min-dirs").arg(min)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let out = run("2");
+    assert!(out.contains("2 projetos inativos encontrados"));
+    assert!(out.contains("duo") && out.contains("trio") && !out.contains("solo"));
+
+    let out = run("3");
+    assert!(out.contains("1 projetos inativos encontrados"));
+    assert!(out.contains("trio") && !out.contains("duo"));
+}
+
+#[test]
+fn test_progress_off_has_no_bar_artifacts() {
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    fs::write(proj.join("node_modules/index.js"), "x").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--yes")
+        .arg("--verbose")
+        .arg("--progress").arg("off")
+        .env("CLICOLOR_FORCE", "0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    for stream in [&output.stdout, &output.stderr] {
+        let text = String::from_utf8_lossy(stream);
+        assert!(!text.contains('\x1b'), "escape sequence in: {:?}", text);
+        assert!(!text.contains('\r'));
+        assert!(!text.contains('⠋'));
+    }
+    // Com a barra oculta, as linhas do --verbose continuam saindo
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("-> {}", proj.join("node_modules").display())));
+    assert!(!proj.join("node_modules").exists());
+}
+
+/// Fora de um terminal o indicatif já esconde as barras sozinho; num
+/// pseudo-terminal (`script` do util-linux) só o `--progress off` as tira.
+#[cfg(target_os = "linux")]
+#[test]
+fn test_progress_off_hides_bars_on_a_terminal() {
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+
+    let run_in_pty = |extra: &str| {
+        let cmdline = format!(
+            "{} {} --days 0 --dry-run --yes {}",
+            env!("CARGO_BIN_EXE_faxina-cli"),
+            temp.path().display(),
+            extra
+        );
+        Command::new("script")
+            .args(["-qec", &cmdline, "/dev/null"])
+            .env("NO_COLOR", "1")
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+    };
+
+    // Sem `script` no sistema não há como simular o terminal
+    let Some(with_bars) = run_in_pty("") else {
+        return;
+    };
+    assert!(with_bars.contains('\x1b'), "expected bar escape sequences: {:?}", with_bars);
+
+    let without_bars = run_in_pty("--progress off").unwrap();
+    assert!(!without_bars.contains('\x1b'), "escape sequence in: {:?}", without_bars);
+    assert!(without_bars.contains("Simulação concluída"));
+}
+
+#[test]
+fn test_max_size_skips_oversized_projects() {
+    let temp = TempDir::new().unwrap();
+    for (name, size) in [("huge", 8 * 1024), ("small", 100)] {
+        let proj = temp.path().join(name);
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        fs::write(proj.join("node_modules/blob"), vec![b'x'; size]).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(temp.path())
+        .arg("--days").arg("0")
+        .arg("--max-size").arg("4K")
+        .arg("--yes")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let section = stdout.find("1 projetos ignorados por passarem de 4.00 KB").expect("oversized section");
+    assert!(stdout[section..].contains("huge"));
+    assert!(stdout.contains("1 pastas removidas"));
+
+    assert!(temp.path().join("huge/node_modules").exists());
+    assert!(!temp.path().join("small/node_modules").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ensure_free_aborts_when_goal_unreachable() {
+    let temp = TempDir::new().unwrap();
+    let proj = temp.path().join("app");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+    fs::write(proj.join("node_modules/blob"), vec![b'x'; 4096]).unwrap();
+
+    // Nenhum disco tem 1000 TB livres
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&proj)
+        .arg("--days").arg("0")
+        .arg("--ensure-free").arg("1000TB")
+        .arg("--yes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("A limpeza não alcança a meta"))
+        .stderr(predicate::str::contains("+ 0 B recuperáveis").not())
+        .stderr(predicate::str::contains("--ensure-free"));
+    assert!(proj.join("node_modules").exists());
+
+    // Mover para o mesmo disco não libera nada
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&proj)
+        .arg("--days").arg("0")
+        .arg("--ensure-free").arg("1000TB")
+        .arg("--move-to").arg(temp.path().join("archive"))
+        .arg("--yes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("+ 0 B recuperáveis"));
+    assert!(proj.join("node_modules").exists());
+
+    // Meta alcançável: limpa normalmente
+    Command::new(env!("CARGO_BIN_EXE_faxina-cli"))
+        .arg(&proj)
+        .arg("--days").arg("0")
+        .arg("--ensure-free").arg("1KB")
+        .arg("--yes")
+        .assert()
+        .success();
+    assert!(!proj.join("node_modules").exists());
+}
+
+#[test]
+fn test_double_verbose_explains_protection() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    // Monorepo ativo com um pacote antigo dentro: o pacote é protegido pelo pai
+    let mono = root.join("mono");
+    fs::create_dir_all(mono.join("node_modules")).unwrap();
+    fs::write(mono.join("package.json"), "{}").unwrap();
+    let old = mono.join("packages/old");
+    fs::create_dir_all(old.join("node_modules")).unwrap();
+    fs::write(old.join("package.json"), "{}").unwrap();
+    let sixty_days_ago = filetime::FileTime::from_system_time(
+        std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 24 * 3600),
+    );
+    filetime::set_file_mtime(old.join("package.json"), sixty_days_ago).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("30")
+        .arg("--dry-run")
+        .arg("-vv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 projetos avaliados"))
+        .stdout(predicate::str::contains("motivo: pai ativo"))
+        // A leitura do projeto ativo para no primeiro arquivo recente
+        .stdout(predicate::str::contains("ou depois"));
+
+    // Só -v: classificação sem o motivo
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("30")
+        .arg("--dry-run")
+        .arg("-v")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("preservado"))
+        .stdout(predicate::str::contains("motivo:").not());
+}
+
+#[test]
+fn test_root_glob_scans_each_match() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    for (dir, project) in [("ws-a", "api"), ("ws-b", "web"), ("other", "legacy")] {
+        let proj = root.join(dir).join(project);
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+    }
+    // Arquivos que casam com o padrão são ignorados
+    fs::write(root.join("ws-notes"), "").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg("--root-glob").arg(root.join("ws-*").to_str().unwrap())
+        .arg("--days").arg("0")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 projetos inativos encontrados"))
+        .stdout(predicate::str::contains("api"))
+        .stdout(predicate::str::contains("web"))
+        .stdout(predicate::str::contains("legacy").not());
+}
+
+#[test]
+fn test_root_glob_nested_matches_keep_outer_protection() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    // `ws` ativo protege o `ws-old` de dentro, que também casa com o padrão
+    let outer = root.join("ws");
+    fs::create_dir_all(outer.join("node_modules")).unwrap();
+    fs::write(outer.join("package.json"), "{}").unwrap();
+    let inner = outer.join("ws-old");
+    fs::create_dir_all(inner.join("node_modules")).unwrap();
+    fs::write(inner.join("package.json"), "{}").unwrap();
+    let sixty_days_ago = filetime::FileTime::from_system_time(
+        std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 24 * 3600),
+    );
+    filetime::set_file_mtime(inner.join("package.json"), sixty_days_ago).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg("--root-glob").arg(root.join("**").join("ws*").to_str().unwrap())
+        .arg("--days").arg("30")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nenhum projeto inativo"));
+}
+
+#[test]
+fn test_root_glob_rejects_path() {
+    let temp = TempDir::new().unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(temp.path())
+        .arg("--root-glob").arg(temp.path().join("*").to_str().unwrap())
+        .arg("--dry-run")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--root-glob"));
+
+    // --parallel-roots só faz sentido com várias raízes
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(temp.path())
+        .arg("--parallel-roots").arg("4")
+        .arg("--dry-run")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--root-glob"));
+}
+
+#[test]
+fn test_confirm_policy_per_kind() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("code");
+
+    let node_proj = root.join("web");
+    fs::create_dir_all(node_proj.join("node_modules")).unwrap();
+    fs::write(node_proj.join("package.json"), "{}").unwrap();
+    let rust_proj = root.join("engine");
+    fs::create_dir_all(rust_proj.join("target")).unwrap();
+    fs::write(rust_proj.join("Cargo.toml"), "[package]").unwrap();
+
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "[confirm]\nnode_modules = \"always\"\ntarget = \"ask\"\n").unwrap();
+
+    // Sem --yes e sem terminal a pergunta é recusada: só o "always" é limpo
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(&root)
+        .arg("--days").arg("0")
+        .arg("--config").arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("política \"always\""));
+
+    assert!(!node_proj.join("node_modules").exists());
+    assert!(rust_proj.join("target").exists());
+
+    // "never" tira o tipo da lista mesmo com --yes
+    fs::write(&config, "[confirm]\ntarget = \"never\"\n").unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(&root)
+        .arg("--days").arg("0")
+        .arg("--config").arg(&config)
+        .arg("--yes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 pastas mantidas pela política \"never\""));
+
+    assert!(rust_proj.join("target").exists());
+}
+
+#[test]
+fn test_max_results_limits_listing_and_cleanup() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+
+    for (name, size) in [("big", 30_000), ("medium", 20_000), ("small", 10_000)] {
+        let proj = root.join(name);
+        fs::create_dir_all(proj.join("node_modules")).unwrap();
+        fs::write(proj.join("package.json"), "{}").unwrap();
+        fs::write(proj.join("node_modules/blob"), vec![0u8; size]).unwrap();
+    }
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(root)
+        .arg("--days").arg("0")
+        .arg("--max-results").arg("2")
+        .arg("--yes")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("os 2 maiores de 3 projetos"))
+        .stdout(predicate::str::contains("2 projetos inativos encontrados"))
+        .stdout(predicate::str::contains("2 pastas removidas"));
+
+    assert!(!root.join("big/node_modules").exists());
+    assert!(!root.join("medium/node_modules").exists());
+    assert!(root.join("small/node_modules").exists());
+}
+
+#[test]
+fn test_profile_fails_closed_on_unreadable_config() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("code");
+    let proj = root.join("web");
+    fs::create_dir_all(proj.join("node_modules")).unwrap();
+    fs::write(proj.join("package.json"), "{}").unwrap();
+
+    // Um diretório no lugar do arquivo: existe, mas a leitura falha (I/O)
+    let config = temp.path().join("config.toml");
+    fs::create_dir_all(&config).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_faxina-cli"));
+    cmd.arg(&root)
+        .arg("--days").arg("0")
+        .arg("--config").arg(&config)
+        .arg("--profile").arg("work")
+        .arg("--yes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Perfil 'work' indisponível"));
+
+    assert!(proj.join("node_modules").exists());
+}